@@ -0,0 +1,714 @@
+use std::{collections::HashMap, fmt, hash::BuildHasherDefault, thread};
+
+use crate::{
+    card::{Card, CardValue, Suit},
+    chart::{CHART_TABLES, ChartAction, ChartTable, StrategyChart},
+    game::Game,
+    player::PlayerAction,
+    probability::{
+        DealerProbabilities, ShoeComposition, ShoeHasher, ShoeMap, blackjack_probability,
+        dealer_probabilities,
+    },
+    rules::TableRules,
+    strategy::BasicStrategy,
+};
+
+// expected net result of every possible first decision, in initial bets, knowing the dealer
+// has no blackjack since the dealer peeks before anyone plays
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActionValues {
+    pub stand: f64,
+    pub hit: Option<f64>,
+    pub double: Option<f64>,
+    pub split: Option<f64>,
+    pub surrender: Option<f64>,
+}
+
+impl ActionValues {
+    pub fn get(&self, action: PlayerAction) -> Option<f64> {
+        match action {
+            PlayerAction::Stand => Some(self.stand),
+            PlayerAction::Hit => self.hit,
+            PlayerAction::Double => self.double,
+            PlayerAction::Split => self.split,
+            PlayerAction::Surrender => self.surrender,
+        }
+    }
+
+    // available actions in a fixed order, stand first
+    pub fn values(&self) -> impl Iterator<Item = (PlayerAction, f64)> + '_ {
+        [
+            PlayerAction::Stand,
+            PlayerAction::Hit,
+            PlayerAction::Double,
+            PlayerAction::Split,
+            PlayerAction::Surrender,
+        ]
+        .into_iter()
+        .filter_map(|action| self.get(action).map(|value| (action, value)))
+    }
+
+    // the first action of the fixed order wins a tie
+    pub fn best(&self) -> (PlayerAction, f64) {
+        self.values().fold(
+            (PlayerAction::Stand, self.stand),
+            |best, (action, value)| {
+                if value > best.1 {
+                    (action, value)
+                } else {
+                    best
+                }
+            },
+        )
+    }
+}
+
+impl fmt::Display for ActionValues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (best, _) = self.best();
+        for (action, value) in self.values() {
+            let mark = if action == best { "  best" } else { "" };
+            writeln!(f, "{:<10} {value:+.4}{mark}", action.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+// exact values of standing, hitting, doubling and surrendering for a hand on its first
+// decision. the shoe holds the cards the player cannot see, neither the hand nor the
+// upcard. splitting assumes no resplit and plays each hand as if the other had not
+// drawn any card, split aces receive a single card.
+pub fn analyze(
+    hand: &[u8],
+    upcard: u8,
+    shoe: &ShoeComposition,
+    rules: &TableRules,
+) -> ActionValues {
+    Solver::new(upcard, rules).analyze(hand, shoe)
+}
+
+// the chart of the best first decisions from a full shoe, e.g. to regenerate the basic
+// strategy when a rule changes. hard totals are valued as two different cards, T6 for 16.
+// every upcard is worked out on its own thread.
+pub fn optimal_chart(rules: &TableRules) -> StrategyChart {
+    let mut columns: Vec<_> = thread::scope(|scope| {
+        let columns: Vec<_> = (2..=11)
+            .map(|upcard| scope.spawn(move || optimal_column(upcard, rules)))
+            .collect();
+        columns
+            .into_iter()
+            .map(|column| column.join().unwrap().into_iter())
+            .collect()
+    });
+    StrategyChart::from_fn(|_, _, dealer| columns[dealer as usize - 2].next().unwrap())
+}
+
+// the cells of every table against the upcard, in the order of CHART_TABLES and their rows
+pub fn optimal_column(upcard: u8, rules: &TableRules) -> Vec<ChartAction> {
+    // values already worked out against the upcard are shared by every row
+    let mut solver = Solver::new(upcard, rules);
+    let mut cells = Vec::new();
+    for table in CHART_TABLES {
+        for row in table.rows() {
+            let hand = parse_hand(row).unwrap();
+            let mut shoe = ShoeComposition::from_decks(rules.decks);
+            for value in hand.iter().chain([&upcard]) {
+                shoe.remove(*value);
+            }
+            let mut values = solver.analyze(&hand, &shoe);
+            if table != ChartTable::Pairs {
+                values.split = None;
+            }
+            cells.push(chart_action(&values));
+        }
+    }
+    cells
+}
+
+// the best action, with the best of hitting, standing or splitting when it cannot be played
+pub fn chart_action(values: &ActionValues) -> ChartAction {
+    let fallback = [PlayerAction::Hit, PlayerAction::Split]
+        .into_iter()
+        .filter_map(|action| values.get(action).map(|value| (action, value)))
+        .fold(
+            (PlayerAction::Stand, values.stand),
+            |best, (action, value)| {
+                if value > best.1 {
+                    (action, value)
+                } else {
+                    best
+                }
+            },
+        );
+    match (values.best().0, fallback.0) {
+        (PlayerAction::Double, _) if values.hit > Some(values.stand) => ChartAction::DoubleOrHit,
+        (PlayerAction::Double, _) => ChartAction::DoubleOrStand,
+        (PlayerAction::Surrender, PlayerAction::Hit) => ChartAction::SurrenderOrHit,
+        (PlayerAction::Surrender, PlayerAction::Split) => ChartAction::SurrenderOrSplit,
+        (PlayerAction::Surrender, _) => ChartAction::SurrenderOrStand,
+        (_, PlayerAction::Hit) => ChartAction::Hit,
+        (_, PlayerAction::Split) => ChartAction::Split,
+        _ => ChartAction::Stand,
+    }
+}
+
+// values of the actions legal for a hand of the game, against the cards its player cannot see
+pub fn analyze_hand(game: &Game, player_index: usize) -> ActionValues {
+    let legal = game.legal_actions(player_index);
+    if legal.is_empty() {
+        panic!("The hand has no decision to make");
+    }
+    let hand: Vec<u8> = game.players[player_index]
+        .hand
+        .iter()
+        .map(|card| card.get_card_value())
+        .collect();
+    let upcard = game.get_dealer_hand()[0].get_card_value();
+    let shoe = ShoeComposition::unseen(game);
+    let values = analyze(&hand, upcard, &shoe, &game.rules);
+    let only_legal = |action, value: Option<f64>| value.filter(|_| legal.contains(&action));
+    ActionValues {
+        stand: values.stand,
+        hit: only_legal(PlayerAction::Hit, values.hit),
+        double: only_legal(PlayerAction::Double, values.double),
+        split: only_legal(PlayerAction::Split, values.split),
+        surrender: only_legal(PlayerAction::Surrender, values.surrender),
+    }
+}
+
+// the best play for the exact cards of a hand and of the shoe, next to the total dependent
+// basic strategy play
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Advice {
+    pub action: PlayerAction,
+    pub value: f64,
+    pub values: ActionValues,
+    pub basic: PlayerAction,
+}
+
+impl Advice {
+    fn new(values: ActionValues, basic: PlayerAction) -> Self {
+        let (action, value) = values.best();
+        Advice {
+            action,
+            value,
+            values,
+            basic,
+        }
+    }
+
+    pub fn disagrees(&self) -> bool {
+        self.action != self.basic
+    }
+
+    // what playing the basic strategy costs on this hand, 0 when both agree
+    pub fn gain(&self) -> f64 {
+        self.value - self.values.get(self.basic).unwrap_or(self.value)
+    }
+}
+
+impl fmt::Display for Advice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:+.4})", self.action, self.value)?;
+        if self.disagrees() {
+            write!(f, ", basic strategy: {}", self.basic)?;
+            if let Some(value) = self.values.get(self.basic) {
+                write!(f, " ({value:+.4})")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// composition dependent advice for a hand of the game, from the cards its player can see
+pub fn advise(game: &Game, player_index: usize) -> Advice {
+    Advice::new(
+        analyze_hand(game, player_index),
+        BasicStrategy::recommend_hand(game, player_index),
+    )
+}
+
+// a two card hand against an upcard where the exact cards change the basic strategy play
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompositionException {
+    pub hand: [u8; 2],
+    pub upcard: u8,
+    pub advice: Advice,
+}
+
+impl fmt::Display for CompositionException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} against {}: {}",
+            card_name(self.hand[0]),
+            card_name(self.hand[1]),
+            card_name(self.upcard),
+            self.advice
+        )
+    }
+}
+
+// every first decision dealt from a full shoe where the best play is not the basic strategy
+// one, by upcard then hand. they matter most with few decks, where each card seen moves the
+// odds. every upcard is worked out on its own thread.
+pub fn composition_exceptions(rules: &TableRules) -> Vec<CompositionException> {
+    thread::scope(|scope| {
+        let columns: Vec<_> = (2..=11)
+            .map(|upcard| scope.spawn(move || upcard_exceptions(upcard, rules)))
+            .collect();
+        columns
+            .into_iter()
+            .flat_map(|column| column.join().unwrap())
+            .collect()
+    })
+}
+
+fn upcard_exceptions(upcard: u8, rules: &TableRules) -> Vec<CompositionException> {
+    let mut solver = Solver::new(upcard, rules);
+    let mut exceptions = Vec::new();
+    for second in 2..=11 {
+        for first in second..=11 {
+            if first + second == 21 {
+                continue;
+            }
+            let mut shoe = ShoeComposition::from_decks(rules.decks);
+            for value in [first, second, upcard] {
+                shoe.remove(value);
+            }
+            let values = solver.analyze(&[first, second], &shoe);
+            let basic = BasicStrategy::recommend(
+                &[value_card(first), value_card(second)],
+                &value_card(upcard),
+                rules,
+            );
+            let advice = Advice::new(values, basic);
+            if advice.disagrees() {
+                exceptions.push(CompositionException {
+                    hand: [first, second],
+                    upcard,
+                    advice,
+                });
+            }
+        }
+    }
+    exceptions
+}
+
+// a card of the value, a ten for 10
+fn value_card(value: u8) -> Card {
+    let value = match value {
+        11 => CardValue::Ace,
+        _ => CardValue::Number(value),
+    };
+    Card::new(value, Suit::Spades)
+}
+
+// a hand as cards like T6, A7 or 88 (T for any ten valued card), or a hard total from 4 to 20
+// made of two different cards, e.g. 16 is T6 and 9 is 54
+pub fn parse_hand(hand: &str) -> Result<Vec<u8>, String> {
+    if let Ok(total @ 4..=20) = hand.parse::<u8>() {
+        return Ok(match total {
+            12.. => vec![10, total - 10],
+            _ => vec![total / 2 + 1, total - total / 2 - 1],
+        });
+    }
+    let cards: Result<Vec<u8>, String> = hand
+        .chars()
+        .filter(|c| *c != ',')
+        .map(|c| parse_card(&c.to_string()))
+        .collect();
+    match cards {
+        Ok(cards) if cards.len() >= 2 => Ok(cards),
+        _ => Err(format!("Invalid hand: {hand}")),
+    }
+}
+
+// value of a card rank: 2 to 9, T, 10, J, Q or K for 10 and A for 11
+pub fn parse_card(card: &str) -> Result<u8, String> {
+    match card.to_uppercase().as_str() {
+        "A" => Ok(11),
+        "T" | "10" | "J" | "Q" | "K" => Ok(10),
+        digit => match digit.parse() {
+            Ok(value @ 2..=9) => Ok(value),
+            _ => Err(format!("Invalid card: {card}")),
+        },
+    }
+}
+
+// rank of a card value as parse_card reads it, T for every ten valued card
+pub fn card_name(value: u8) -> &'static str {
+    ["2", "3", "4", "5", "6", "7", "8", "9", "T", "A"][value as usize - 2]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Hand {
+    // every ace counted as 1
+    hard: u8,
+    ace: bool,
+}
+
+impl Hand {
+    fn from_values(values: &[u8], ace: bool) -> Self {
+        values
+            .iter()
+            .fold(Hand { hard: 0, ace }, |hand, value| hand.add(*value))
+    }
+
+    fn add(self, value: u8) -> Self {
+        let ace = value == 11;
+        Hand {
+            hard: self.hard + if ace { 1 } else { value },
+            ace: self.ace || ace,
+        }
+    }
+
+    fn value(self) -> u8 {
+        if self.ace && self.hard + 10 <= 21 {
+            self.hard + 10
+        } else {
+            self.hard
+        }
+    }
+}
+
+// values are weighted by the chance the dealer has no blackjack and divided by it at the end.
+// the memos hold whole shoes, so they stay valid from one hand to the next against the upcard.
+struct Solver<'a> {
+    upcard: u8,
+    rules: &'a TableRules,
+    dealer: ShoeMap<DealerProbabilities>,
+    // best of hitting and standing, the shoe left tells which cards the hand drew
+    hands: HashMap<(ShoeComposition, Hand), f64, BuildHasherDefault<ShoeHasher>>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(upcard: u8, rules: &'a TableRules) -> Self {
+        Solver {
+            upcard,
+            rules,
+            dealer: ShoeMap::default(),
+            hands: HashMap::default(),
+        }
+    }
+
+    fn analyze(&mut self, hand: &[u8], shoe: &ShoeComposition) -> ActionValues {
+        if hand.len() < 2 {
+            panic!("A hand has at least two cards");
+        }
+        let start = Hand::from_values(hand, false);
+        let natural = hand.len() == 2 && start.value() == 21;
+        if natural {
+            let (numerator, denominator) = self.rules.blackjack_payout;
+            return ActionValues {
+                stand: numerator as f64 / denominator as f64,
+                hit: None,
+                double: None,
+                split: None,
+                surrender: None,
+            };
+        }
+        let no_blackjack = 1.0 - blackjack_probability(shoe, self.upcard);
+        let first_decision = hand.len() == 2;
+        let pair = first_decision && hand[0] == hand[1] && self.rules.max_hands >= 2;
+        let mut shoe = *shoe;
+        let values = ActionValues {
+            stand: self.stand(&shoe, start),
+            hit: (start.value() < 21).then(|| self.hit(&mut shoe, start)),
+            double: first_decision.then(|| self.double(&mut shoe, start)),
+            split: pair.then(|| self.split(&mut shoe, hand[0])),
+            surrender: (first_decision && self.rules.surrender).then_some(-0.5 * no_blackjack),
+        };
+        ActionValues {
+            stand: values.stand / no_blackjack,
+            hit: values.hit.map(|value| value / no_blackjack),
+            double: values.double.map(|value| value / no_blackjack),
+            split: values.split.map(|value| value / no_blackjack),
+            surrender: values.surrender.map(|value| value / no_blackjack),
+        }
+    }
+
+    fn stand(&mut self, shoe: &ShoeComposition, hand: Hand) -> f64 {
+        let value = hand.value();
+        if value > 21 {
+            return -(1.0 - blackjack_probability(shoe, self.upcard));
+        }
+        let dealer = self.dealer_probabilities(shoe);
+        let mut result = dealer.bust;
+        for total in 17..=21 {
+            result += dealer.get_total(total) * (value.cmp(&total) as i8) as f64;
+        }
+        result
+    }
+
+    fn dealer_probabilities(&mut self, shoe: &ShoeComposition) -> DealerProbabilities {
+        *self
+            .dealer
+            .entry(*shoe)
+            .or_insert_with(|| dealer_probabilities(shoe, self.upcard, self.rules))
+    }
+
+    // one card, then the best of hitting and standing
+    fn hit(&mut self, shoe: &mut ShoeComposition, hand: Hand) -> f64 {
+        self.draw(shoe, |solver, shoe, card| solver.play(shoe, hand.add(card)))
+    }
+
+    fn double(&mut self, shoe: &mut ShoeComposition, hand: Hand) -> f64 {
+        2.0 * self.draw(shoe, |solver, shoe, card| {
+            solver.stand(shoe, hand.add(card))
+        })
+    }
+
+    fn play(&mut self, shoe: &mut ShoeComposition, hand: Hand) -> f64 {
+        if hand.value() >= 21 {
+            return self.stand(shoe, hand);
+        }
+        if let Some(value) = self.hands.get(&(*shoe, hand)) {
+            return *value;
+        }
+        let value = self.stand(shoe, hand).max(self.hit(shoe, hand));
+        self.hands.insert((*shoe, hand), value);
+        value
+    }
+
+    fn split(&mut self, shoe: &mut ShoeComposition, card: u8) -> f64 {
+        let single = Hand::from_values(&[card], false);
+        let das = self.rules.double_after_split;
+        let hand = self.draw(shoe, |solver, shoe, drawn| {
+            let hand = single.add(drawn);
+            if card == 11 {
+                return solver.stand(shoe, hand);
+            }
+            let mut best = solver.play(shoe, hand);
+            if das && hand.value() < 21 {
+                best = best.max(solver.double(shoe, hand));
+            }
+            best
+        });
+        2.0 * hand
+    }
+
+    // expected value over the next card of the shoe
+    fn draw<F: FnMut(&mut Self, &mut ShoeComposition, u8) -> f64>(
+        &mut self,
+        shoe: &mut ShoeComposition,
+        mut next: F,
+    ) -> f64 {
+        let mut result = 0.0;
+        for (card, p) in shoe.draws().collect::<Vec<_>>() {
+            shoe.remove(card);
+            result += p * next(self, shoe, card);
+            shoe.add(card);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shoe(decks: u8, seen: &[u8]) -> ShoeComposition {
+        let mut shoe = ShoeComposition::from_decks(decks);
+        for value in seen {
+            shoe.remove(*value);
+        }
+        shoe
+    }
+
+    fn values(hand: &[u8], upcard: u8, rules: &TableRules) -> ActionValues {
+        let shoe = shoe(rules.decks, &[hand, &[upcard]].concat());
+        analyze(hand, upcard, &shoe, rules)
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} is not {expected} ± {tolerance}"
+        );
+    }
+
+    #[test]
+    fn hands_and_cards_are_parsed() {
+        assert_eq!(parse_hand("16"), Ok(vec![10, 6]));
+        assert_eq!(parse_hand("9"), Ok(vec![5, 4]));
+        assert_eq!(parse_hand("A7"), Ok(vec![11, 7]));
+        assert_eq!(parse_hand("88"), Ok(vec![8, 8]));
+        assert_eq!(parse_hand("t,6,2"), Ok(vec![10, 6, 2]));
+        assert_eq!(parse_hand("Z"), Err("Invalid hand: Z".to_string()));
+        assert_eq!(parse_card("k"), Ok(10));
+        assert_eq!(parse_card("1"), Err("Invalid card: 1".to_string()));
+    }
+
+    // close to the published six deck S17 values for T6 against a ten
+    #[test]
+    fn sixteen_against_a_ten() {
+        let values = values(&[10, 6], 10, &TableRules::default());
+        assert_close(values.stand, -0.5404, 0.002);
+        assert_close(values.hit.unwrap(), -0.5350, 0.002);
+        assert_eq!(values.surrender, Some(-0.5));
+        assert_eq!(values.split, None);
+        assert_eq!(values.best().0, PlayerAction::Surrender);
+    }
+
+    #[test]
+    fn eleven_is_doubled_and_eights_are_split() {
+        let rules = TableRules::default();
+        let eleven = values(&[6, 5], 6, &rules);
+        assert_eq!(eleven.best().0, PlayerAction::Double);
+        assert_close(eleven.double.unwrap(), 2.0 * eleven.hit.unwrap(), 0.2);
+
+        let rules = TableRules {
+            decks: 1,
+            ..Default::default()
+        };
+        let eights = values(&[8, 8], 7, &rules);
+        assert_eq!(eights.best().0, PlayerAction::Split);
+    }
+
+    #[test]
+    fn natural_is_paid_without_a_decision() {
+        let values = values(&[11, 10], 9, &TableRules::default());
+        assert_eq!(values.stand, 1.5);
+        assert_eq!(values.best(), (PlayerAction::Stand, 1.5));
+        assert_eq!(values.values().count(), 1);
+    }
+
+    #[test]
+    fn standing_matches_the_dealer_probabilities() {
+        let rules = TableRules::default();
+        let seen = shoe(6, &[10, 10, 6]);
+        let dealer = dealer_probabilities(&seen, 6, &rules);
+        let values = analyze(&[10, 10], 6, &seen, &rules);
+        let expected =
+            dealer.bust + dealer.get_total(17) + dealer.get_total(18) + dealer.get_total(19)
+                - dealer.get_total(21);
+        assert_close(values.stand, expected, 1e-12);
+        assert!(values.split.unwrap() < values.stand);
+    }
+
+    #[test]
+    fn only_legal_actions_of_a_game_hand_are_valued() {
+        let mut game = Game::with_seed(1, TableRules::default(), 9);
+        game.new_round();
+        game.place_bet(0, 10);
+        game.deal_initial_cards();
+        while let Some(hand) = game.current_hand() {
+            let values = analyze_hand(&game, hand);
+            for (action, _) in values.values() {
+                assert!(game.legal_actions(hand).contains(&action), "{action}");
+            }
+            game.apply_action(hand, PlayerAction::Hit);
+        }
+    }
+
+    #[test]
+    fn cells_fall_back_on_the_best_allowed_action() {
+        let values = |stand, hit, double, split, surrender| ActionValues {
+            stand,
+            hit: Some(hit),
+            double,
+            split,
+            surrender,
+        };
+        let cases = [
+            (values(-0.2, -0.1, None, None, None), ChartAction::Hit),
+            (
+                values(0.1, -0.1, Some(-0.3), None, None),
+                ChartAction::Stand,
+            ),
+            (
+                values(0.1, 0.2, Some(0.3), None, None),
+                ChartAction::DoubleOrHit,
+            ),
+            (
+                values(0.2, 0.1, Some(0.3), None, None),
+                ChartAction::DoubleOrStand,
+            ),
+            (
+                values(-0.6, -0.4, None, Some(-0.3), Some(-0.5)),
+                ChartAction::Split,
+            ),
+            (
+                values(-0.6, -0.55, None, None, Some(-0.5)),
+                ChartAction::SurrenderOrHit,
+            ),
+            (
+                values(-0.52, -0.55, None, None, Some(-0.5)),
+                ChartAction::SurrenderOrStand,
+            ),
+            (
+                values(-0.6, -0.6, None, Some(-0.55), Some(-0.5)),
+                ChartAction::SurrenderOrSplit,
+            ),
+        ];
+        for (values, expected) in cases {
+            assert_eq!(chart_action(&values), expected, "{values:?}");
+        }
+    }
+
+    #[test]
+    fn optimal_column_against_a_ten() {
+        let rules = TableRules {
+            decks: 1,
+            ..Default::default()
+        };
+        let column = optimal_column(10, &rules);
+        assert_eq!(column.len(), 16 + 8 + 10);
+        let cell = |table: ChartTable, row: &str| {
+            let offset: usize = CHART_TABLES
+                .iter()
+                .take_while(|t| **t != table)
+                .map(|t| t.rows().len())
+                .sum();
+            column[offset + table.rows().iter().position(|r| *r == row).unwrap()]
+        };
+        assert_eq!(cell(ChartTable::Hard, "10"), ChartAction::Hit);
+        assert_eq!(cell(ChartTable::Hard, "11"), ChartAction::DoubleOrHit);
+        assert_eq!(cell(ChartTable::Hard, "16"), ChartAction::SurrenderOrHit);
+        assert_eq!(cell(ChartTable::Hard, "17"), ChartAction::Stand);
+        assert_eq!(cell(ChartTable::Soft, "A7"), ChartAction::Hit);
+        assert_eq!(cell(ChartTable::Pairs, "88"), ChartAction::Split);
+        assert_eq!(cell(ChartTable::Pairs, "TT"), ChartAction::Stand);
+        assert_eq!(cell(ChartTable::Pairs, "AA"), ChartAction::Split);
+    }
+
+    #[test]
+    fn ten_two_against_a_four_is_hit() {
+        let rules = TableRules {
+            decks: 1,
+            ..Default::default()
+        };
+        let exceptions = upcard_exceptions(4, &rules);
+        let ten_two = exceptions.iter().find(|e| e.hand == [10, 2]).unwrap();
+        assert_eq!(ten_two.advice.action, PlayerAction::Hit);
+        assert_eq!(ten_two.advice.basic, PlayerAction::Stand);
+        assert_eq!(
+            ten_two.to_string(),
+            "T2 against 4: hit (-0.1940), basic strategy: stand (-0.2118)"
+        );
+        // the other twelves are stood on like the basic strategy says
+        assert!(!exceptions.iter().any(|e| e.hand == [9, 3]));
+        for exception in &exceptions {
+            assert!(exception.advice.disagrees());
+            assert!(exception.advice.gain() > 0.0);
+        }
+    }
+
+    #[test]
+    fn advice_is_never_worse_than_the_basic_strategy() {
+        for seed in 0..20 {
+            let mut game = Game::with_seed(1, TableRules::default(), seed);
+            game.new_round();
+            game.place_bet(0, 10);
+            game.deal_initial_cards();
+            while let Some(hand) = game.current_hand() {
+                let advice = advise(&game, hand);
+                assert!(game.legal_actions(hand).contains(&advice.action));
+                assert!(advice.gain() >= 0.0);
+                assert_eq!(advice.disagrees(), advice.action != advice.basic);
+                game.apply_action(hand, advice.basic);
+            }
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+use crate::{
+    card::Card,
+    chart::StrategyChart,
+    events::GameEvent,
+    game::Game,
+    player::{Player, PlayerAction},
+    rules::TableRules,
+    strategy::BasicStrategy,
+};
+
+// what a seat can see of the table: every hand, the dealer upcard, never the hole card
+pub struct TableView<'a> {
+    game: &'a Game,
+    seat: usize,
+    hand: Option<usize>,
+}
+
+impl<'a> TableView<'a> {
+    // hand is None while betting
+    pub fn new(game: &'a Game, seat: usize, hand: Option<usize>) -> Self {
+        TableView { game, seat, hand }
+    }
+
+    pub fn get_seat(&self) -> usize {
+        self.seat
+    }
+
+    pub fn get_bankroll(&self) -> i64 {
+        self.game.get_bankroll(self.seat)
+    }
+
+    pub fn get_rules(&self) -> &TableRules {
+        &self.game.rules
+    }
+
+    pub fn get_round(&self) -> u64 {
+        self.game.get_round()
+    }
+
+    // index of the hand to play among all the hands of the table
+    pub fn get_hand_index(&self) -> Option<usize> {
+        self.hand
+    }
+
+    pub fn get_hand(&self) -> &'a [Card] {
+        match self.hand {
+            Some(hand) => &self.game.players[hand].hand,
+            None => &[],
+        }
+    }
+
+    pub fn get_player(&self) -> Option<&'a Player> {
+        self.hand.map(|hand| &self.game.players[hand])
+    }
+
+    pub fn get_players(&self) -> &'a [Player] {
+        &self.game.players
+    }
+
+    pub fn get_dealer_upcard(&self) -> Option<&'a Card> {
+        self.game.get_dealer_hand().first()
+    }
+
+    // only how many, the burn cards among the discards were never shown
+    pub fn cards_discarded(&self) -> usize {
+        self.game.discards.len()
+    }
+
+    pub fn cards_remaining(&self) -> usize {
+        self.game.deck.cards.len()
+    }
+
+    pub fn legal_actions(&self) -> Vec<PlayerAction> {
+        match self.hand {
+            Some(hand) => self.game.legal_actions(hand),
+            None => Vec::new(),
+        }
+    }
+}
+
+// decides for a seat: its bet before the deal and then every action of its hands
+pub trait PlayerStrategy {
+    // None once the seat has no chips left to bet
+    fn bet(&mut self, view: &TableView) -> Option<u32>;
+
+    // only called with at least one legal action, the action played must be one of them
+    fn action(&mut self, view: &TableView) -> PlayerAction;
+
+    // only asked when the insurance is offered, before any hand plays
+    fn insure(&mut self, _view: &TableView) -> bool {
+        false
+    }
+
+    // every event of the game, e.g. to count cards. the hole card is dealt face down and
+    // only shows once revealed.
+    fn on_event(&mut self, _event: &GameEvent) {}
+}
+
+pub const STRATEGIES: [&str; 4] = ["basic", "never-bust", "mimic-dealer", "random"];
+
+// the reference strategy with this name, betting the same amount every round
+pub fn strategy_by_name(
+    name: &str,
+    rules: &TableRules,
+    bet: u32,
+    seed: u64,
+) -> Option<Box<dyn PlayerStrategy>> {
+    let strategy: Box<dyn PlayerStrategy> = match name {
+        "basic" => Box::new(ChartStrategy::basic(rules, bet)),
+        "never-bust" => Box::new(NeverBust { bet }),
+        "mimic-dealer" => Box::new(MimicDealer { bet }),
+        "random" => Box::new(RandomAction::new(bet, seed)),
+        _ => return None,
+    };
+    Some(strategy)
+}
+
+// the whole bankroll once it is lower than the bet, None once it is empty
+pub(crate) fn flat_bet(bet: u32, view: &TableView) -> Option<u32> {
+    let bet = bet.min(view.get_bankroll().clamp(0, u32::MAX as i64) as u32);
+    (bet > 0).then_some(bet)
+}
+
+// plays a strategy chart, the basic strategy or one loaded from a file
+pub struct ChartStrategy {
+    pub chart: StrategyChart,
+    pub bet: u32,
+}
+
+impl ChartStrategy {
+    pub fn basic(rules: &TableRules, bet: u32) -> Self {
+        ChartStrategy {
+            chart: BasicStrategy::chart(rules),
+            bet,
+        }
+    }
+}
+
+impl PlayerStrategy for ChartStrategy {
+    fn bet(&mut self, view: &TableView) -> Option<u32> {
+        flat_bet(self.bet, view)
+    }
+
+    fn action(&mut self, view: &TableView) -> PlayerAction {
+        self.chart.recommend(
+            view.get_hand(),
+            view.get_dealer_upcard().unwrap(),
+            &view.legal_actions(),
+        )
+    }
+}
+
+// stands on 12 and more, a hit can never bust the hand
+pub struct NeverBust {
+    pub bet: u32,
+}
+
+impl PlayerStrategy for NeverBust {
+    fn bet(&mut self, view: &TableView) -> Option<u32> {
+        flat_bet(self.bet, view)
+    }
+
+    fn action(&mut self, view: &TableView) -> PlayerAction {
+        match view.get_player() {
+            Some(player) if player.hand_value() < 12 => PlayerAction::Hit,
+            _ => PlayerAction::Stand,
+        }
+    }
+}
+
+// follows the dealer rules: hits below 17, and soft 17 when the dealer does
+pub struct MimicDealer {
+    pub bet: u32,
+}
+
+impl PlayerStrategy for MimicDealer {
+    fn bet(&mut self, view: &TableView) -> Option<u32> {
+        flat_bet(self.bet, view)
+    }
+
+    fn action(&mut self, view: &TableView) -> PlayerAction {
+        let Some(player) = view.get_player() else {
+            return PlayerAction::Stand;
+        };
+        let value = player.hand_value();
+        if value < 17 || (value == 17 && player.is_soft() && view.get_rules().dealer_hits_soft_17) {
+            PlayerAction::Hit
+        } else {
+            PlayerAction::Stand
+        }
+    }
+}
+
+// any legal action, from a seeded generator so that games can be replayed
+pub struct RandomAction {
+    pub bet: u32,
+    rng: StdRng,
+}
+
+impl RandomAction {
+    pub fn new(bet: u32, seed: u64) -> Self {
+        RandomAction {
+            bet,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl PlayerStrategy for RandomAction {
+    fn bet(&mut self, view: &TableView) -> Option<u32> {
+        flat_bet(self.bet, view)
+    }
+
+    fn action(&mut self, view: &TableView) -> PlayerAction {
+        *view
+            .legal_actions()
+            .choose(&mut self.rng)
+            .unwrap_or(&PlayerAction::Stand)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{CardValue, Suit};
+
+    fn card(value: CardValue) -> Card {
+        Card::new(value, Suit::Diamonds)
+    }
+
+    // one seat holding the hand against the dealer cards, as if just dealt
+    fn table(rules: TableRules, hand: Vec<CardValue>, dealer: Vec<CardValue>) -> Game {
+        let mut game = Game::with_seed(1, rules, 1);
+        game.new_round();
+        game.place_bet(0, 10);
+        game.players[0].hand = hand.into_iter().map(card).collect();
+        game.dealer.hand = dealer.into_iter().map(card).collect();
+        game
+    }
+
+    fn decide(strategy: &mut dyn PlayerStrategy, game: &Game) -> PlayerAction {
+        strategy.action(&TableView::new(game, 0, Some(0)))
+    }
+
+    #[test]
+    fn view_shows_the_hand_and_the_dealer_upcard() {
+        let mut game = Game::with_seed(2, TableRules::default(), 4);
+        game.new_round();
+        let view = TableView::new(&game, 1, None);
+        assert!(view.get_hand().is_empty());
+        assert!(view.legal_actions().is_empty());
+        assert_eq!(view.get_dealer_upcard(), None);
+        assert_eq!(view.cards_discarded(), game.rules.burn_cards as usize);
+
+        game.place_bet(0, 10);
+        game.place_bet(1, 10);
+        game.deal_initial_cards();
+        let view = TableView::new(&game, 1, Some(1));
+        assert_eq!(view.get_seat(), 1);
+        assert_eq!(view.get_hand(), game.get_player_hand(1).as_slice());
+        assert_eq!(view.get_dealer_upcard(), Some(&game.get_dealer_hand()[0]));
+        assert_eq!(view.legal_actions(), game.legal_actions(1));
+        assert_eq!(view.get_bankroll(), 990);
+        assert_eq!(view.get_players().len(), 2);
+    }
+
+    #[test]
+    fn basic_strategy_follows_the_chart() {
+        let game = table(
+            TableRules::default(),
+            vec![CardValue::Number(6), CardValue::Number(5)],
+            vec![CardValue::Number(6), CardValue::Number(10)],
+        );
+        let mut strategy = ChartStrategy::basic(&game.rules, 10);
+        assert_eq!(decide(&mut strategy, &game), PlayerAction::Double);
+    }
+
+    #[test]
+    fn never_bust_stands_on_twelve() {
+        let mut strategy = NeverBust { bet: 10 };
+        let twelve = vec![CardValue::Number(10), CardValue::Number(2)];
+        let eleven = vec![CardValue::Number(9), CardValue::Number(2)];
+        let dealer = vec![CardValue::Ace, CardValue::Number(5)];
+        let game = table(TableRules::default(), twelve, dealer.clone());
+        assert_eq!(decide(&mut strategy, &game), PlayerAction::Stand);
+        let game = table(TableRules::default(), eleven, dealer);
+        assert_eq!(decide(&mut strategy, &game), PlayerAction::Hit);
+    }
+
+    #[test]
+    fn mimic_dealer_hits_soft_17_only_when_the_dealer_does() {
+        let mut strategy = MimicDealer { bet: 10 };
+        let soft_17 = vec![CardValue::Ace, CardValue::Number(6)];
+        let dealer = vec![CardValue::Number(9), CardValue::Number(5)];
+        let game = table(TableRules::default(), soft_17.clone(), dealer.clone());
+        assert_eq!(decide(&mut strategy, &game), PlayerAction::Stand);
+        let h17 = TableRules {
+            dealer_hits_soft_17: true,
+            ..Default::default()
+        };
+        let game = table(h17, soft_17, dealer.clone());
+        assert_eq!(decide(&mut strategy, &game), PlayerAction::Hit);
+        let game = table(
+            TableRules::default(),
+            vec![CardValue::Number(10), CardValue::Number(6)],
+            dealer,
+        );
+        assert_eq!(decide(&mut strategy, &game), PlayerAction::Hit);
+    }
+
+    #[test]
+    fn random_action_is_legal_and_seeded() {
+        let game = table(
+            TableRules::default(),
+            vec![CardValue::Number(8), CardValue::Number(8)],
+            vec![CardValue::Number(9), CardValue::Number(5)],
+        );
+        let legal = game.legal_actions(0);
+        let mut first = RandomAction::new(10, 3);
+        let mut second = RandomAction::new(10, 3);
+        for _ in 0..20 {
+            let action = decide(&mut first, &game);
+            assert!(legal.contains(&action));
+            assert_eq!(decide(&mut second, &game), action);
+        }
+    }
+
+    #[test]
+    fn bet_never_exceeds_the_bankroll() {
+        let mut game = Game::with_seed(1, TableRules::default(), 1);
+        game.set_bankroll(0, 4);
+        let mut strategy = NeverBust { bet: 10 };
+        assert_eq!(strategy.bet(&TableView::new(&game, 0, None)), Some(4));
+        game.set_bankroll(0, 0);
+        assert_eq!(strategy.bet(&TableView::new(&game, 0, None)), None);
+    }
+
+    #[test]
+    fn reference_strategies_by_name() {
+        for name in STRATEGIES {
+            assert!(strategy_by_name(name, &TableRules::default(), 10, 1).is_some());
+        }
+        assert!(strategy_by_name("martingale", &TableRules::default(), 10, 1).is_none());
+    }
+}
@@ -0,0 +1,355 @@
+use crate::{
+    autoplay::{PlayerStrategy, TableView, flat_bet},
+    counting::{CountTracker, CountingSystem},
+    events::{GameEvent, GameObserver},
+    player::PlayerAction,
+};
+
+// advantage gained for every true count of Hi-Lo over a shoe starting at the house edge
+const BASE_EDGE: f64 = -0.005;
+const EDGE_PER_TRUE_COUNT: f64 = 0.005;
+
+// variance of a hand of blackjack, in squared initial bets
+const HAND_VARIANCE: f64 = 1.3;
+
+// rounds won in a row before Paroli takes its winnings back to the unit
+const PAROLI_WINS: u32 = 3;
+
+// what a seat knows when it bets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BetContext {
+    pub true_count: f64,
+    pub running_count: f64,
+    // bankroll of the session, it is not stopped once ruined
+    pub bankroll: i64,
+    // net result of the previous round, None before the first one
+    pub last_net: Option<i64>,
+}
+
+// decides the bet of every round, at least 1
+pub trait BettingStrategy {
+    fn bet(&mut self, context: &BetContext) -> u32;
+}
+
+pub const BETTING_STRATEGIES: [&str; 6] =
+    ["flat", "ramp", "kelly", "martingale", "paroli", "dalembert"];
+
+// betting between unit and unit * spread, the table maximum of the progressions
+pub fn betting_by_name(name: &str, unit: u32, spread: u32) -> Option<Box<dyn BettingStrategy>> {
+    let max = unit.saturating_mul(spread);
+    let betting: Box<dyn BettingStrategy> = match name {
+        "flat" => Box::new(FlatBet { unit }),
+        "ramp" => Box::new(BetRamp { unit, spread }),
+        "kelly" => Box::new(KellyBet {
+            unit,
+            max,
+            fraction: 1.0,
+        }),
+        "martingale" => Box::new(Martingale::new(unit, max)),
+        "paroli" => Box::new(Paroli::new(unit)),
+        "dalembert" => Box::new(DAlembert::new(unit, max)),
+        _ => return None,
+    };
+    Some(betting)
+}
+
+pub struct FlatBet {
+    pub unit: u32,
+}
+
+impl BettingStrategy for FlatBet {
+    fn bet(&mut self, _context: &BetContext) -> u32 {
+        self.unit
+    }
+}
+
+// one unit up to a true count of 1, then one more unit for every true count up to the spread
+pub struct BetRamp {
+    pub unit: u32,
+    pub spread: u32,
+}
+
+impl BettingStrategy for BetRamp {
+    fn bet(&mut self, context: &BetContext) -> u32 {
+        let units = (context.true_count.floor() - 1.0).clamp(1.0, self.spread.max(1) as f64);
+        self.unit.saturating_mul(units as u32)
+    }
+}
+
+// the fraction of the bankroll that maximizes its growth for the edge at the true count,
+// the unit when the house has the edge. the edge is calibrated for the true counts of Hi-Lo,
+// other systems count on other scales.
+pub struct KellyBet {
+    pub unit: u32,
+    pub max: u32,
+    // 1 for full Kelly, 0.5 for half Kelly
+    pub fraction: f64,
+}
+
+impl BettingStrategy for KellyBet {
+    fn bet(&mut self, context: &BetContext) -> u32 {
+        let edge = BASE_EDGE + EDGE_PER_TRUE_COUNT * context.true_count;
+        let kelly = self.fraction * context.bankroll.max(0) as f64 * edge / HAND_VARIANCE;
+        kelly.clamp(self.unit as f64, self.max.max(self.unit) as f64) as u32
+    }
+}
+
+// doubles the bet after every loss to win the unit back, until the table maximum turns a
+// losing streak into a big loss
+pub struct Martingale {
+    pub unit: u32,
+    pub max: u32,
+    bet: u32,
+}
+
+impl Martingale {
+    pub fn new(unit: u32, max: u32) -> Self {
+        Martingale {
+            unit,
+            max,
+            bet: unit,
+        }
+    }
+}
+
+impl BettingStrategy for Martingale {
+    fn bet(&mut self, context: &BetContext) -> u32 {
+        self.bet = match context.last_net {
+            Some(net) if net < 0 => match self.bet.saturating_mul(2) {
+                doubled if doubled <= self.max => doubled,
+                _ => self.unit,
+            },
+            Some(net) if net > 0 => self.unit,
+            _ => self.bet,
+        };
+        self.bet
+    }
+}
+
+// lets the winnings ride for PAROLI_WINS rounds and starts again from the unit after a loss
+pub struct Paroli {
+    pub unit: u32,
+    wins: u32,
+}
+
+impl Paroli {
+    pub fn new(unit: u32) -> Self {
+        Paroli { unit, wins: 0 }
+    }
+}
+
+impl BettingStrategy for Paroli {
+    fn bet(&mut self, context: &BetContext) -> u32 {
+        match context.last_net {
+            Some(net) if net > 0 => self.wins = (self.wins + 1) % PAROLI_WINS,
+            Some(net) if net < 0 => self.wins = 0,
+            _ => {}
+        }
+        self.unit.saturating_mul(1 << self.wins)
+    }
+}
+
+// one unit more after a loss and one less after a win
+pub struct DAlembert {
+    pub unit: u32,
+    pub max: u32,
+    units: u32,
+}
+
+impl DAlembert {
+    pub fn new(unit: u32, max: u32) -> Self {
+        DAlembert {
+            unit,
+            max,
+            units: 1,
+        }
+    }
+}
+
+impl BettingStrategy for DAlembert {
+    fn bet(&mut self, context: &BetContext) -> u32 {
+        let max_units = (self.max / self.unit.max(1)).max(1);
+        match context.last_net {
+            Some(net) if net < 0 => self.units = (self.units + 1).min(max_units),
+            Some(net) if net > 0 => self.units = self.units.saturating_sub(1).max(1),
+            _ => {}
+        }
+        self.unit.saturating_mul(self.units)
+    }
+}
+
+// plays with a strategy and bets with another, counting the cards of the table on the way.
+// the bankroll of the session follows the results of the seat from a starting amount, so the
+// chips of the seat in the game may be far larger, as in a simulation.
+pub struct BettingPlayer {
+    play: Box<dyn PlayerStrategy>,
+    betting: Box<dyn BettingStrategy>,
+    tracker: CountTracker,
+    bankroll: i64,
+    // bankroll of the seat in the game at the previous bet
+    last_bankroll: Option<i64>,
+}
+
+impl BettingPlayer {
+    pub fn new(
+        play: Box<dyn PlayerStrategy>,
+        betting: Box<dyn BettingStrategy>,
+        system: Box<dyn CountingSystem>,
+        nb_decks: u8,
+        bankroll: i64,
+    ) -> Self {
+        BettingPlayer {
+            play,
+            betting,
+            tracker: CountTracker::new(system, nb_decks),
+            bankroll,
+            last_bankroll: None,
+        }
+    }
+
+    pub fn get_bankroll(&self) -> i64 {
+        self.bankroll
+    }
+
+    pub fn get_tracker(&self) -> &CountTracker {
+        &self.tracker
+    }
+}
+
+impl PlayerStrategy for BettingPlayer {
+    fn bet(&mut self, view: &TableView) -> Option<u32> {
+        let seat_bankroll = view.get_bankroll();
+        let last_net = self.last_bankroll.map(|last| seat_bankroll - last);
+        self.bankroll += last_net.unwrap_or(0);
+        self.last_bankroll = Some(seat_bankroll);
+        let context = BetContext {
+            true_count: self.tracker.get_true_count(),
+            running_count: self.tracker.get_running_count(),
+            bankroll: self.bankroll,
+            last_net,
+        };
+        flat_bet(self.betting.bet(&context).max(1), view)
+    }
+
+    fn action(&mut self, view: &TableView) -> PlayerAction {
+        self.play.action(view)
+    }
+
+    fn insure(&mut self, view: &TableView) -> bool {
+        self.play.insure(view)
+    }
+
+    fn on_event(&mut self, event: &GameEvent) {
+        self.tracker.on_event(event);
+        self.play.on_event(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        autoplay::ChartStrategy,
+        counting::HI_LO,
+        game::{DEFAULT_BANKROLL, Game},
+        rules::TableRules,
+    };
+
+    fn context(true_count: f64, last_net: Option<i64>) -> BetContext {
+        BetContext {
+            true_count,
+            running_count: true_count,
+            bankroll: 10_000,
+            last_net,
+        }
+    }
+
+    fn bets(betting: &mut dyn BettingStrategy, nets: &[i64]) -> Vec<u32> {
+        let mut bets = vec![betting.bet(&context(0.0, None))];
+        for net in nets {
+            bets.push(betting.bet(&context(0.0, Some(*net))));
+        }
+        bets
+    }
+
+    #[test]
+    fn every_name_builds_a_strategy() {
+        for name in BETTING_STRATEGIES {
+            assert!(betting_by_name(name, 10, 8).is_some(), "{name}");
+        }
+        assert!(betting_by_name("labouchere", 10, 8).is_none());
+    }
+
+    #[test]
+    fn ramp_and_kelly_follow_the_true_count() {
+        let mut ramp = BetRamp {
+            unit: 10,
+            spread: 8,
+        };
+        let ramp_bets: Vec<u32> = [-3.0, 1.5, 2.0, 4.7, 20.0]
+            .into_iter()
+            .map(|tc| ramp.bet(&context(tc, None)))
+            .collect();
+        assert_eq!(ramp_bets, vec![10, 10, 10, 30, 80]);
+
+        let mut kelly = KellyBet {
+            unit: 10,
+            max: 500,
+            fraction: 1.0,
+        };
+        assert_eq!(kelly.bet(&context(-2.0, None)), 10);
+        // 1.5% of 10000 over the variance
+        assert_eq!(kelly.bet(&context(4.0, None)), 115);
+        assert_eq!(kelly.bet(&context(40.0, None)), 500);
+    }
+
+    #[test]
+    fn progressions_follow_the_results() {
+        let mut martingale = Martingale::new(10, 40);
+        assert_eq!(
+            bets(&mut martingale, &[-10, -20, 0, -40, 10, 15]),
+            vec![10, 20, 40, 40, 10, 10, 10]
+        );
+        let mut paroli = Paroli::new(10);
+        assert_eq!(
+            bets(&mut paroli, &[10, 20, 40, 10, -20]),
+            vec![10, 20, 40, 10, 20, 10]
+        );
+        let mut dalembert = DAlembert::new(10, 30);
+        assert_eq!(
+            bets(&mut dalembert, &[-10, -20, -30, 30, 0, 20, 10]),
+            vec![10, 20, 30, 30, 20, 20, 10, 10]
+        );
+    }
+
+    #[test]
+    fn betting_player_follows_its_session_bankroll() {
+        let rules = TableRules::default();
+        let mut game = Game::with_seed(1, rules.clone(), 4);
+        let mut player = BettingPlayer::new(
+            Box::new(ChartStrategy::basic(&rules, 10)),
+            betting_by_name("martingale", 10, 8).unwrap(),
+            Box::new(HI_LO),
+            rules.decks,
+            500,
+        );
+        for _ in 0..30 {
+            game.new_round();
+            let bet = player.bet(&TableView::new(&game, 0, None)).unwrap();
+            assert!((10..=80).contains(&bet));
+            game.place_bet(0, bet);
+            game.deal_initial_cards();
+            while let Some(hand) = game.current_hand() {
+                let action = player.action(&TableView::new(&game, 0, Some(hand)));
+                game.apply_action(hand, action);
+            }
+            game.dealer_play();
+            game.settle();
+        }
+        player.bet(&TableView::new(&game, 0, None));
+        assert_eq!(
+            player.get_bankroll(),
+            500 + game.get_bankroll(0) - DEFAULT_BANKROLL
+        );
+    }
+}
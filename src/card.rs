@@ -1,6 +1,9 @@
+use std::{fmt, str::FromStr};
+
 use rand::Rng;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CardValue {
     King,
     Queen,
@@ -10,6 +13,7 @@ pub enum CardValue {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Suit {
     Hearts,
     Diamonds,
@@ -31,19 +35,18 @@ impl Suit {
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-#[allow(unused)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Card {
     pub value: CardValue,
     suit: Suit,
 }
 
-#[allow(dead_code)]
 impl Card {
     pub fn new(value: CardValue, suit: Suit) -> Self {
-        if let CardValue::Number(n) = value {
-            if n < 2 || n > 10 {
-                panic!("Card number must be between 2 and 10");
-            }
+        if let CardValue::Number(n) = value
+            && !(2..=10).contains(&n)
+        {
+            panic!("Card number must be between 2 and 10");
         }
         Card { value, suit }
     }
@@ -61,6 +64,10 @@ impl Card {
         Card { value, suit }
     }
 
+    pub fn get_suit(&self) -> Suit {
+        self.suit
+    }
+
     pub fn get_card_value(&self) -> u8 {
         match self.value {
             CardValue::Number(n) => n,
@@ -70,6 +77,57 @@ impl Card {
     }
 }
 
+// short text form used in logs and files: rank then suit letter, e.g. 10H, AS, QD
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rank = match self.value {
+            CardValue::Ace => "A".to_string(),
+            CardValue::King => "K".to_string(),
+            CardValue::Queen => "Q".to_string(),
+            CardValue::Jack => "J".to_string(),
+            CardValue::Number(n) => n.to_string(),
+        };
+        let suit = match self.suit {
+            Suit::Hearts => 'H',
+            Suit::Diamonds => 'D',
+            Suit::Clubs => 'C',
+            Suit::Spades => 'S',
+        };
+        write!(f, "{rank}{suit}")
+    }
+}
+
+impl FromStr for Card {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid card: {s}");
+        // on the last character, which may not be a single byte
+        let Some((index, _)) = s.char_indices().next_back() else {
+            return Err(invalid());
+        };
+        let (rank, suit) = s.split_at(index);
+        let suit = match suit {
+            "H" => Suit::Hearts,
+            "D" => Suit::Diamonds,
+            "C" => Suit::Clubs,
+            "S" => Suit::Spades,
+            _ => return Err(invalid()),
+        };
+        let value = match rank {
+            "A" => CardValue::Ace,
+            "K" => CardValue::King,
+            "Q" => CardValue::Queen,
+            "J" => CardValue::Jack,
+            _ => match rank.parse() {
+                Ok(n) if (2..=10).contains(&n) => CardValue::Number(n),
+                _ => return Err(invalid()),
+            },
+        };
+        Ok(Card::new(value, suit))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::Card;
@@ -127,4 +185,40 @@ mod test {
             ));
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn card_json_round_trip() {
+        let card = Card::new(CardValue::Number(7), Suit::Diamonds);
+        let json = serde_json::to_string(&card).unwrap();
+        assert_eq!(serde_json::from_str::<Card>(&json).unwrap(), card);
+    }
+
+    #[test]
+    fn card_text_round_trip() {
+        for suit in [Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades] {
+            for value in [
+                CardValue::Ace,
+                CardValue::King,
+                CardValue::Queen,
+                CardValue::Jack,
+                CardValue::Number(2),
+                CardValue::Number(10),
+            ] {
+                let card = Card::new(value, suit);
+                assert_eq!(card.to_string().parse::<Card>(), Ok(card));
+            }
+        }
+        assert_eq!(
+            Card::new(CardValue::Number(10), Suit::Hearts).to_string(),
+            "10H"
+        );
+    }
+
+    #[test]
+    fn invalid_card_text_is_rejected() {
+        for text in ["", "S", "1S", "11H", "AX", "KS ", "ZZ", "A♠", "♠", "10♥"] {
+            assert_eq!(text.parse::<Card>(), Err(format!("Invalid card: {text}")));
+        }
+    }
 }
@@ -0,0 +1,788 @@
+//! Strategy charts: hard, soft and pair tables indexed by the player hand and the
+//! dealer upcard, read from and written to CSV or TOML files.
+//!
+//! Each table has one row per hand and one column per dealer upcard, `2` to `10`
+//! then `A`. Hard rows are the totals `5` to `20`, soft rows `A2` to `A9` and pair
+//! rows `22` to `99`, `TT` and `AA`. Lower hard totals are played as 5, hard and
+//! soft 21 always stand and soft 12 always hits.
+//!
+//! Cells hold one of these codes, the second action is played when the first is
+//! not allowed:
+//!
+//! ```text
+//! H   hit                 P   split
+//! S   stand               Rh  surrender, else hit
+//! D   double, else hit    Rs  surrender, else stand
+//! Ds  double, else stand  Rp  surrender, else split
+//! ```
+//!
+//! In CSV every table starts with a header line naming it and its columns,
+//! e.g. `hard,2,3,4,5,6,7,8,9,10,A`, followed by its rows. Blank lines and lines
+//! starting with `#` are ignored. In TOML every table is a section with one array
+//! of codes per row, e.g. `[hard]` then `12 = ["H", "H", "S", ...]`.
+//!
+//! Charts can also be written, not read, as Markdown tables or as an HTML page.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{
+    card::Card,
+    game::Game,
+    player::{Player, PlayerAction},
+};
+
+// a cell of a strategy chart, the first legal action of the cell is played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartAction {
+    Hit,
+    Stand,
+    DoubleOrHit,
+    DoubleOrStand,
+    Split,
+    SurrenderOrHit,
+    SurrenderOrStand,
+    SurrenderOrSplit,
+}
+
+impl ChartAction {
+    pub fn preferences(self) -> &'static [PlayerAction] {
+        match self {
+            ChartAction::Hit => &[PlayerAction::Hit],
+            ChartAction::Stand => &[PlayerAction::Stand],
+            ChartAction::DoubleOrHit => &[PlayerAction::Double, PlayerAction::Hit],
+            ChartAction::DoubleOrStand => &[PlayerAction::Double, PlayerAction::Stand],
+            ChartAction::Split => &[PlayerAction::Split],
+            ChartAction::SurrenderOrHit => &[PlayerAction::Surrender, PlayerAction::Hit],
+            ChartAction::SurrenderOrStand => &[PlayerAction::Surrender, PlayerAction::Stand],
+            ChartAction::SurrenderOrSplit => &[PlayerAction::Surrender, PlayerAction::Split],
+        }
+    }
+
+    fn resolve(self, legal: &[PlayerAction]) -> Option<PlayerAction> {
+        self.preferences()
+            .iter()
+            .copied()
+            .find(|action| legal.contains(action))
+    }
+}
+
+impl fmt::Display for ChartAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = match self {
+            ChartAction::Hit => "H",
+            ChartAction::Stand => "S",
+            ChartAction::DoubleOrHit => "D",
+            ChartAction::DoubleOrStand => "Ds",
+            ChartAction::Split => "P",
+            ChartAction::SurrenderOrHit => "Rh",
+            ChartAction::SurrenderOrStand => "Rs",
+            ChartAction::SurrenderOrSplit => "Rp",
+        };
+        write!(f, "{code}")
+    }
+}
+
+impl FromStr for ChartAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "H" => Ok(ChartAction::Hit),
+            "S" => Ok(ChartAction::Stand),
+            "D" | "Dh" => Ok(ChartAction::DoubleOrHit),
+            "Ds" => Ok(ChartAction::DoubleOrStand),
+            "P" => Ok(ChartAction::Split),
+            "Rh" => Ok(ChartAction::SurrenderOrHit),
+            "Rs" => Ok(ChartAction::SurrenderOrStand),
+            "Rp" => Ok(ChartAction::SurrenderOrSplit),
+            _ => Err(format!(
+                "unknown code {s}, expected one of H, S, D, Ds, P, Rh, Rs, Rp"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartTable {
+    Hard,
+    Soft,
+    Pairs,
+}
+
+pub const CHART_TABLES: [ChartTable; 3] = [ChartTable::Hard, ChartTable::Soft, ChartTable::Pairs];
+
+// dealer upcards 2 to 10 then the ace
+pub const DEALER_COLUMNS: [&str; 10] = ["2", "3", "4", "5", "6", "7", "8", "9", "10", "A"];
+
+impl ChartTable {
+    pub fn rows(self) -> &'static [&'static str] {
+        match self {
+            ChartTable::Hard => &[
+                "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16", "17", "18",
+                "19", "20",
+            ],
+            ChartTable::Soft => &["A2", "A3", "A4", "A5", "A6", "A7", "A8", "A9"],
+            ChartTable::Pairs => &["22", "33", "44", "55", "66", "77", "88", "99", "TT", "AA"],
+        }
+    }
+}
+
+impl ChartTable {
+    fn title(self) -> &'static str {
+        match self {
+            ChartTable::Hard => "Hard totals",
+            ChartTable::Soft => "Soft totals",
+            ChartTable::Pairs => "Pairs",
+        }
+    }
+}
+
+impl fmt::Display for ChartTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ChartTable::Hard => "hard",
+            ChartTable::Soft => "soft",
+            ChartTable::Pairs => "pairs",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for ChartTable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hard" => Ok(ChartTable::Hard),
+            "soft" => Ok(ChartTable::Soft),
+            "pairs" => Ok(ChartTable::Pairs),
+            _ => Err(format!("unknown table {s}, expected hard, soft or pairs")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ChartError {
+    Io(io::Error),
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    Row {
+        table: ChartTable,
+        row: String,
+        message: String,
+    },
+    Cell {
+        table: ChartTable,
+        row: String,
+        column: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for ChartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChartError::Io(error) => write!(f, "{error}"),
+            ChartError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            ChartError::Row {
+                table,
+                row,
+                message,
+            } => write!(f, "{table} row {row}: {message}"),
+            ChartError::Cell {
+                table,
+                row,
+                column,
+                message,
+            } => write!(f, "{table} row {row}, column {column}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ChartError {}
+
+impl From<io::Error> for ChartError {
+    fn from(error: io::Error) -> Self {
+        ChartError::Io(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyChart {
+    hard: Vec<[ChartAction; 10]>,
+    soft: Vec<[ChartAction; 10]>,
+    pairs: Vec<[ChartAction; 10]>,
+}
+
+impl StrategyChart {
+    // fills every cell from the player row and the dealer upcard value, 11 for an ace
+    pub fn from_fn<F: FnMut(ChartTable, &str, u8) -> ChartAction>(mut cell: F) -> Self {
+        let mut table = |table: ChartTable| {
+            table
+                .rows()
+                .iter()
+                .map(|row| std::array::from_fn(|column| cell(table, row, column as u8 + 2)))
+                .collect()
+        };
+        StrategyChart {
+            hard: table(ChartTable::Hard),
+            soft: table(ChartTable::Soft),
+            pairs: table(ChartTable::Pairs),
+        }
+    }
+
+    pub fn get(&self, table: ChartTable, row: usize, dealer: u8) -> ChartAction {
+        let rows = match table {
+            ChartTable::Hard => &self.hard,
+            ChartTable::Soft => &self.soft,
+            ChartTable::Pairs => &self.pairs,
+        };
+        rows[row][dealer as usize - 2]
+    }
+
+    // the first legal action of the cell, a pair that cannot be split is played on its total
+    pub fn recommend(
+        &self,
+        hand: &[Card],
+        dealer_upcard: &Card,
+        legal: &[PlayerAction],
+    ) -> PlayerAction {
+        let player = Player {
+            hand: hand.to_vec(),
+            ..Default::default()
+        };
+        let dealer = dealer_upcard.get_card_value();
+        if player.is_pair()
+            && let Some(action) = self
+                .get(
+                    ChartTable::Pairs,
+                    hand[0].get_card_value() as usize - 2,
+                    dealer,
+                )
+                .resolve(legal)
+        {
+            return action;
+        }
+        let total = player.hand_value();
+        let cell = match (player.is_soft(), total) {
+            (_, 21..) => ChartAction::Stand,
+            (true, 13..) => self.get(ChartTable::Soft, total as usize - 13, dealer),
+            (true, _) => ChartAction::Hit,
+            (false, _) => self.get(ChartTable::Hard, total.max(5) as usize - 5, dealer),
+        };
+        cell.resolve(legal).unwrap_or(PlayerAction::Stand)
+    }
+
+    // advice for a hand of the game, against the dealer face up card
+    pub fn recommend_hand(&self, game: &Game, player_index: usize) -> PlayerAction {
+        self.recommend(
+            game.get_player_hand(player_index),
+            &game.get_dealer_hand()[0],
+            &game.legal_actions(player_index),
+        )
+    }
+
+    pub fn read_csv<R: BufRead>(reader: R) -> Result<Self, ChartError> {
+        let mut builder = ChartBuilder::default();
+        let mut table = None;
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let number = i + 1;
+            let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+            if line.trim().is_empty() || cells[0].starts_with('#') {
+                continue;
+            }
+            if let Ok(header) = cells[0].parse::<ChartTable>() {
+                if cells[1..] != DEALER_COLUMNS {
+                    return Err(ChartError::Syntax {
+                        line: number,
+                        message: format!(
+                            "the {header} header must list the dealer upcards {}",
+                            DEALER_COLUMNS.join(",")
+                        ),
+                    });
+                }
+                table = Some(header);
+                continue;
+            }
+            let Some(table) = table else {
+                return Err(ChartError::Syntax {
+                    line: number,
+                    message: "expected a table header such as hard,2,3,...,10,A".to_string(),
+                });
+            };
+            builder.set_row(table, cells[0], &cells[1..])?;
+        }
+        builder.build()
+    }
+
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (i, table) in CHART_TABLES.into_iter().enumerate() {
+            if i > 0 {
+                writeln!(writer)?;
+            }
+            writeln!(writer, "{table},{}", DEALER_COLUMNS.join(","))?;
+            for (row, label) in table.rows().iter().enumerate() {
+                let cells: Vec<String> = (2..=11)
+                    .map(|dealer| self.get(table, row, dealer).to_string())
+                    .collect();
+                writeln!(writer, "{label},{}", cells.join(","))?;
+            }
+        }
+        writer.flush()
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_toml(text: &str) -> Result<Self, ChartError> {
+        use std::collections::BTreeMap;
+
+        let tables: BTreeMap<String, BTreeMap<String, Vec<String>>> = toml::from_str(text)
+            .map_err(|error| ChartError::Syntax {
+                line: error
+                    .span()
+                    .map_or(0, |span| text[..span.start].lines().count().max(1)),
+                message: error.message().to_string(),
+            })?;
+        let mut builder = ChartBuilder::default();
+        for (name, rows) in &tables {
+            let table = name.parse().map_err(|message| ChartError::Syntax {
+                line: text
+                    .lines()
+                    .position(|line| line.trim() == format!("[{name}]"))
+                    .map_or(0, |line| line + 1),
+                message,
+            })?;
+            for (row, cells) in rows {
+                let cells: Vec<&str> = cells.iter().map(String::as_str).collect();
+                builder.set_row(table, row, &cells)?;
+            }
+        }
+        builder.build()
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_toml(&self) -> String {
+        let mut text = String::new();
+        for (i, table) in CHART_TABLES.into_iter().enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            text.push_str(&format!("[{table}]\n"));
+            text.push_str(&format!("# dealer {}\n", DEALER_COLUMNS.join(" ")));
+            for (row, label) in table.rows().iter().enumerate() {
+                let cells: Vec<String> = (2..=11)
+                    .map(|dealer| format!("{:?}", self.get(table, row, dealer).to_string()))
+                    .collect();
+                text.push_str(&format!("{label} = [{}]\n", cells.join(", ")));
+            }
+        }
+        text
+    }
+
+    // one table per chart table under a heading, then the codes
+    pub fn write_markdown<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for table in CHART_TABLES {
+            writeln!(writer, "## {}\n", table.title())?;
+            writeln!(writer, "| | {} |", DEALER_COLUMNS.join(" | "))?;
+            writeln!(writer, "|---{}|", "|---".repeat(DEALER_COLUMNS.len()))?;
+            for (row, label) in table.rows().iter().enumerate() {
+                let cells: Vec<String> = (2..=11)
+                    .map(|dealer| self.get(table, row, dealer).to_string())
+                    .collect();
+                writeln!(writer, "| {label} | {} |", cells.join(" | "))?;
+            }
+            writeln!(writer)?;
+        }
+        writeln!(writer, "{}", LEGEND)?;
+        writer.flush()
+    }
+
+    // a whole page, every cell coloured by its first action
+    pub fn write_html<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{HTML_HEAD}")?;
+        for table in CHART_TABLES {
+            writeln!(writer, "<h2>{}</h2>\n<table>", table.title())?;
+            let header: Vec<String> = DEALER_COLUMNS
+                .iter()
+                .map(|column| format!("<th>{column}</th>"))
+                .collect();
+            writeln!(writer, "<tr><th></th>{}</tr>", header.concat())?;
+            for (row, label) in table.rows().iter().enumerate() {
+                let cells: Vec<String> = (2..=11)
+                    .map(|dealer| {
+                        let cell = self.get(table, row, dealer);
+                        let class = cell.preferences()[0].to_string();
+                        format!("<td class=\"{class}\">{cell}</td>")
+                    })
+                    .collect();
+                writeln!(writer, "<tr><th>{label}</th>{}</tr>", cells.concat())?;
+            }
+            writeln!(writer, "</table>")?;
+        }
+        writeln!(writer, "<p>{LEGEND}</p>\n</body>\n</html>")?;
+        writer.flush()
+    }
+
+    // the format is picked from the extension, .toml or .csv
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ChartError> {
+        let path = path.as_ref();
+        match ChartFormat::of(path) {
+            ChartFormat::Csv => StrategyChart::read_csv(BufReader::new(File::open(path)?)),
+            ChartFormat::Toml => load_toml(path),
+            ChartFormat::Markdown | ChartFormat::Html => {
+                Err(io::Error::other("charts can only be read from .csv or .toml files").into())
+            }
+        }
+    }
+
+    // also .md for Markdown and .html for a page
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ChartError> {
+        let path = path.as_ref();
+        match ChartFormat::of(path) {
+            ChartFormat::Csv => Ok(self.write_csv(io::BufWriter::new(File::create(path)?))?),
+            ChartFormat::Toml => save_toml(self, path),
+            ChartFormat::Markdown => {
+                Ok(self.write_markdown(io::BufWriter::new(File::create(path)?))?)
+            }
+            ChartFormat::Html => Ok(self.write_html(io::BufWriter::new(File::create(path)?))?),
+        }
+    }
+}
+
+const LEGEND: &str = "H hit, S stand, D double else hit, Ds double else stand, P split, \
+Rh surrender else hit, Rs surrender else stand, Rp surrender else split";
+
+const HTML_HEAD: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Strategy chart</title>
+<style>
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #888; padding: 2px 8px; text-align: center; }
+.hit { background: #ffffff; }
+.stand { background: #f6d55c; }
+.double { background: #7fc97f; }
+.split { background: #80b1d3; }
+.surrender { background: #fb8072; }
+</style>
+</head>
+<body>"#;
+
+enum ChartFormat {
+    Csv,
+    Toml,
+    Markdown,
+    Html,
+}
+
+impl ChartFormat {
+    // csv unless the extension says otherwise
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => ChartFormat::Toml,
+            Some("md" | "markdown") => ChartFormat::Markdown,
+            Some("html" | "htm") => ChartFormat::Html,
+            _ => ChartFormat::Csv,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn load_toml(path: &Path) -> Result<StrategyChart, ChartError> {
+    StrategyChart::from_toml(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(feature = "serde")]
+fn save_toml(chart: &StrategyChart, path: &Path) -> Result<(), ChartError> {
+    Ok(std::fs::write(path, chart.to_toml())?)
+}
+
+#[cfg(not(feature = "serde"))]
+fn load_toml(_path: &Path) -> Result<StrategyChart, ChartError> {
+    Err(io::Error::other("TOML charts need the serde feature").into())
+}
+
+#[cfg(not(feature = "serde"))]
+fn save_toml(_chart: &StrategyChart, _path: &Path) -> Result<(), ChartError> {
+    Err(io::Error::other("TOML charts need the serde feature").into())
+}
+
+// collects the rows of a chart being read, every cell must be filled exactly once
+struct ChartBuilder {
+    // one entry per row of each table, in the order of CHART_TABLES
+    tables: Vec<Vec<Option<[ChartAction; 10]>>>,
+}
+
+impl Default for ChartBuilder {
+    fn default() -> Self {
+        ChartBuilder {
+            tables: CHART_TABLES
+                .iter()
+                .map(|table| vec![None; table.rows().len()])
+                .collect(),
+        }
+    }
+}
+
+impl ChartBuilder {
+    fn set_row(&mut self, table: ChartTable, row: &str, cells: &[&str]) -> Result<(), ChartError> {
+        let row_error = |message: String| ChartError::Row {
+            table,
+            row: row.to_string(),
+            message,
+        };
+        let Some(index) = table.rows().iter().position(|label| *label == row) else {
+            return Err(row_error(format!(
+                "unknown row, expected one of {}",
+                table.rows().join(", ")
+            )));
+        };
+        let slot = &mut self.tables[table as usize][index];
+        if slot.is_some() {
+            return Err(row_error("row given twice".to_string()));
+        }
+        if cells.len() > DEALER_COLUMNS.len() {
+            return Err(row_error(format!(
+                "{} cells, expected one per dealer upcard {}",
+                cells.len(),
+                DEALER_COLUMNS.join(",")
+            )));
+        }
+        let mut actions = [ChartAction::Stand; 10];
+        for (column, label) in DEALER_COLUMNS.iter().enumerate() {
+            let cell_error = |message: String| ChartError::Cell {
+                table,
+                row: row.to_string(),
+                column: label.to_string(),
+                message,
+            };
+            actions[column] = match cells.get(column).map(|cell| cell.trim()) {
+                None | Some("") => return Err(cell_error("empty cell".to_string())),
+                Some(code) => code.parse().map_err(cell_error)?,
+            };
+        }
+        *slot = Some(actions);
+        Ok(())
+    }
+
+    fn build(self) -> Result<StrategyChart, ChartError> {
+        let mut tables = Vec::new();
+        for (table, rows) in CHART_TABLES.into_iter().zip(self.tables) {
+            let mut filled = Vec::new();
+            for (label, row) in table.rows().iter().zip(rows) {
+                let Some(row) = row else {
+                    return Err(ChartError::Row {
+                        table,
+                        row: label.to_string(),
+                        message: "missing row".to_string(),
+                    });
+                };
+                filled.push(row);
+            }
+            tables.push(filled);
+        }
+        let pairs = tables.pop().unwrap();
+        let soft = tables.pop().unwrap();
+        let hard = tables.pop().unwrap();
+        Ok(StrategyChart { hard, soft, pairs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rules::TableRules, strategy::BasicStrategy};
+
+    fn basic_csv() -> String {
+        let mut buffer = Vec::new();
+        BasicStrategy::chart(&TableRules::default())
+            .write_csv(&mut buffer)
+            .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    fn read(csv: &str) -> Result<StrategyChart, ChartError> {
+        StrategyChart::read_csv(csv.as_bytes())
+    }
+
+    #[test]
+    fn csv_export_is_read_back() {
+        let csv = basic_csv();
+        assert!(csv.starts_with("hard,2,3,4,5,6,7,8,9,10,A\n5,H,H,H,H,H,H,H,H,H,H\n"));
+        assert!(csv.contains("\n16,S,S,S,S,S,H,H,Rh,Rh,Rh\n"));
+        assert!(csv.contains("\nsoft,2,3,4,5,6,7,8,9,10,A\nA2,H,H,H,D,D,H,H,H,H,H\n"));
+        assert!(csv.contains("\nAA,P,P,P,P,P,P,P,P,P,P\n"));
+        assert_eq!(
+            read(&csv).unwrap(),
+            BasicStrategy::chart(&TableRules::default())
+        );
+    }
+
+    #[test]
+    fn comments_blank_lines_and_spaces_are_ignored() {
+        let csv = basic_csv()
+            .replace("hard,", "# my chart\n\nhard,")
+            .replace(",S,", ", S ,");
+        assert!(read(&csv).is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn toml_export_is_read_back() {
+        let chart = BasicStrategy::chart(&TableRules::preset("downtown").unwrap());
+        let toml = chart.to_toml();
+        assert!(toml.starts_with("[hard]\n# dealer 2 3 4 5 6 7 8 9 10 A\n5 = [\"H\","));
+        assert_eq!(StrategyChart::from_toml(&toml).unwrap(), chart);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn toml_errors_give_the_line_or_the_cell() {
+        let toml = BasicStrategy::chart(&TableRules::default()).to_toml();
+        let error = StrategyChart::from_toml(&toml.replacen("\"Ds\"", "\"X\"", 1))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "soft row A7, column 3: unknown code X, expected one of H, S, D, Ds, P, Rh, Rs, Rp"
+        );
+        let error = StrategyChart::from_toml(&toml.replacen("[soft]", "[sfot]", 1))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 20: unknown table sfot, expected hard, soft or pairs"
+        );
+        let error = StrategyChart::from_toml("[hard]\n5 = [\"H\"\n")
+            .err()
+            .unwrap();
+        assert!(
+            matches!(error, ChartError::Syntax { line: 2, .. }),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn invalid_cells_are_reported_by_row_and_column() {
+        let csv = basic_csv();
+        let cases = [
+            (
+                csv.replace("\n12,H,H,S,", "\n12,H,H,X,"),
+                "hard row 12, column 4: unknown code X, expected one of H, S, D, Ds, P, Rh, Rs, Rp",
+            ),
+            (
+                csv.replace("\n12,H,H,S,", "\n12,H,H,,"),
+                "hard row 12, column 4: empty cell",
+            ),
+            (
+                csv.replace("\nAA,P,P,P,P,P,P,P,P,P,P", "\nAA,P,P,P,P,P,P,P,P"),
+                "pairs row AA, column 10: empty cell",
+            ),
+            (csv.replace("\nA5,", "\n# A5,"), "soft row A5: missing row"),
+            (
+                csv.replace("\n21,", "\n22,").replace("\n20,", "\n21,"),
+                "hard row 21: unknown row, expected one of 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20",
+            ),
+            (
+                csv.replace("\n19,", "\n20,"),
+                "hard row 20: row given twice",
+            ),
+            (
+                csv.replace("\nTT,S,S,S,S,S,S,S,S,S,S", "\nTT,S,S,S,S,S,S,S,S,S,S,S"),
+                "pairs row TT: 11 cells, expected one per dealer upcard 2,3,4,5,6,7,8,9,10,A",
+            ),
+            (
+                csv.replace("hard,2,3,4,5,6,7,8,9,10,A", "hard,A,2,3,4,5,6,7,8,9,10"),
+                "line 1: the hard header must list the dealer upcards 2,3,4,5,6,7,8,9,10,A",
+            ),
+            (
+                format!("5,H\n{csv}"),
+                "line 1: expected a table header such as hard,2,3,...,10,A",
+            ),
+        ];
+        for (csv, message) in cases {
+            assert_eq!(read(&csv).err().unwrap().to_string(), message);
+        }
+    }
+
+    #[test]
+    fn loaded_chart_drives_the_advice() {
+        let csv = basic_csv().replace(
+            "\n16,S,S,S,S,S,H,H,Rh,Rh,Rh\n",
+            "\n16,S,S,S,S,S,S,S,S,S,S\n",
+        );
+        let chart = read(&csv).unwrap();
+        let hand = [
+            Card::new(crate::card::CardValue::Number(10), crate::card::Suit::Clubs),
+            Card::new(crate::card::CardValue::Number(6), crate::card::Suit::Clubs),
+        ];
+        let upcard = Card::new(crate::card::CardValue::Ace, crate::card::Suit::Clubs);
+        let legal = [
+            PlayerAction::Hit,
+            PlayerAction::Stand,
+            PlayerAction::Surrender,
+        ];
+        assert_eq!(chart.recommend(&hand, &upcard, &legal), PlayerAction::Stand);
+    }
+
+    #[test]
+    fn chart_file_format_follows_the_extension() {
+        let dir = std::env::temp_dir();
+        let chart = BasicStrategy::chart(&TableRules::default());
+        let mut extensions = vec!["csv"];
+        if cfg!(feature = "serde") {
+            extensions.push("toml");
+        }
+        for extension in extensions {
+            let path = dir.join(format!(
+                "blackjack-chart-{}.{extension}",
+                std::process::id()
+            ));
+            chart.save(&path).unwrap();
+            let loaded = StrategyChart::load(&path);
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(loaded.unwrap(), chart);
+        }
+    }
+
+    #[test]
+    fn markdown_and_html_list_every_cell() {
+        let chart = BasicStrategy::chart(&TableRules::default());
+        let mut markdown = Vec::new();
+        chart.write_markdown(&mut markdown).unwrap();
+        let markdown = String::from_utf8(markdown).unwrap();
+        assert!(markdown.starts_with(
+            "## Hard totals\n\n| | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9 | 10 | A |\n\
+             |---|---|---|---|---|---|---|---|---|---|---|\n| 5 | H | H |"
+        ));
+        assert!(markdown.contains("\n| 16 | S | S | S | S | S | H | H | Rh | Rh | Rh |\n"));
+        assert!(markdown.contains("\n## Pairs\n"));
+        assert_eq!(markdown.matches("\n| ").count(), 16 + 8 + 10 + 3);
+
+        let mut html = Vec::new();
+        chart.write_html(&mut html).unwrap();
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<tr><th>AA</th><td class=\"split\">P</td>"));
+        assert!(html.contains("<td class=\"surrender\">Rh</td>"));
+        assert_eq!(html.matches("<td ").count(), 340);
+        assert!(html.trim_end().ends_with("</html>"));
+
+        let path = std::env::temp_dir().join(format!("blackjack-chart-{}.md", std::process::id()));
+        chart.save(&path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        let loaded = StrategyChart::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved, markdown);
+        assert!(loaded.is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use std::io::{self, Write};
+
+use blackjack::{
+    analysis::{analyze, card_name, composition_exceptions},
+    probability::{ShoeComposition, dealer_probabilities},
+    rules::TableRules,
+};
+
+// values of the decisions for the hand and the upcard dealt from a full shoe
+pub fn run<W: Write>(
+    hand: &[u8],
+    upcard: u8,
+    rules: &TableRules,
+    output: &mut W,
+) -> io::Result<()> {
+    let mut shoe = ShoeComposition::from_decks(rules.decks);
+    for value in hand.iter().chain([&upcard]) {
+        if shoe.get(*value) == 0 {
+            let message = format!(
+                "Not enough cards of value {value} in {} deck(s)",
+                rules.decks
+            );
+            return Err(io::Error::other(message));
+        }
+        shoe.remove(*value);
+    }
+    let cards: Vec<&str> = hand.iter().map(|value| card_name(*value)).collect();
+    writeln!(
+        output,
+        "{} against {}, {} deck(s)",
+        cards.join(""),
+        card_name(upcard),
+        rules.decks
+    )?;
+    let dealer = dealer_probabilities(&shoe, upcard, rules);
+    if dealer.blackjack > 0.0 {
+        writeln!(
+            output,
+            "Dealer without blackjack: {}",
+            dealer.without_blackjack()
+        )?;
+    } else {
+        writeln!(output, "Dealer: {dealer}")?;
+    }
+    write!(output, "{}", analyze(hand, upcard, &shoe, rules))
+}
+
+// two card hands where the best play for the exact cards is not the basic strategy one
+pub fn exceptions<W: Write>(rules: &TableRules, output: &mut W) -> io::Result<()> {
+    writeln!(
+        output,
+        "Composition dependent plays, {} deck(s)",
+        rules.decks
+    )?;
+    let exceptions = composition_exceptions(rules);
+    if exceptions.is_empty() {
+        return writeln!(
+            output,
+            "None, the basic strategy is the best play of every hand"
+        );
+    }
+    for exception in exceptions {
+        writeln!(output, "{exception}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_decision_is_printed() {
+        let mut output = Vec::new();
+        run(&[10, 6], 10, &TableRules::default(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "T6 against T, 6 deck(s)");
+        assert!(lines[1].starts_with("Dealer without blackjack: 17: "));
+        assert!(lines[2].starts_with("stand      -0.54"));
+        assert!(lines[3].starts_with("hit        -0.53"));
+        assert!(lines[4].starts_with("double     -1.0"));
+        assert_eq!(lines[5], "surrender  -0.5000  best");
+    }
+
+    #[test]
+    fn missing_cards_are_reported() {
+        let rules = TableRules {
+            decks: 1,
+            ..Default::default()
+        };
+        assert!(run(&[11, 11, 2], 11, &rules, &mut Vec::new()).is_ok());
+        let error = run(&[11, 11, 11, 11], 11, &rules, &mut Vec::new()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Not enough cards of value 11 in 1 deck(s)"
+        );
+    }
+}
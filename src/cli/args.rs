@@ -0,0 +1,759 @@
+use blackjack::{
+    analysis::{parse_card, parse_hand},
+    autoplay::STRATEGIES,
+    betting::BETTING_STRATEGIES,
+    counting::{HI_LO, SYSTEMS, TagSystem, system_by_name},
+    game::{DEFAULT_BANKROLL, MAX_PLAYERS},
+    player::PlayerAction,
+    replay::parse_actions,
+    rules::{PRESETS, TableRules},
+};
+
+pub const DEFAULT_BET: u32 = 10;
+
+pub const DEFAULT_ROUNDS: u64 = 100_000;
+
+pub const DEFAULT_SPREAD: u32 = 8;
+
+// in percent
+pub const DEFAULT_TARGET_RISK: f64 = 5.0;
+
+pub const DEFAULT_SESSIONS: u64 = 1000;
+
+pub const USAGE: &str = "\
+Usage: blackjack [MODE] [OPTIONS]
+       blackjack analyze <HAND> vs <UPCARD> [OPTIONS]
+
+Modes:
+  interactive              Play at the table from the terminal (default)
+  simulate                 Run automated rounds and report the results
+  replay                   Rebuild a game from a seed and an action log
+  analyze                  Exact value of every decision for a hand against an
+                           upcard from a full shoe, e.g. analyze 16 vs T or
+                           analyze A7 vs 9 -d 2. A hand is a hard total or
+                           cards (2-9, T, A) such as 88 or T,2,4
+  plot                     Chart the bankroll of every seat over the rounds of
+                           the --history file to the --svg prefix
+
+Options:
+  -p, --players <N>        Number of players, 1 to 7 (asked when omitted)
+  -d, --decks <N>          Number of decks in the shoe, 1 to 8
+  -r, --rules <PRESET>     Rule preset: default, vegas-strip, atlantic-city,
+                           downtown or single-deck
+      --h17 | --s17        Dealer hits or stands on soft 17
+      --das | --no-das     Double after split allowed or not
+      --surrender | --no-surrender
+                           Late surrender allowed or not
+      --blackjack-pays <N:D>
+                           Blackjack payout, e.g. 3:2 or 6:5
+      --penetration <F>    Fraction of the shoe dealt before reshuffling
+  -b, --bankroll <N>       Starting bankroll of every player
+  -s, --seed <N>           Seed of the shoe shuffles
+      --history <FILE>     Append the hand history of every round to FILE
+      --actions <LIST>     Actions to replay, e.g. \"H S D\" (hit, stand, double,
+                           split, surrender), needs --seed
+      --auto <SEAT:STRATEGY>
+                           Let a strategy play the seat: basic, never-bust,
+                           mimic-dealer or random, e.g. --auto 2:basic
+      --bet <N>            Bet of the automated and simulated seats and of every
+                           player in each replayed round (10)
+      --chart <FILE>       Strategy chart (.csv or .toml) used for hints and
+                           played by the simulated seats
+      --export-chart <FILE>
+                           Write the strategy chart for the rules, or the one
+                           given with --chart, to FILE and exit. The format
+                           follows the extension: .csv, .toml, .md or .html
+      --generate-chart <FILE>
+                           Work out the best play of every chart cell for the
+                           rules from exact values, write it to FILE and exit
+      --composition-exceptions
+                           List the two card hands where the exact cards change
+                           the basic strategy play for the rules and exit
+      --rounds <N>         Rounds to simulate, or the most rounds with
+                           --target-ci (100000)
+      --target-ci <F>      Simulate until the 95% confidence interval of the
+                           house edge is within ±F percent, e.g. 0.1
+      --strategy <NAME>    Strategy of every simulated seat: basic, never-bust,
+                           mimic-dealer or random (basic), --chart overrides it
+      --threads <N>        Threads of the simulation, the results are the same
+                           for any number of threads (one per core)
+      --betting <LIST>     Betting strategies of the simulated seats, each one
+                           simulated in turn on the same cards: flat, ramp,
+                           kelly, martingale, paroli or dalembert, e.g.
+                           flat,ramp. The unit is --bet and the session starts
+                           with --bankroll, kelly needs the hi-lo count
+      --count <SYSTEM>     Count followed by the betting strategies: hi-lo, ko,
+                           hi-opt-1, hi-opt-2, omega-2, zen or wong-halves
+                           (hi-lo)
+      --spread <N>         Largest bet in units, of the ramp and the table
+                           maximum of the other strategies (8)
+      --deviations <LIST>  Playing deviations by true count over the basic
+                           strategy or the --chart: illustrious-18, fab-4 or
+                           CSV files, e.g. illustrious-18,fab-4. The EV added
+                           by each deviation is simulated too
+      --trip <HANDS>       Horizon of the risk of ruin in hands, playing on
+                           forever when omitted
+      --target-risk <F>    Risk of ruin in percent the needed bankroll is
+                           worked out for (5)
+      --sessions <N>       Sessions from --bankroll simulated to measure the
+                           risk of ruin, a lifetime lasting up to 100000 hands
+                           (1000)
+      --summary-json <FILE>
+                           Write a JSON summary of every simulated run to FILE
+      --true-count-csv <FILE>
+                           Write the EV of every run by true count to FILE
+      --hand-log <FILE>    Write every simulated hand to FILE as CSV, one file
+                           per betting strategy named after it when there are
+                           several
+      --svg <PREFIX>       Write SVG charts to files starting with PREFIX: a
+                           session drawn from the simulated hands, the
+                           percentiles of --sessions sessions and the EV by true
+                           count, over --trip hands (10000). With plot, the
+                           bankrolls of the history
+  -h, --help               Print this help
+
+Interactive commands:
+  bet <N>, hit, stand, double, split, surrender, hint, advise (best play for the
+  exact cards seen), save <FILE>, load <FILE>, quit
+";
+
+#[derive(Debug, PartialEq)]
+pub enum Mode {
+    Interactive,
+    Simulate,
+    Replay,
+    Analyze,
+    Plot,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub mode: Mode,
+    pub players: Option<u8>,
+    pub rules: TableRules,
+    pub bankroll: i64,
+    pub seed: Option<u64>,
+    pub history: Option<String>,
+    pub actions: Vec<PlayerAction>,
+    pub bet: u32,
+    pub chart: Option<String>,
+    pub export_chart: Option<String>,
+    pub generate_chart: Option<String>,
+    pub composition_exceptions: bool,
+    // seat and strategy name of every automated seat
+    pub auto: Vec<(usize, String)>,
+    pub rounds: u64,
+    // in percent of the bet
+    pub target_ci: Option<f64>,
+    pub strategy: String,
+    // 0 for one per core
+    pub threads: usize,
+    // none to bet the flat --bet without counting
+    pub betting: Vec<String>,
+    pub count: TagSystem,
+    pub spread: u32,
+    // names of deviation sets or paths of deviation files
+    pub deviations: Vec<String>,
+    // none for a lifetime
+    pub trip: Option<u64>,
+    // in percent
+    pub target_risk: f64,
+    pub sessions: u64,
+    pub summary_json: Option<String>,
+    pub true_count_csv: Option<String>,
+    pub hand_log: Option<String>,
+    pub svg: Option<String>,
+    // card values of the analyzed hand and of the dealer upcard
+    pub analyze: Option<(Vec<u8>, u8)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ArgsError {
+    Help,
+    Invalid(String),
+}
+
+// options given one by one are applied over the preset, whatever their order
+#[derive(Default)]
+struct RuleOverrides {
+    decks: Option<u8>,
+    dealer_hits_soft_17: Option<bool>,
+    double_after_split: Option<bool>,
+    surrender: Option<bool>,
+    blackjack_payout: Option<(u32, u32)>,
+    penetration: Option<f32>,
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Args, ArgsError> {
+    let mut args = args.into_iter();
+    let mut mode = None;
+    let mut players = None;
+    let mut preset = TableRules::default();
+    let mut overrides = RuleOverrides::default();
+    let mut bankroll = DEFAULT_BANKROLL;
+    let mut seed = None;
+    let mut history = None;
+    let mut actions = Vec::new();
+    let mut bet = DEFAULT_BET;
+    let mut chart = None;
+    let mut export_chart = None;
+    let mut generate_chart = None;
+    let mut composition_exceptions = false;
+    let mut auto = Vec::new();
+    let mut rounds = DEFAULT_ROUNDS;
+    let mut target_ci = None;
+    let mut strategy = "basic".to_string();
+    let mut threads = 0;
+    let mut betting = Vec::new();
+    let mut count = system_by_name("hi-lo").unwrap();
+    let mut spread = DEFAULT_SPREAD;
+    let mut deviations = Vec::new();
+    let mut trip = None;
+    let mut target_risk = DEFAULT_TARGET_RISK;
+    let mut sessions = DEFAULT_SESSIONS;
+    let mut summary_json = None;
+    let mut true_count_csv = None;
+    let mut hand_log = None;
+    let mut svg = None;
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Err(ArgsError::Help),
+            "-p" | "--players" => {
+                let n = parse_value(&arg, args.next())?;
+                if !(1..=MAX_PLAYERS).contains(&n) {
+                    return invalid(format!("{arg} must be between 1 and {MAX_PLAYERS}"));
+                }
+                players = Some(n);
+            }
+            "-d" | "--decks" => {
+                let n = parse_value(&arg, args.next())?;
+                if !(1..=8).contains(&n) {
+                    return invalid(format!("{arg} must be between 1 and 8"));
+                }
+                overrides.decks = Some(n);
+            }
+            "-r" | "--rules" => {
+                let name: String = parse_value(&arg, args.next())?;
+                let Some(rules) = TableRules::preset(&name) else {
+                    return invalid(format!(
+                        "Unknown rule preset {name}, expected one of: {}",
+                        PRESETS.join(", ")
+                    ));
+                };
+                preset = rules;
+            }
+            "--h17" => overrides.dealer_hits_soft_17 = Some(true),
+            "--s17" => overrides.dealer_hits_soft_17 = Some(false),
+            "--das" => overrides.double_after_split = Some(true),
+            "--no-das" => overrides.double_after_split = Some(false),
+            "--surrender" => overrides.surrender = Some(true),
+            "--no-surrender" => overrides.surrender = Some(false),
+            "--blackjack-pays" => {
+                let payout: String = parse_value(&arg, args.next())?;
+                overrides.blackjack_payout = Some(parse_payout(&payout)?);
+            }
+            "--penetration" => {
+                let penetration = parse_value(&arg, args.next())?;
+                if !(penetration > 0.0 && penetration <= 1.0) {
+                    return invalid(format!("{arg} must be between 0 and 1"));
+                }
+                overrides.penetration = Some(penetration);
+            }
+            "-b" | "--bankroll" => {
+                bankroll = parse_value(&arg, args.next())?;
+                if bankroll <= 0 {
+                    return invalid(format!("{arg} must be positive"));
+                }
+            }
+            "-s" | "--seed" => seed = Some(parse_value(&arg, args.next())?),
+            "--history" => history = Some(parse_value(&arg, args.next())?),
+            "--actions" => {
+                let log: String = parse_value(&arg, args.next())?;
+                actions = parse_actions(&log).or_else(invalid)?;
+            }
+            "--bet" => {
+                bet = parse_value(&arg, args.next())?;
+                if bet == 0 {
+                    return invalid(format!("{arg} must be positive"));
+                }
+            }
+            "--chart" => chart = Some(parse_value(&arg, args.next())?),
+            "--export-chart" => export_chart = Some(parse_value(&arg, args.next())?),
+            "--generate-chart" => generate_chart = Some(parse_value(&arg, args.next())?),
+            "--composition-exceptions" => composition_exceptions = true,
+            "--auto" => auto.push(parse_auto(&parse_value::<String>(&arg, args.next())?)?),
+            "--rounds" => {
+                rounds = parse_value(&arg, args.next())?;
+                if rounds == 0 {
+                    return invalid(format!("{arg} must be positive"));
+                }
+            }
+            "--target-ci" => {
+                let ci: f64 = parse_value(&arg, args.next())?;
+                if ci.is_nan() || ci <= 0.0 {
+                    return invalid(format!("{arg} must be positive"));
+                }
+                target_ci = Some(ci);
+            }
+            "--strategy" => {
+                strategy = parse_value(&arg, args.next())?;
+                if !STRATEGIES.contains(&strategy.as_str()) {
+                    return invalid(unknown_strategy(&strategy));
+                }
+            }
+            "--threads" => {
+                threads = parse_value(&arg, args.next())?;
+                if threads == 0 {
+                    return invalid(format!("{arg} must be positive"));
+                }
+            }
+            "--betting" => {
+                let list: String = parse_value(&arg, args.next())?;
+                betting = list
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .collect();
+                if let Some(name) = betting
+                    .iter()
+                    .find(|name| !BETTING_STRATEGIES.contains(&name.as_str()))
+                {
+                    return invalid(format!(
+                        "Unknown betting strategy {name}, expected one of: {}",
+                        BETTING_STRATEGIES.join(", ")
+                    ));
+                }
+            }
+            "--count" => {
+                let name: String = parse_value(&arg, args.next())?;
+                count = match system_by_name(&name) {
+                    Some(system) => system,
+                    None => {
+                        let names: Vec<&str> = SYSTEMS.iter().map(|system| system.name).collect();
+                        return invalid(format!(
+                            "Unknown counting system {name}, expected one of: {}",
+                            names.join(", ")
+                        ));
+                    }
+                };
+            }
+            "--spread" => {
+                spread = parse_value(&arg, args.next())?;
+                if spread == 0 {
+                    return invalid(format!("{arg} must be positive"));
+                }
+            }
+            "--deviations" => {
+                let list: String = parse_value(&arg, args.next())?;
+                deviations = list
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .collect();
+            }
+            "--trip" => {
+                let hands = parse_value(&arg, args.next())?;
+                if hands == 0 {
+                    return invalid(format!("{arg} must be positive"));
+                }
+                trip = Some(hands);
+            }
+            "--target-risk" => {
+                target_risk = parse_value(&arg, args.next())?;
+                if !(target_risk > 0.0 && target_risk < 100.0) {
+                    return invalid(format!("{arg} must be between 0 and 100"));
+                }
+            }
+            "--sessions" => {
+                sessions = parse_value(&arg, args.next())?;
+                if sessions == 0 {
+                    return invalid(format!("{arg} must be positive"));
+                }
+            }
+            "--summary-json" => summary_json = Some(parse_value(&arg, args.next())?),
+            "--true-count-csv" => true_count_csv = Some(parse_value(&arg, args.next())?),
+            "--hand-log" => hand_log = Some(parse_value(&arg, args.next())?),
+            "--svg" => svg = Some(parse_value(&arg, args.next())?),
+            "interactive" | "simulate" | "replay" | "analyze" | "plot" if mode.is_none() => {
+                mode = Some(match arg.as_str() {
+                    "interactive" => Mode::Interactive,
+                    "simulate" => Mode::Simulate,
+                    "replay" => Mode::Replay,
+                    "plot" => Mode::Plot,
+                    _ => Mode::Analyze,
+                });
+            }
+            other if mode == Some(Mode::Analyze) && !other.starts_with('-') => {
+                positional.push(other.to_string());
+            }
+            other => return invalid(format!("Unexpected argument: {other}")),
+        }
+    }
+
+    let rules = TableRules {
+        decks: overrides.decks.unwrap_or(preset.decks),
+        dealer_hits_soft_17: overrides
+            .dealer_hits_soft_17
+            .unwrap_or(preset.dealer_hits_soft_17),
+        double_after_split: overrides
+            .double_after_split
+            .unwrap_or(preset.double_after_split),
+        surrender: overrides.surrender.unwrap_or(preset.surrender),
+        blackjack_payout: overrides
+            .blackjack_payout
+            .unwrap_or(preset.blackjack_payout),
+        penetration: overrides.penetration.unwrap_or(preset.penetration),
+        ..preset
+    };
+
+    let mode = mode.unwrap_or(Mode::Interactive);
+    if mode == Mode::Replay && seed.is_none() {
+        return invalid("replay needs the --seed of the game".to_string());
+    }
+    if mode == Mode::Plot && (history.is_none() || svg.is_none()) {
+        return invalid("plot needs a --history file and an --svg prefix".to_string());
+    }
+    if betting.iter().any(|name| name == "kelly") && count != HI_LO {
+        return invalid(format!(
+            "kelly is calibrated for hi-lo true counts, not {}",
+            count.name
+        ));
+    }
+    if !deviations.is_empty() && strategy != "basic" {
+        return invalid(format!(
+            "--deviations are played over the basic strategy, not {strategy}"
+        ));
+    }
+    let analyze = match mode {
+        Mode::Analyze => Some(parse_position(&positional)?),
+        _ => None,
+    };
+
+    Ok(Args {
+        mode,
+        players,
+        rules,
+        bankroll,
+        seed,
+        history,
+        actions,
+        bet,
+        chart,
+        export_chart,
+        generate_chart,
+        composition_exceptions,
+        auto,
+        rounds,
+        target_ci,
+        strategy,
+        threads,
+        betting,
+        count,
+        spread,
+        deviations,
+        trip,
+        target_risk,
+        sessions,
+        summary_json,
+        true_count_csv,
+        hand_log,
+        svg,
+        analyze,
+    })
+}
+
+fn invalid<T>(message: String) -> Result<T, ArgsError> {
+    Err(ArgsError::Invalid(message))
+}
+
+fn parse_value<T: std::str::FromStr>(option: &str, value: Option<String>) -> Result<T, ArgsError> {
+    match value {
+        None => invalid(format!("{option} expects a value")),
+        Some(value) => value
+            .parse()
+            .or_else(|_| invalid(format!("Invalid value for {option}: {value}"))),
+    }
+}
+
+fn parse_auto(auto: &str) -> Result<(usize, String), ArgsError> {
+    let Some((seat, name)) = auto.split_once(':') else {
+        return invalid(format!(
+            "Invalid value for --auto: {auto}, expected SEAT:STRATEGY"
+        ));
+    };
+    let seat = match seat.parse::<u8>() {
+        Ok(seat) if (1..=MAX_PLAYERS).contains(&seat) => seat as usize - 1,
+        _ => return invalid(format!("--auto seat must be between 1 and {MAX_PLAYERS}")),
+    };
+    if !STRATEGIES.contains(&name) {
+        return invalid(unknown_strategy(name));
+    }
+    Ok((seat, name.to_string()))
+}
+
+fn unknown_strategy(name: &str) -> String {
+    format!(
+        "Unknown strategy {name}, expected one of: {}",
+        STRATEGIES.join(", ")
+    )
+}
+
+// HAND vs UPCARD, the vs being optional
+fn parse_position(words: &[String]) -> Result<(Vec<u8>, u8), ArgsError> {
+    let (hand, upcard) = match words {
+        [hand, vs, upcard] if vs.eq_ignore_ascii_case("vs") => (hand, upcard),
+        [hand, upcard] => (hand, upcard),
+        _ => return invalid("analyze expects a hand and an upcard, e.g. 16 vs T".to_string()),
+    };
+    Ok((
+        parse_hand(hand).or_else(invalid)?,
+        parse_card(upcard).or_else(invalid)?,
+    ))
+}
+
+fn parse_payout(payout: &str) -> Result<(u32, u32), ArgsError> {
+    match payout.split_once(':').map(|(n, d)| (n.parse(), d.parse())) {
+        Some((Ok(numerator), Ok(denominator))) if denominator > 0 => Ok((numerator, denominator)),
+        _ => invalid(format!("Invalid blackjack payout {payout}, expected N:D")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Args, ArgsError> {
+        parse_args(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn no_arguments_gives_interactive_defaults() {
+        let args = parse("").unwrap();
+        assert_eq!(args.mode, Mode::Interactive);
+        assert_eq!(args.players, None);
+        assert_eq!(args.rules, TableRules::default());
+        assert_eq!(args.bankroll, DEFAULT_BANKROLL);
+        assert_eq!(args.seed, None);
+        assert_eq!(args.history, None);
+        assert_eq!(args.actions, Vec::new());
+        assert_eq!(args.bet, DEFAULT_BET);
+        assert_eq!(args.chart, None);
+        assert_eq!(args.export_chart, None);
+        assert_eq!(args.generate_chart, None);
+        assert!(!args.composition_exceptions);
+        assert_eq!(args.auto, Vec::new());
+        assert_eq!(args.rounds, DEFAULT_ROUNDS);
+        assert_eq!(args.target_ci, None);
+        assert_eq!(args.strategy, "basic");
+        assert_eq!(args.threads, 0);
+        assert!(args.betting.is_empty());
+        assert_eq!(args.count.name, "hi-lo");
+        assert_eq!(args.spread, DEFAULT_SPREAD);
+        assert!(args.deviations.is_empty());
+        assert_eq!(args.trip, None);
+        assert_eq!(args.target_risk, DEFAULT_TARGET_RISK);
+        assert_eq!(args.sessions, DEFAULT_SESSIONS);
+        assert_eq!(args.summary_json, None);
+        assert_eq!(args.true_count_csv, None);
+        assert_eq!(args.hand_log, None);
+        assert_eq!(args.svg, None);
+        assert_eq!(args.analyze, None);
+    }
+
+    #[test]
+    fn analyzed_position() {
+        let args = parse("analyze 16 vs T -d 2").unwrap();
+        assert_eq!(args.mode, Mode::Analyze);
+        assert_eq!(args.analyze, Some((vec![10, 6], 10)));
+        assert_eq!(args.rules.decks, 2);
+        let args = parse("analyze A7 9").unwrap();
+        assert_eq!(args.analyze, Some((vec![11, 7], 9)));
+    }
+
+    #[test]
+    fn simulation_options() {
+        let args =
+            parse("simulate --rounds 5000 --target-ci 0.5 --strategy never-bust --threads 4")
+                .unwrap();
+        assert_eq!(args.rounds, 5000);
+        assert_eq!(args.target_ci, Some(0.5));
+        assert_eq!(args.strategy, "never-bust");
+        assert_eq!(args.threads, 4);
+
+        let args = parse("simulate --betting flat,ramp --count zen --spread 12").unwrap();
+        assert_eq!(args.betting, vec!["flat", "ramp"]);
+        assert_eq!(args.count.name, "zen");
+        assert_eq!(args.spread, 12);
+        let args = parse("simulate --betting kelly --count hi-lo").unwrap();
+        assert_eq!(args.betting, vec!["kelly"]);
+
+        let args = parse("simulate --deviations illustrious-18,mine.csv").unwrap();
+        assert_eq!(args.deviations, vec!["illustrious-18", "mine.csv"]);
+
+        let args = parse("simulate --trip 5000 --target-risk 2.5 --sessions 200").unwrap();
+        assert_eq!(args.trip, Some(5000));
+        assert_eq!(args.target_risk, 2.5);
+        assert_eq!(args.sessions, 200);
+
+        let args =
+            parse("simulate --summary-json run.json --true-count-csv tc.csv --hand-log hands.csv")
+                .unwrap();
+        assert_eq!(args.summary_json, Some("run.json".to_string()));
+        assert_eq!(args.true_count_csv, Some("tc.csv".to_string()));
+        assert_eq!(args.hand_log, Some("hands.csv".to_string()));
+
+        let args = parse("plot --history hands.txt --svg session").unwrap();
+        assert_eq!(args.mode, Mode::Plot);
+        assert_eq!(args.svg, Some("session".to_string()));
+    }
+
+    #[test]
+    fn automated_seats() {
+        let args = parse("-p 3 --auto 2:basic --auto 3:mimic-dealer").unwrap();
+        assert_eq!(
+            args.auto,
+            vec![(1, "basic".to_string()), (2, "mimic-dealer".to_string())]
+        );
+    }
+
+    #[test]
+    fn chart_files() {
+        let args =
+            parse("--chart team.csv --export-chart team.toml --generate-chart h17.md").unwrap();
+        assert_eq!(args.chart, Some("team.csv".to_string()));
+        assert_eq!(args.export_chart, Some("team.toml".to_string()));
+        assert_eq!(args.generate_chart, Some("h17.md".to_string()));
+        assert!(
+            parse("-d 1 --composition-exceptions")
+                .unwrap()
+                .composition_exceptions
+        );
+    }
+
+    #[test]
+    fn mode_players_bankroll_and_seed() {
+        let args = parse("simulate --players 3 -b 500 --seed 42 --history hands.txt").unwrap();
+        assert_eq!(args.mode, Mode::Simulate);
+        assert_eq!(args.players, Some(3));
+        assert_eq!(args.bankroll, 500);
+        assert_eq!(args.seed, Some(42));
+        assert_eq!(args.history, Some("hands.txt".to_string()));
+    }
+
+    #[test]
+    fn replay_takes_an_action_log() {
+        let args = parse_args(
+            ["replay", "-s", "42", "--bet", "25", "--actions", "H S,d"].map(String::from),
+        )
+        .unwrap();
+        assert_eq!(args.mode, Mode::Replay);
+        assert_eq!(args.bet, 25);
+        assert_eq!(
+            args.actions,
+            vec![PlayerAction::Hit, PlayerAction::Stand, PlayerAction::Double]
+        );
+    }
+
+    #[test]
+    fn individual_rules_override_preset_in_any_order() {
+        let args = parse("--decks 2 --s17 --rules downtown --no-das --blackjack-pays 6:5").unwrap();
+        let downtown = TableRules::preset("downtown").unwrap();
+        assert_eq!(
+            args.rules,
+            TableRules {
+                decks: 2,
+                dealer_hits_soft_17: false,
+                double_after_split: false,
+                blackjack_payout: (6, 5),
+                ..downtown
+            }
+        );
+    }
+
+    #[test]
+    fn help_is_requested() {
+        assert_eq!(parse("--players 2 --help"), Err(ArgsError::Help));
+        assert_eq!(parse("-h"), Err(ArgsError::Help));
+    }
+
+    #[test]
+    fn invalid_arguments_are_reported() {
+        let cases = [
+            ("--players", "--players expects a value"),
+            ("--players 8", "--players must be between 1 and 7"),
+            ("--decks zero", "Invalid value for --decks: zero"),
+            ("--decks 9", "--decks must be between 1 and 8"),
+            ("--penetration 1.5", "--penetration must be between 0 and 1"),
+            ("--bankroll 0", "--bankroll must be positive"),
+            (
+                "--blackjack-pays 3",
+                "Invalid blackjack payout 3, expected N:D",
+            ),
+            (
+                "--blackjack-pays 3:0",
+                "Invalid blackjack payout 3:0, expected N:D",
+            ),
+            ("simulate replay", "Unexpected argument: replay"),
+            ("replay", "replay needs the --seed of the game"),
+            (
+                "plot --svg session",
+                "plot needs a --history file and an --svg prefix",
+            ),
+            ("--actions H,X", "Invalid action: x"),
+            ("--bet 0", "--bet must be positive"),
+            (
+                "--auto basic",
+                "Invalid value for --auto: basic, expected SEAT:STRATEGY",
+            ),
+            ("--auto 8:basic", "--auto seat must be between 1 and 7"),
+            (
+                "--auto 1:card-counter",
+                "Unknown strategy card-counter, expected one of: basic, never-bust, mimic-dealer, random",
+            ),
+            ("--rounds 0", "--rounds must be positive"),
+            ("--target-ci -1", "--target-ci must be positive"),
+            ("--threads 0", "--threads must be positive"),
+            (
+                "--strategy martingale",
+                "Unknown strategy martingale, expected one of: basic, never-bust, mimic-dealer, random",
+            ),
+            (
+                "--betting flat,labouchere",
+                "Unknown betting strategy labouchere, expected one of: flat, ramp, kelly, martingale, paroli, dalembert",
+            ),
+            (
+                "--count red-7",
+                "Unknown counting system red-7, expected one of: hi-lo, ko, hi-opt-1, hi-opt-2, omega-2, zen, wong-halves",
+            ),
+            (
+                "--betting kelly --count ko",
+                "kelly is calibrated for hi-lo true counts, not ko",
+            ),
+            ("--spread 0", "--spread must be positive"),
+            (
+                "--deviations fab-4 --strategy random",
+                "--deviations are played over the basic strategy, not random",
+            ),
+            ("--trip 0", "--trip must be positive"),
+            (
+                "--target-risk 100",
+                "--target-risk must be between 0 and 100",
+            ),
+            ("--sessions 0", "--sessions must be positive"),
+            (
+                "analyze 16",
+                "analyze expects a hand and an upcard, e.g. 16 vs T",
+            ),
+            ("analyze 16 vs X", "Invalid card: X"),
+            ("analyze 1X vs T", "Invalid hand: 1X"),
+            ("16 vs T", "Unexpected argument: 16"),
+            ("--fast", "Unexpected argument: --fast"),
+        ];
+        for (args, message) in cases {
+            assert_eq!(parse(args), Err(ArgsError::Invalid(message.to_string())));
+        }
+        assert!(
+            matches!(parse("--rules macau"), Err(ArgsError::Invalid(m)) if m.starts_with("Unknown rule preset macau"))
+        );
+    }
+}
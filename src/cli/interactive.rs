@@ -0,0 +1,603 @@
+use std::io::{self, BufRead, Write};
+
+use blackjack::{
+    analysis::advise,
+    chart::StrategyChart,
+    game::{Game, MAX_PLAYERS, Phase},
+    player::PlayerAction,
+    render::Renderer,
+    strategy::BasicStrategy,
+};
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Bet(u32),
+    Action(PlayerAction),
+    Hint,
+    Advise,
+    Save(String),
+    Load(String),
+    Quit,
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or("").to_lowercase();
+    let command = match command.as_str() {
+        "hit" | "h" => Command::Action(PlayerAction::Hit),
+        "stand" | "s" => Command::Action(PlayerAction::Stand),
+        "double" | "d" => Command::Action(PlayerAction::Double),
+        "split" | "p" => Command::Action(PlayerAction::Split),
+        "surrender" | "r" => Command::Action(PlayerAction::Surrender),
+        "hint" | "?" => Command::Hint,
+        "advise" | "cd" => Command::Advise,
+        "quit" | "q" => Command::Quit,
+        "bet" | "b" => match words.next().map(str::parse) {
+            Some(Ok(amount)) => Command::Bet(amount),
+            _ => return Err("Usage: bet <amount>".to_string()),
+        },
+        "save" => match words.next() {
+            Some(path) => Command::Save(path.to_string()),
+            None => return Err("Usage: save <file>".to_string()),
+        },
+        "load" => match words.next() {
+            Some(path) => Command::Load(path.to_string()),
+            None => return Err("Usage: load <file>".to_string()),
+        },
+        "" => return Err("Enter a command".to_string()),
+        other => return Err(format!("Unknown command: {other}")),
+    };
+    if words.next().is_some() {
+        return Err(format!("Too many arguments in: {}", line.trim()));
+    }
+    Ok(command)
+}
+
+// None when the input is closed
+fn ask<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    prompt: &str,
+) -> io::Result<Option<String>> {
+    write!(output, "{prompt}")?;
+    output.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+pub fn ask_number_of_players<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<Option<u8>> {
+    loop {
+        let Some(line) = ask(
+            input,
+            output,
+            &format!("Number of players [1-{MAX_PLAYERS}]: "),
+        )?
+        else {
+            return Ok(None);
+        };
+        match line.parse::<u8>() {
+            Ok(n) if (1..=MAX_PLAYERS).contains(&n) => return Ok(Some(n)),
+            _ => writeln!(output, "Enter a number between 1 and {MAX_PLAYERS}")?,
+        }
+    }
+}
+
+// hints come from the chart when given, from the basic strategy for the game rules otherwise
+pub fn run<R: BufRead, W: Write>(
+    game: &mut Game,
+    renderer: &Renderer,
+    chart: Option<&StrategyChart>,
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<()> {
+    loop {
+        if game.phase() == Phase::Betting
+            && let Err(error) = game.place_automated_bets()
+        {
+            writeln!(output, "{error}, game over")?;
+            return Ok(());
+        }
+        if let Some((hand, action)) = game.play_automated_action() {
+            writeln!(output, "Player {}: {action}", game.players[hand].seat + 1)?;
+            writeln!(output, "{}", renderer.render_table(game))?;
+            continue;
+        }
+        let prompt = match game.phase() {
+            Phase::Betting => match next_bettor(game) {
+                Some(seat) => format!(
+                    "Player {} (bankroll {}) > ",
+                    seat + 1,
+                    game.get_bankroll(seat)
+                ),
+                None => {
+                    game.deal_initial_cards();
+                    writeln!(output, "{}", renderer.render_table(game))?;
+                    if !offer_insurance(game, input, output)? {
+                        return Ok(());
+                    }
+                    continue;
+                }
+            },
+            Phase::PlayerTurn(hand) => {
+                let names: Vec<String> = game
+                    .legal_actions(hand)
+                    .iter()
+                    .map(PlayerAction::to_string)
+                    .collect();
+                format!(
+                    "Player {} [{}] > ",
+                    game.players[hand].seat + 1,
+                    names.join(", ")
+                )
+            }
+            Phase::DealerTurn => {
+                finish_round(game, renderer, output)?;
+                continue;
+            }
+            Phase::Settled => {
+                if let Some(seat) = (0..game.nb_seats()).find(|&seat| game.get_bankroll(seat) <= 0)
+                {
+                    writeln!(output, "Player {} is out of chips, game over", seat + 1)?;
+                    return Ok(());
+                }
+                "Play another round? [y/n] ".to_string()
+            }
+        };
+        let Some(line) = ask(input, output, &prompt)? else {
+            return Ok(());
+        };
+
+        let command = parse_command(&line);
+        match command {
+            Ok(Command::Quit) => return Ok(()),
+            Ok(Command::Save(path)) => {
+                save(game, &path, output)?;
+                continue;
+            }
+            Ok(Command::Load(path)) => {
+                if let Some(mut loaded) = load(&path, output)? {
+                    for observer in game.take_observers() {
+                        loaded.subscribe(observer);
+                    }
+                    for (seat, strategy) in game.take_strategies().into_iter().enumerate() {
+                        if let Some(strategy) = strategy
+                            && seat < loaded.nb_seats()
+                        {
+                            loaded.set_strategy(seat, strategy);
+                        }
+                    }
+                    *game = loaded;
+                    if game.phase() != Phase::Betting {
+                        writeln!(output, "{}", renderer.render_table(game))?;
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        match game.phase() {
+            Phase::Betting => {
+                let seat = next_bettor(game).unwrap();
+                let bankroll = game.get_bankroll(seat);
+                match command {
+                    Ok(Command::Bet(amount)) if amount > 0 && amount as i64 <= bankroll => {
+                        game.place_bet(seat, amount)
+                    }
+                    Ok(Command::Bet(_)) => {
+                        writeln!(output, "Bet must be between 1 and {bankroll}")?
+                    }
+                    Ok(_) => writeln!(output, "Place a bet first: bet <amount>")?,
+                    Err(error) => writeln!(output, "{error}")?,
+                }
+            }
+            Phase::PlayerTurn(hand) => match command {
+                Ok(Command::Action(action)) if game.legal_actions(hand).contains(&action) => {
+                    game.apply_action(hand, action);
+                    writeln!(output, "{}", renderer.render_table(game))?;
+                }
+                Ok(Command::Action(action)) => writeln!(output, "Cannot {action} now")?,
+                Ok(Command::Hint) => {
+                    let action = match chart {
+                        Some(chart) => chart.recommend_hand(game, hand),
+                        None => BasicStrategy::recommend_hand(game, hand),
+                    };
+                    writeln!(output, "Hint: {action}")?
+                }
+                Ok(Command::Advise) => writeln!(output, "Advice: {}", advise(game, hand))?,
+                Ok(_) => writeln!(output, "Bets are closed")?,
+                Err(error) => writeln!(output, "{error}")?,
+            },
+            Phase::Settled if line.to_lowercase().starts_with('y') => game.new_round(),
+            Phase::Settled | Phase::DealerTurn => return Ok(()),
+        }
+    }
+}
+
+// asks every human seat that can afford it whether to insure against the dealer ace, before
+// the peek, false once the input is closed
+fn offer_insurance<R: BufRead, W: Write>(
+    game: &mut Game,
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<bool> {
+    if !game.insurance_offered() {
+        return Ok(true);
+    }
+    game.place_automated_insurance();
+    for seat in 0..game.nb_seats() {
+        // no hand is split yet, the hand of the seat has its index
+        let amount = game.players[seat].bet / 2;
+        if game.is_automated(seat) || amount == 0 || amount as i64 > game.get_bankroll(seat) {
+            continue;
+        }
+        let prompt = format!("Player {} insurance for {amount}? [y/n] > ", seat + 1);
+        let Some(line) = ask(input, output, &prompt)? else {
+            return Ok(false);
+        };
+        if line.to_lowercase().starts_with('y') {
+            game.insure(seat);
+        }
+    }
+    Ok(true)
+}
+
+fn next_bettor(game: &Game) -> Option<usize> {
+    game.players.iter().position(|player| player.bet == 0)
+}
+
+fn finish_round<W: Write>(game: &mut Game, renderer: &Renderer, output: &mut W) -> io::Result<()> {
+    game.dealer_play();
+    let results = game.settle();
+    writeln!(output, "{}", renderer.render_table(game))?;
+    print_results(game, &results, output)
+}
+
+pub fn print_results<W: Write>(game: &Game, results: &[i64], output: &mut W) -> io::Result<()> {
+    for (i, net) in results.iter().enumerate() {
+        let seat = game.players[i].seat;
+        writeln!(
+            output,
+            "Player {}: {:?} ({net:+}), bankroll {}",
+            seat + 1,
+            game.get_outcome(i),
+            game.get_bankroll(seat),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+fn save<W: Write>(game: &Game, path: &str, output: &mut W) -> io::Result<()> {
+    match blackjack::save::save_game(game, path) {
+        Ok(()) => writeln!(output, "Game saved to {path}"),
+        Err(error) => writeln!(output, "Cannot save to {path}: {error}"),
+    }
+}
+
+#[cfg(feature = "serde")]
+fn load<W: Write>(path: &str, output: &mut W) -> io::Result<Option<Game>> {
+    match blackjack::save::load_game(path) {
+        Ok(game) => {
+            writeln!(output, "Game loaded from {path}")?;
+            Ok(Some(game))
+        }
+        Err(error) => {
+            writeln!(output, "Cannot load {path}: {error}")?;
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn save<W: Write>(_game: &Game, _path: &str, output: &mut W) -> io::Result<()> {
+    writeln!(output, "Saving needs the serde feature")
+}
+
+#[cfg(not(feature = "serde"))]
+fn load<W: Write>(_path: &str, output: &mut W) -> io::Result<Option<Game>> {
+    writeln!(output, "Loading needs the serde feature")?;
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blackjack::{
+        card::{Card, CardValue, Suit},
+        deck::Deck,
+        game::DEFAULT_BANKROLL,
+        rules::TableRules,
+    };
+
+    fn stacked_game(nb_players: u8, values: Vec<CardValue>) -> Game {
+        let mut game = Game::with_rules(nb_players, TableRules::default());
+        game.deck = Deck::from_vec(
+            values
+                .into_iter()
+                .rev()
+                .map(|value| Card::new(value, Suit::Spades))
+                .collect(),
+        );
+        game
+    }
+
+    fn play(game: &mut Game, script: &str) -> String {
+        let mut output = Vec::new();
+        run(
+            game,
+            &Renderer::plain(),
+            None,
+            &mut script.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn parse_commands_and_abbreviations() {
+        assert_eq!(parse_command("hit"), Ok(Command::Action(PlayerAction::Hit)));
+        assert_eq!(
+            parse_command(" S "),
+            Ok(Command::Action(PlayerAction::Stand))
+        );
+        assert_eq!(parse_command("p"), Ok(Command::Action(PlayerAction::Split)));
+        assert_eq!(parse_command("bet 25"), Ok(Command::Bet(25)));
+        assert_eq!(parse_command("quit"), Ok(Command::Quit));
+        assert_eq!(parse_command("?"), Ok(Command::Hint));
+        assert_eq!(parse_command("cd"), Ok(Command::Advise));
+        assert_eq!(
+            parse_command("save game.txt"),
+            Ok(Command::Save("game.txt".to_string()))
+        );
+        assert_eq!(
+            parse_command("load game.txt"),
+            Ok(Command::Load("game.txt".to_string()))
+        );
+        assert!(parse_command("save").is_err());
+        assert!(parse_command("bet").is_err());
+        assert!(parse_command("bet ten").is_err());
+        assert!(parse_command("hit me").is_err());
+        assert!(parse_command("fold").is_err());
+    }
+
+    #[test]
+    fn round_is_played_and_settled() {
+        let mut game = stacked_game(
+            1,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(7),
+                CardValue::Number(4),
+            ],
+        );
+        let output = play(&mut game, "bet 10\nhit\nstand\nn\n");
+        assert!(output.contains("Player 1: Win (+10), bankroll 1010"));
+        assert_eq!(game.get_bankroll(0), DEFAULT_BANKROLL + 10);
+    }
+
+    #[test]
+    fn multiple_seats_bet_and_play_in_turn() {
+        let mut game = stacked_game(
+            2,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(8),
+                CardValue::Number(10),
+                CardValue::Number(5),
+                CardValue::Number(10),
+                CardValue::Number(8),
+                CardValue::Number(10),
+            ],
+        );
+        let output = play(&mut game, "bet 10\nbet 20\nstand\nhit\nn\n");
+        assert!(output.contains("Player 1: Push (+0), bankroll 1000"));
+        assert!(output.contains("Player 2: Bust (-20), bankroll 980"));
+    }
+
+    #[test]
+    fn illegal_action_and_bad_bet_are_reported() {
+        let mut game = stacked_game(
+            1,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(7),
+            ],
+        );
+        let output = play(&mut game, "hit\nbet 5000\nbet 10\nsplit\nstand\nn\n");
+        assert!(output.contains("Place a bet first"));
+        assert!(output.contains("Bet must be between 1 and 1000"));
+        assert!(output.contains("Cannot split now"));
+        assert!(output.contains("Player 1: Lose (-10)"));
+    }
+
+    #[test]
+    fn hint_shows_the_basic_strategy_action() {
+        let mut game = stacked_game(
+            1,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(5),
+            ],
+        );
+        let output = play(&mut game, "bet 10\nhint\nstand\nn\n");
+        assert!(output.contains("Hint: surrender"));
+    }
+
+    #[test]
+    fn advice_uses_the_exact_cards() {
+        let mut game = stacked_game(
+            1,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(5),
+            ],
+        );
+        // enough cards under the stacked ones for the dealer to draw
+        game.deck.cards.splice(0..0, Deck::from_decks(1).cards);
+        let output = play(&mut game, "bet 10\nadvise\nstand\nn\n");
+        assert!(output.contains("Advice: surrender (-0.5000)"));
+    }
+
+    #[test]
+    fn automated_seat_plays_without_prompts() {
+        let mut game = stacked_game(
+            2,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(7),
+                CardValue::Number(10),
+                CardValue::Number(2),
+                CardValue::Number(10),
+                CardValue::Number(8),
+                CardValue::Number(7),
+            ],
+        );
+        game.set_strategy(1, Box::new(blackjack::autoplay::MimicDealer { bet: 5 }));
+        let output = play(&mut game, "bet 10\nstand\nn\n");
+        assert_eq!(output.matches("Player 2 (bankroll").count(), 0);
+        assert!(output.contains("Player 2: hit"));
+        assert!(output.contains("Player 2: stand"));
+        assert!(output.contains("Player 1: Lose (-10), bankroll 990"));
+        assert!(output.contains("Player 2: Win (+5), bankroll 1005"));
+    }
+
+    #[test]
+    fn game_ends_when_an_automated_seat_is_out_of_chips() {
+        let mut game = Game::with_rules(2, TableRules::default());
+        game.set_bankroll(1, 0);
+        game.set_strategy(1, Box::new(blackjack::autoplay::MimicDealer { bet: 5 }));
+        let output = play(&mut game, "bet 10\n");
+        assert!(output.contains("Player 2 is out of chips, game over"));
+        assert!(!output.contains("Player 1 (bankroll"));
+    }
+
+    #[test]
+    fn human_seats_are_offered_insurance() {
+        let mut game = stacked_game(
+            2,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(9),
+                CardValue::Ace,
+                CardValue::King,
+            ],
+        );
+        let output = play(&mut game, "bet 10\nbet 20\ny\nn\nn\n");
+        assert!(output.contains("Player 1 insurance for 5? [y/n] > "));
+        assert!(output.contains("Player 2 insurance for 10? [y/n] > "));
+        assert!(output.contains("Player 1: Lose (+0), bankroll 1000"));
+        assert!(output.contains("Player 2: Lose (-20), bankroll 980"));
+
+        let mut game = stacked_game(
+            1,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(9),
+            ],
+        );
+        let output = play(&mut game, "bet 10\nstand\nn\n");
+        assert!(!output.contains("insurance"));
+    }
+
+    #[test]
+    fn another_round_is_played_on_yes() {
+        let mut game = stacked_game(
+            1,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(7),
+                CardValue::Number(10),
+                CardValue::Number(8),
+                CardValue::Number(2),
+                CardValue::Number(3),
+                CardValue::Number(4),
+                CardValue::Number(5),
+            ],
+        );
+        let output = play(&mut game, "bet 10\nstand\ny\nq\n");
+        assert_eq!(output.matches("Play another round?").count(), 1);
+        assert_eq!(output.matches("Player 1 (bankroll").count(), 2);
+    }
+
+    #[test]
+    fn game_ends_when_input_is_closed() {
+        let mut game = stacked_game(
+            1,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(7),
+            ],
+        );
+        let output = play(&mut game, "bet 10\n");
+        assert!(!output.contains("Play another round?"));
+    }
+
+    #[test]
+    fn number_of_players_is_asked_until_valid() {
+        let mut output = Vec::new();
+        let players = ask_number_of_players(&mut "0\nabc\n3\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(players, Some(3));
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("Enter a number between 1 and 7").count(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn game_saved_mid_round_is_resumed_after_load() {
+        let path =
+            std::env::temp_dir().join(format!("blackjack-interactive-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut game = stacked_game(
+            1,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(7),
+                CardValue::Number(4),
+            ],
+        );
+        let output = play(&mut game, &format!("bet 10\nsave {path}\nq\n"));
+        assert!(output.contains(&format!("Game saved to {path}")));
+
+        let mut other = Game::with_rules(3, TableRules::default());
+        let output = play(&mut other, &format!("load {path}\nhit\nstand\nn\n"));
+        std::fs::remove_file(path).unwrap();
+        assert!(output.contains(&format!("Game loaded from {path}")));
+        assert!(output.contains("Player 1: Win (+10), bankroll 1010"));
+        assert_eq!(other.nb_seats(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn failed_load_keeps_the_current_game() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        let output = play(&mut game, "load /nonexistent/blackjack.txt\nq\n");
+        assert!(output.contains("Cannot load /nonexistent/blackjack.txt"));
+        assert_eq!(game.phase(), Phase::Betting);
+    }
+}
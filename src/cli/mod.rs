@@ -0,0 +1,206 @@
+use std::{fs::OpenOptions, io, process::ExitCode};
+
+use blackjack::{
+    BasicStrategy, Game, Renderer,
+    analysis::optimal_chart,
+    autoplay::strategy_by_name,
+    chart::StrategyChart,
+    deviations::DeviationSet,
+    history::HandHistoryWriter,
+    replay::Replay,
+    simulation::{SimulationConfig, StopCondition},
+};
+
+use crate::cli::args::{ArgsError, Mode, USAGE};
+
+pub mod analyze;
+pub mod args;
+pub mod interactive;
+pub mod plot;
+pub mod replay;
+pub mod simulate;
+
+#[cfg_attr(test, mutants::skip)]
+pub fn run<I: IntoIterator<Item = String>>(args: I) -> ExitCode {
+    let args = match args::parse_args(args) {
+        Ok(args) => args,
+        Err(ArgsError::Help) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(ArgsError::Invalid(message)) => {
+            eprintln!("{message}\nRun with --help to see the available options");
+            return ExitCode::from(2);
+        }
+    };
+
+    let chart = match &args.chart {
+        Some(path) => match StrategyChart::load(path) {
+            Ok(chart) => Some(chart),
+            Err(error) => {
+                eprintln!("Cannot load the chart {path}: {error}");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+    if let Some(path) = &args.generate_chart {
+        return save_chart(&optimal_chart(&args.rules), path);
+    }
+    if args.composition_exceptions {
+        return match analyze::exceptions(&args.rules, &mut io::stdout()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{error}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if let Some(path) = &args.export_chart {
+        let chart = chart.unwrap_or_else(|| BasicStrategy::chart(&args.rules));
+        return save_chart(&chart, path);
+    }
+
+    if let Some((hand, upcard)) = &args.analyze {
+        return match analyze::run(hand, *upcard, &args.rules, &mut io::stdout()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{error}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if args.mode == Mode::Simulate {
+        let stop = match args.target_ci {
+            Some(ci) => StopCondition::Confidence {
+                half_width: ci / 100.0,
+                max_rounds: args.rounds,
+            },
+            None => StopCondition::Rounds(args.rounds),
+        };
+        let config = SimulationConfig {
+            rules: args.rules.clone(),
+            seats: args.players.unwrap_or(1),
+            seed: args.seed.unwrap_or_else(rand::random),
+            stop,
+            count: args.count,
+        };
+        let mut output = io::stdout();
+        let deviations = match load_deviations(&args.deviations) {
+            Ok(deviations) => deviations,
+            Err(error) => {
+                eprintln!("{error}");
+                return ExitCode::FAILURE;
+            }
+        };
+        return match simulate::run(
+            &config,
+            &args,
+            chart.as_ref(),
+            deviations.as_ref(),
+            &mut output,
+        ) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{error}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if args.mode == Mode::Plot {
+        let (Some(history), Some(prefix)) = (&args.history, &args.svg) else {
+            unreachable!("plot needs a history and a prefix");
+        };
+        return match plot::run(history, prefix) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{error}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let renderer = Renderer::for_terminal();
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout();
+    let result = (|| {
+        let nb_players = match args.players {
+            Some(nb_players) => nb_players,
+            None if args.mode == Mode::Replay => 1,
+            None => match interactive::ask_number_of_players(&mut input, &mut output)? {
+                Some(nb_players) => nb_players,
+                None => return Ok(()),
+            },
+        };
+        let mut game = match args.seed {
+            Some(seed) => Game::with_seed(nb_players, args.rules, seed),
+            None => Game::with_rules(nb_players, args.rules),
+        };
+        for seat in 0..game.nb_seats() {
+            game.set_bankroll(seat, args.bankroll);
+        }
+        for (seat, name) in &args.auto {
+            if *seat >= game.nb_seats() {
+                let message = format!("No seat {} for --auto with {nb_players} players", seat + 1);
+                return Err(io::Error::other(message));
+            }
+            let seed = game.get_seed().wrapping_add(*seat as u64);
+            let strategy = strategy_by_name(name, &game.rules, args.bet, seed).unwrap();
+            game.set_strategy(*seat, strategy);
+        }
+        if let Some(path) = &args.history {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            game.subscribe(Box::new(HandHistoryWriter::new(file)));
+        }
+        if args.mode == Mode::Replay {
+            let replay = Replay::new(game, args.bet, args.actions).map_err(io::Error::other)?;
+            return replay::run(replay, &renderer, &mut output);
+        }
+        // starts round 1 once the observers are registered so it is recorded too
+        game.new_round();
+        interactive::run(
+            &mut game,
+            &renderer,
+            chart.as_ref(),
+            &mut input,
+            &mut output,
+        )
+    })();
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// None without any deviation, the sets are looked up in order
+fn load_deviations(names: &[String]) -> Result<Option<DeviationSet>, String> {
+    if names.is_empty() {
+        return Ok(None);
+    }
+    let mut deviations = DeviationSet::default();
+    for name in names {
+        let set = match DeviationSet::by_name(name) {
+            Some(set) => set,
+            None => DeviationSet::load(name)
+                .map_err(|error| format!("Cannot load the deviations {name}: {error}"))?,
+        };
+        deviations.extend(set);
+    }
+    Ok(Some(deviations))
+}
+
+fn save_chart(chart: &StrategyChart, path: &str) -> ExitCode {
+    match chart.save(path) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Cannot write the chart {path}: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
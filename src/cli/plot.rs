@@ -0,0 +1,137 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+};
+
+use blackjack::{
+    history::{parse_history, seat_bankrolls},
+    risk::NetDistribution,
+    simulation::{SimulationConfig, SimulationStats},
+    svg::{Bar, Series, bar_chart, fan_chart, line_chart},
+};
+
+use crate::cli::args::Args;
+
+// hands of the charted sessions without --trip
+pub const DEFAULT_CHART_HANDS: u64 = 10_000;
+
+// percentiles of the fan of sessions
+const PERCENTILES: [f64; 5] = [5.0, 25.0, 50.0, 75.0, 95.0];
+
+// points of the fan along the hands
+const FAN_POINTS: usize = 200;
+
+// true counts met less often are left out of the chart, their EV being mostly noise
+const MIN_FREQUENCY: f64 = 0.005;
+
+// the bankroll of every seat over the rounds of a hand history, to PREFIX-bankroll.svg
+pub fn run(history: &str, prefix: &str) -> io::Result<()> {
+    let file = File::open(history)
+        .map_err(|error| io::Error::other(format!("Cannot read {history}: {error}")))?;
+    let records = parse_history(BufReader::new(file))
+        .map_err(|error| io::Error::other(format!("Cannot read {history}: {error}")))?;
+    let series: Vec<Series> = seat_bankrolls(&records)
+        .into_iter()
+        .enumerate()
+        .map(|(seat, bankrolls)| Series {
+            name: format!("seat {}", seat + 1),
+            points: points(&bankrolls),
+        })
+        .collect();
+    let chart = line_chart("Bankroll over the rounds", "round", "chips", &series);
+    write(&format!("{prefix}-bankroll.svg"), &chart)
+}
+
+// a session drawn from the hands of the simulation, the percentiles of many of them and the
+// EV by true count, to PREFIX-bankroll.svg, PREFIX-fan.svg and PREFIX-true-count.svg
+pub fn simulation_charts(
+    prefix: &str,
+    name: &str,
+    config: &SimulationConfig,
+    stats: &SimulationStats,
+    args: &Args,
+) -> io::Result<()> {
+    let hands = args.trip.unwrap_or(DEFAULT_CHART_HANDS);
+    if let Some(distribution) = NetDistribution::from_stats(stats) {
+        let session = distribution.trajectory(args.bankroll, hands, config.seed);
+        let series = [Series {
+            name: name.to_string(),
+            points: points(&session),
+        }];
+        let title = format!("Bankroll over a session of {hands} hands, {name}");
+        write(
+            &format!("{prefix}-bankroll.svg"),
+            &line_chart(&title, "hand", "chips", &series),
+        )?;
+
+        let (at, percentiles) = distribution.percentiles(
+            args.bankroll,
+            hands,
+            args.sessions,
+            config.seed,
+            &PERCENTILES,
+            FAN_POINTS,
+        );
+        let xs: Vec<f64> = at.iter().map(|hand| *hand as f64).collect();
+        let percentiles: Vec<(f64, Vec<f64>)> = PERCENTILES.into_iter().zip(percentiles).collect();
+        let title = format!("Bankroll of {} sessions, {name}", args.sessions);
+        write(
+            &format!("{prefix}-fan.svg"),
+            &fan_chart(&title, "hand", "chips", &xs, &percentiles),
+        )?;
+    }
+
+    let bars: Vec<Bar> = stats
+        .true_counts
+        .iter()
+        .filter(|(_, by_count)| by_count.hands as f64 >= MIN_FREQUENCY * stats.hands as f64)
+        .map(|(true_count, by_count)| Bar {
+            label: format!("{true_count:+}"),
+            value: by_count.edge() * 100.0,
+            error: by_count.edge_confidence() * 100.0,
+        })
+        .collect();
+    let title = format!("EV by true count of {}, {name}", config.count.name);
+    write(
+        &format!("{prefix}-true-count.svg"),
+        &bar_chart(&title, "true count", "EV in % of the bet", &bars),
+    )
+}
+
+fn points(bankrolls: &[i64]) -> Vec<(f64, f64)> {
+    bankrolls
+        .iter()
+        .enumerate()
+        .map(|(x, bankroll)| (x as f64, *bankroll as f64))
+        .collect()
+}
+
+fn write(path: &str, svg: &str) -> io::Result<()> {
+    fs::write(path, svg).map_err(|error| io::Error::other(format!("Cannot write {path}: {error}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_is_plotted() {
+        let dir = std::env::temp_dir().join(format!("blackjack-plot-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let history = dir.join("hands.txt");
+        fs::write(
+            &history,
+            "ROUND 1\nSEAT 1 100\nSEAT 2 50\nPAYOUT 1 1 +10\nPAYOUT 2 2 -10\nEND\n",
+        )
+        .unwrap();
+        let prefix = dir.join("session").to_string_lossy().into_owned();
+        run(&history.to_string_lossy(), &prefix).unwrap();
+        let svg = fs::read_to_string(format!("{prefix}-bankroll.svg")).unwrap();
+        assert!(svg.contains(">seat 1<") && svg.contains(">seat 2<"));
+
+        let missing = dir.join("missing.txt").to_string_lossy().into_owned();
+        let error = run(&missing, &prefix).unwrap_err().to_string();
+        assert!(error.starts_with(&format!("Cannot read {missing}: ")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+use std::io::{self, Write};
+
+use blackjack::{game::Phase, render::Renderer, replay::Replay};
+
+use crate::cli::interactive::print_results;
+
+// prints the table after every action of the log and the results of each settled round
+pub fn run<W: Write>(mut replay: Replay, renderer: &Renderer, output: &mut W) -> io::Result<()> {
+    writeln!(output, "{}", renderer.render_table(replay.get_game()))?;
+    while let Some((hand, action)) = replay.step().map_err(io::Error::other)? {
+        let game = replay.get_game();
+        writeln!(
+            output,
+            "Round {}, player {}: {action}",
+            game.get_round(),
+            game.players[hand].seat + 1
+        )?;
+        writeln!(output, "{}", renderer.render_table(game))?;
+        if game.phase() == Phase::Settled {
+            print_results(game, replay.get_results(), output)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blackjack::{game::Game, replay::parse_actions, rules::TableRules};
+
+    #[test]
+    fn every_action_is_printed() {
+        let game = Game::with_seed(1, TableRules::default(), 42);
+        let replay = Replay::new(game, 10, parse_actions("S S S").unwrap()).unwrap();
+        let mut output = Vec::new();
+        run(replay, &Renderer::plain(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches(", player 1: stand").count(), 3);
+        assert_eq!(output.matches("Player 1: ").count(), 3);
+    }
+
+    #[test]
+    fn illegal_action_stops_the_replay() {
+        let rules = TableRules {
+            surrender: false,
+            ..Default::default()
+        };
+        let game = Game::with_seed(1, rules, 42);
+        let replay = Replay::new(game, 10, parse_actions("R").unwrap()).unwrap();
+        let error = run(replay, &Renderer::plain(), &mut Vec::new()).unwrap_err();
+        assert!(error.to_string().starts_with("step 1: cannot surrender"));
+    }
+}
@@ -0,0 +1,431 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use blackjack::{
+    BasicStrategy,
+    autoplay::{ChartStrategy, PlayerStrategy, strategy_by_name},
+    betting::{BettingPlayer, betting_by_name},
+    chart::StrategyChart,
+    deviations::{DeviationSet, DeviationStrategy},
+    export::{RunSummary, write_json, write_true_count_csv},
+    risk::{Horizon, LIFETIME_HANDS, RiskReport},
+    simulation::{SimulationConfig, SimulationStats, simulate_parallel, simulate_parallel_logged},
+};
+
+use crate::cli::{args::Args, plot};
+
+// every seat plays the chart when one is given, the named strategy otherwise, with the
+// deviations for its count. each betting strategy is simulated in turn on the same cards.
+pub fn run<W: Write>(
+    config: &SimulationConfig,
+    args: &Args,
+    chart: Option<&StrategyChart>,
+    deviations: Option<&DeviationSet>,
+    output: &mut W,
+) -> io::Result<()> {
+    let strategy = if chart.is_some() {
+        "chart"
+    } else {
+        args.strategy.as_str()
+    };
+    let deviated = if deviations.is_some() {
+        " with deviations"
+    } else {
+        ""
+    };
+    let seats = format!(
+        "Seed {}, {} seat(s) playing {strategy}{deviated}",
+        config.seed, config.seats
+    );
+    // the runs are named after their betting strategy, or the strategy without any
+    let bettings: Vec<Option<&str>> = match args.betting.is_empty() {
+        true => vec![None],
+        false => args
+            .betting
+            .iter()
+            .map(|name| Some(name.as_str()))
+            .collect(),
+    };
+    let mut summaries = Vec::new();
+    for (i, betting) in bettings.iter().enumerate() {
+        let name = betting.unwrap_or(strategy);
+        let stats = match &args.hand_log {
+            Some(path) => {
+                let path = run_path(path, name, bettings.len());
+                let mut log = BufWriter::new(create(&path)?);
+                simulate(config, args, chart, deviations, *betting, Some(&mut log))
+                    .map_err(|error| cannot_write(&path, error))?
+            }
+            None => simulate(config, args, chart, deviations, *betting, None)?,
+        };
+        if i > 0 {
+            writeln!(output)?;
+        }
+        match betting {
+            Some(betting) => writeln!(
+                output,
+                "{seats}, betting {betting} with {}, unit {} up to {}",
+                args.count.name,
+                args.bet,
+                args.bet.saturating_mul(args.spread)
+            )?,
+            None => writeln!(output, "{seats}, bet {}", args.bet)?,
+        }
+        let risk = RiskReport::new(
+            &stats,
+            args.bankroll,
+            args.trip.map_or(Horizon::Lifetime, Horizon::Trip),
+            args.target_risk / 100.0,
+            args.sessions,
+            config.seed,
+            args.threads,
+        );
+        report(&stats, &risk, output)?;
+        if let Some(prefix) = &args.svg {
+            let prefix = match bettings.len() {
+                1 => prefix.clone(),
+                _ => format!("{prefix}-{name}"),
+            };
+            plot::simulation_charts(&prefix, name, config, &stats, args)?;
+        }
+        summaries.push(RunSummary::new(name, config, &stats, risk));
+    }
+    if let Some(path) = &args.summary_json {
+        write_json(&summaries, BufWriter::new(create(path)?))
+            .map_err(|error| cannot_write(path, error))?;
+    }
+    if let Some(path) = &args.true_count_csv {
+        write_true_count_csv(&summaries, BufWriter::new(create(path)?))
+            .map_err(|error| cannot_write(path, error))?;
+    }
+    if let Some(deviations) = deviations {
+        let betting = args.betting.first().map(String::as_str);
+        writeln!(
+            output,
+            "\nEV added by each deviation alone, betting {}:",
+            betting.unwrap_or("flat")
+        )?;
+        let base = simulate(
+            config,
+            args,
+            chart,
+            Some(&DeviationSet::default()),
+            betting,
+            None,
+        )?;
+        for deviation in &deviations.deviations {
+            let single = DeviationSet {
+                deviations: vec![*deviation],
+            };
+            let stats = simulate(config, args, chart, Some(&single), betting, None)?;
+            let confidence = stats.edge_confidence().hypot(base.edge_confidence());
+            writeln!(
+                output,
+                "  {:<28} {:+.4}% ± {:.4}%",
+                deviation.to_string(),
+                (stats.edge() - base.edge()) * 100.0,
+                confidence * 100.0
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn simulate(
+    config: &SimulationConfig,
+    args: &Args,
+    chart: Option<&StrategyChart>,
+    deviations: Option<&DeviationSet>,
+    betting: Option<&str>,
+    log: Option<&mut (dyn Write + Send)>,
+) -> io::Result<SimulationStats> {
+    let play = |seed| -> Box<dyn PlayerStrategy> {
+        match (chart, deviations) {
+            (_, Some(deviations)) => Box::new(DeviationStrategy::new(
+                chart
+                    .cloned()
+                    .unwrap_or_else(|| BasicStrategy::chart(&config.rules)),
+                deviations.clone(),
+                Box::new(args.count),
+                config.rules.decks,
+                args.bet,
+            )),
+            (Some(chart), None) => Box::new(ChartStrategy {
+                chart: chart.clone(),
+                bet: args.bet,
+            }),
+            (None, None) => {
+                strategy_by_name(&args.strategy, &config.rules, args.bet, seed).unwrap()
+            }
+        }
+    };
+    let strategy = |_, seed| -> Box<dyn PlayerStrategy> {
+        match betting {
+            Some(name) => Box::new(BettingPlayer::new(
+                play(seed),
+                betting_by_name(name, args.bet, args.spread).unwrap(),
+                Box::new(args.count),
+                config.rules.decks,
+                args.bankroll,
+            )),
+            None => play(seed),
+        }
+    };
+    match log {
+        Some(log) => simulate_parallel_logged(config, args.threads, strategy, log),
+        None => Ok(simulate_parallel(config, args.threads, strategy)),
+    }
+}
+
+// the file of a run among several gets its name, e.g. hands-ramp.csv
+fn run_path(path: &str, name: &str, runs: usize) -> String {
+    if runs == 1 {
+        return path.to_string();
+    }
+    let path = Path::new(path);
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("");
+    let file = match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => format!("{stem}-{name}.{extension}"),
+        None => format!("{stem}-{name}"),
+    };
+    path.with_file_name(file).to_string_lossy().into_owned()
+}
+
+fn create(path: &str) -> io::Result<File> {
+    File::create(path).map_err(|error| cannot_write(path, error))
+}
+
+fn cannot_write(path: &str, error: io::Error) -> io::Error {
+    io::Error::other(format!("Cannot write {path}: {error}"))
+}
+
+// the stats followed by the risk of ruin of the bankroll, by formula and over sessions drawn
+// from the hands of the simulation
+fn report<W: Write>(stats: &SimulationStats, risk: &RiskReport, output: &mut W) -> io::Result<()> {
+    write!(output, "{stats}")?;
+    let horizon = risk.horizon();
+    write!(
+        output,
+        "Risk of ruin with a bankroll of {}, {horizon}: {:.4}% by formula",
+        risk.bankroll,
+        risk.risk_of_ruin * 100.0
+    )?;
+    if let Some(sessions) = risk.simulated {
+        write!(
+            output,
+            ", {:.4}% ± {:.4}% over {} sessions",
+            sessions.risk() * 100.0,
+            sessions.confidence() * 100.0,
+            sessions.sessions
+        )?;
+        if horizon == Horizon::Lifetime {
+            write!(output, " of up to {LIFETIME_HANDS} hands")?;
+        }
+    }
+    writeln!(output)?;
+    write!(
+        output,
+        "Bankroll for a {}% risk of ruin, {horizon}: ",
+        (risk.target_risk * 1e6).round() / 1e4
+    )?;
+    match risk.bankroll_for_target {
+        Some(bankroll) => writeln!(output, "{} chips", bankroll.ceil())?,
+        None => writeln!(output, "none without an edge")?,
+    }
+    match risk.n0 {
+        Some(n0) => writeln!(
+            output,
+            "N0: {} hands to win one standard deviation",
+            n0.ceil()
+        ),
+        None => writeln!(output, "N0: none without an edge"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::args::parse_args;
+    use blackjack::{rules::TableRules, simulation::StopCondition};
+
+    fn config(rounds: u64) -> SimulationConfig {
+        SimulationConfig {
+            rules: TableRules::default(),
+            seats: 1,
+            seed: 5,
+            stop: StopCondition::Rounds(rounds),
+            count: blackjack::counting::HI_LO,
+        }
+    }
+
+    fn args(line: &str) -> Args {
+        parse_args(line.split_whitespace().map(String::from)).unwrap()
+    }
+
+    fn output(config: &SimulationConfig, line: &str, chart: Option<&StrategyChart>) -> String {
+        let mut output = Vec::new();
+        run(config, &args(line), chart, None, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn report_is_printed() {
+        let output = output(
+            &config(200),
+            "simulate --strategy never-bust --threads 2",
+            None,
+        );
+        assert!(output.starts_with("Seed 5, 1 seat(s) playing never-bust, bet 10\nRounds: 200"));
+        assert!(output.contains("Hands per shoe: "));
+        assert!(output.contains("\nRisk of ruin with a bankroll of 1000, lifetime: 100.0000% by formula, 100.0000% ± 0.0000% over 1000 sessions of up to 100000 hands\n"));
+        assert!(
+            output.contains("\nBankroll for a 5% risk of ruin, lifetime: none without an edge\n")
+        );
+        assert!(output.ends_with("\nN0: none without an edge\n"));
+    }
+
+    #[test]
+    fn risk_of_a_trip_is_reported() {
+        let output = output(
+            &config(2000),
+            "simulate --trip 100 --target-risk 1 --sessions 400 -b 100",
+            None,
+        );
+        let risk = output
+            .lines()
+            .find(|line| line.starts_with("Risk of ruin"))
+            .unwrap();
+        assert!(
+            risk.starts_with("Risk of ruin with a bankroll of 100, trip of 100 hands: "),
+            "{risk}"
+        );
+        assert!(risk.ends_with(" over 400 sessions"), "{risk}");
+        assert!(output.contains("\nBankroll for a 1% risk of ruin, trip of 100 hands: "));
+        assert!(output.contains(" chips\n"));
+    }
+
+    #[test]
+    fn charts_are_drawn() {
+        let dir = std::env::temp_dir().join(format!("blackjack-charts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let prefix = dir.join("run").to_string_lossy().into_owned();
+        output(
+            &config(1000),
+            &format!("simulate --betting flat,ramp --trip 300 --sessions 50 --svg {prefix}"),
+            None,
+        );
+        for name in ["flat", "ramp"] {
+            for chart in ["bankroll", "fan", "true-count"] {
+                let svg = std::fs::read_to_string(format!("{prefix}-{name}-{chart}.svg")).unwrap();
+                assert!(svg.starts_with("<svg "), "{name} {chart}");
+            }
+        }
+        let fan = std::fs::read_to_string(format!("{prefix}-ramp-fan.svg")).unwrap();
+        assert!(fan.contains(">Bankroll of 50 sessions, ramp<"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chart_replaces_the_strategy() {
+        let config = config(200);
+        let chart = blackjack::BasicStrategy::chart(&config.rules);
+        let with_chart = output(
+            &config,
+            "simulate --strategy random --threads 2 --sessions 10",
+            Some(&chart),
+        );
+        let basic = output(&config, "simulate --threads 1 --sessions 10", None);
+        assert!(with_chart.contains("playing chart"));
+        assert_eq!(
+            with_chart.lines().skip(1).collect::<Vec<_>>(),
+            basic.lines().skip(1).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn every_betting_strategy_is_reported() {
+        let config = config(300);
+        let output = output(
+            &config,
+            "simulate --betting flat,martingale --count ko --spread 4 --sessions 10",
+            None,
+        );
+        let reports: Vec<&str> = output.split("\n\n").collect();
+        assert_eq!(reports.len(), 2);
+        assert!(reports[0].starts_with(
+            "Seed 5, 1 seat(s) playing basic, betting flat with ko, unit 10 up to 40\n"
+        ));
+        assert!(reports[1].starts_with("Seed 5, 1 seat(s) playing basic, betting martingale"));
+        // same cards and plays, only the bets differ
+        let flat = output.lines().nth(1).unwrap();
+        let unbet = super::tests::output(&config, "simulate --sessions 10", None);
+        assert_eq!(flat, unbet.lines().nth(1).unwrap());
+        assert!(reports[1].contains("Standard deviation per hand: "));
+    }
+
+    #[test]
+    fn results_are_exported() {
+        let dir = std::env::temp_dir().join(format!("blackjack-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = |file: &str| dir.join(file).to_string_lossy().into_owned();
+        output(
+            &config(300),
+            &format!(
+                "simulate --betting flat,ramp --sessions 10 --true-count-csv {} --hand-log {}",
+                path("tc.csv"),
+                path("hands.csv")
+            ),
+            None,
+        );
+
+        let true_counts = std::fs::read_to_string(path("tc.csv")).unwrap();
+        assert!(true_counts.starts_with("run,true_count,hands,"));
+        let hands: u64 = true_counts
+            .lines()
+            .skip(1)
+            .filter(|line| line.starts_with("ramp,"))
+            .map(|line| line.split(',').nth(2).unwrap().parse::<u64>().unwrap())
+            .sum();
+        assert_eq!(hands, 300);
+
+        for name in ["flat", "ramp"] {
+            let log = std::fs::read_to_string(path(&format!("hands-{name}.csv"))).unwrap();
+            let mut lines = log.lines();
+            assert_eq!(lines.next(), Some(blackjack::simulation::HAND_LOG_HEADER));
+            let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+            assert!(rows.len() >= 300);
+            assert_eq!(rows[0][..3], ["1", "1", "1"]);
+            assert_eq!(rows.last().unwrap()[0], "300");
+            assert!(rows.iter().all(|row| row.len() == 10));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn every_deviation_is_measured() {
+        let set = DeviationSet::fab_4();
+        let mut output = Vec::new();
+        run(
+            &config(300),
+            &args("simulate --deviations fab-4 --sessions 10"),
+            None,
+            Some(&set),
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("Seed 5, 1 seat(s) playing basic with deviations, bet 10\n"));
+        let (_, gains) = output
+            .split_once("\nEV added by each deviation alone, betting flat:\n")
+            .unwrap();
+        let lines: Vec<&str> = gains.lines().collect();
+        assert_eq!(lines.len(), set.deviations.len());
+        assert!(lines[0].starts_with("  14 vs T: surrender at >=3 "));
+    }
+}
@@ -0,0 +1,305 @@
+//! Card counting: tag values for every rank and a tracker keeping the count of a shoe.
+//!
+//! Every ten valued card has the same tag, so tags are looked up by card value as
+//! `Card::get_card_value` gives it, 2 to 10 and 11 for an ace.
+
+use crate::{
+    card::Card,
+    events::{GameEvent, GameObserver},
+    game::Game,
+};
+
+pub const CARDS_PER_DECK: u16 = 52;
+
+pub trait CountingSystem {
+    fn name(&self) -> &str;
+
+    // tag of a card value, from 2 to 11 for the aces
+    fn tag(&self, value: u8) -> f64;
+
+    // count of the shoe before any card is seen
+    fn initial_count(&self, _nb_decks: u8) -> f64 {
+        0.0
+    }
+
+    fn tag_card(&self, card: &Card) -> f64 {
+        self.tag(card.get_card_value())
+    }
+}
+
+// a counting system given by its tags, from 2 to 9, the tens then the aces
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TagSystem {
+    pub name: &'static str,
+    pub tags: [f64; 10],
+}
+
+impl TagSystem {
+    // the tags of a whole deck add up to zero
+    pub fn is_balanced(&self) -> bool {
+        self.deck_total() == 0.0
+    }
+
+    fn deck_total(&self) -> f64 {
+        self.tags[..8].iter().sum::<f64>() * 4.0 + self.tags[8] * 16.0 + self.tags[9] * 4.0
+    }
+}
+
+impl CountingSystem for TagSystem {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn tag(&self, value: u8) -> f64 {
+        if !(2..=11).contains(&value) {
+            panic!("Card value must be between 2 and 11");
+        }
+        self.tags[value as usize - 2]
+    }
+
+    // an unbalanced count starts low enough to reach +4 once the whole shoe is seen, the
+    // usual starting count of KO
+    fn initial_count(&self, nb_decks: u8) -> f64 {
+        if self.is_balanced() {
+            0.0
+        } else {
+            4.0 - self.deck_total() * nb_decks as f64
+        }
+    }
+}
+
+pub const HI_LO: TagSystem = TagSystem {
+    name: "hi-lo",
+    tags: [1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, -1.0, -1.0],
+};
+
+pub const KO: TagSystem = TagSystem {
+    name: "ko",
+    tags: [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, -1.0, -1.0],
+};
+
+pub const HI_OPT_I: TagSystem = TagSystem {
+    name: "hi-opt-1",
+    tags: [0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, -1.0, 0.0],
+};
+
+pub const HI_OPT_II: TagSystem = TagSystem {
+    name: "hi-opt-2",
+    tags: [1.0, 1.0, 2.0, 2.0, 1.0, 1.0, 0.0, 0.0, -2.0, 0.0],
+};
+
+pub const OMEGA_II: TagSystem = TagSystem {
+    name: "omega-2",
+    tags: [1.0, 1.0, 2.0, 2.0, 2.0, 1.0, 0.0, -1.0, -2.0, 0.0],
+};
+
+pub const ZEN: TagSystem = TagSystem {
+    name: "zen",
+    tags: [1.0, 1.0, 2.0, 2.0, 2.0, 1.0, 0.0, 0.0, -2.0, -1.0],
+};
+
+pub const WONG_HALVES: TagSystem = TagSystem {
+    name: "wong-halves",
+    tags: [0.5, 1.0, 1.0, 1.5, 1.0, 0.5, 0.0, -0.5, -1.0, -1.0],
+};
+
+pub const SYSTEMS: [TagSystem; 7] = [HI_LO, KO, HI_OPT_I, HI_OPT_II, OMEGA_II, ZEN, WONG_HALVES];
+
+pub fn system_by_name(name: &str) -> Option<TagSystem> {
+    SYSTEMS.into_iter().find(|system| system.name == name)
+}
+
+// counts the cards a player sees at the table. the hole card is counted when it is revealed,
+// burnt cards and a hole card never shown are not, but they still leave the shoe.
+pub struct CountTracker {
+    system: Box<dyn CountingSystem>,
+    nb_decks: u8,
+    running_count: f64,
+    cards_seen: u16,
+    // every card out of the shoe since the shuffle, seen or not
+    cards_dealt: u16,
+    // a hole card dealt from this shoe and not revealed yet
+    hole_card: bool,
+}
+
+impl CountTracker {
+    pub fn new(system: Box<dyn CountingSystem>, nb_decks: u8) -> Self {
+        let running_count = system.initial_count(nb_decks);
+        CountTracker {
+            system,
+            nb_decks,
+            running_count,
+            cards_seen: 0,
+            cards_dealt: 0,
+            hole_card: false,
+        }
+    }
+
+    // for a game already under way, the cards out of its shoe were not seen
+    pub fn for_game(system: Box<dyn CountingSystem>, game: &Game) -> Self {
+        let mut tracker = CountTracker::new(system, game.rules.decks);
+        let shoe = game.rules.decks as usize * CARDS_PER_DECK as usize;
+        tracker.cards_dealt = shoe.saturating_sub(game.deck.cards.len()) as u16;
+        tracker.hole_card = game.dealer.is_hole_card_hidden() && game.dealer.hand.len() == 2;
+        tracker
+    }
+
+    pub fn get_system(&self) -> &dyn CountingSystem {
+        self.system.as_ref()
+    }
+
+    pub fn get_running_count(&self) -> f64 {
+        self.running_count
+    }
+
+    pub fn get_cards_seen(&self) -> u16 {
+        self.cards_seen
+    }
+
+    // estimated from the cards out of the shoe, at least one card so the true count is finite
+    pub fn get_decks_remaining(&self) -> f64 {
+        let shoe = self.nb_decks as u16 * CARDS_PER_DECK;
+        shoe.saturating_sub(self.cards_dealt).max(1) as f64 / CARDS_PER_DECK as f64
+    }
+
+    pub fn get_true_count(&self) -> f64 {
+        self.running_count / self.get_decks_remaining()
+    }
+
+    pub fn reset(&mut self) {
+        self.running_count = self.system.initial_count(self.nb_decks);
+        self.cards_seen = 0;
+        self.cards_dealt = 0;
+        self.hole_card = false;
+    }
+
+    pub fn count(&mut self, card: &Card) {
+        self.running_count += self.system.tag_card(card);
+        self.cards_seen += 1;
+    }
+}
+
+impl GameObserver for CountTracker {
+    fn on_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Shuffle => self.reset(),
+            GameEvent::Burn => self.cards_dealt += 1,
+            GameEvent::CardDealt { card, .. } => {
+                self.cards_dealt += 1;
+                self.count(card);
+            }
+            GameEvent::HoleCardDealt => {
+                self.cards_dealt += 1;
+                self.hole_card = true;
+            }
+            // a hole card dealt before a reshuffle in the middle of the round is not part
+            // of the new shoe
+            GameEvent::HoleCardRevealed(card) if self.hole_card => {
+                self.hole_card = false;
+                self.count(card);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        card::{CardValue, Suit},
+        deck::Deck,
+        events::Recipient,
+        player::PlayerAction,
+        rules::TableRules,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn systems_have_their_tags() {
+        assert_eq!(SYSTEMS.len(), 7);
+        for system in SYSTEMS {
+            assert_eq!(system_by_name(system.name), Some(system));
+            let balanced = system.name != "ko";
+            assert_eq!(system.is_balanced(), balanced, "{}", system.name);
+        }
+        assert_eq!(system_by_name("red-seven"), None);
+        assert_eq!(HI_LO.tag(5), 1.0);
+        assert_eq!(HI_LO.tag(11), -1.0);
+        assert_eq!(WONG_HALVES.tag(5), 1.5);
+        assert_eq!(HI_OPT_II.tag(11), 0.0);
+        assert_eq!(KO.initial_count(6), -20.0);
+        assert_eq!(HI_LO.initial_count(6), 0.0);
+    }
+
+    #[test]
+    fn whole_shoe_counts_back_to_the_start() {
+        let cards = Deck::from_decks(2).cards;
+        for system in SYSTEMS {
+            let mut tracker = CountTracker::new(Box::new(system), 2);
+            for card in &cards {
+                tracker.count(card);
+            }
+            let expected = if system.is_balanced() { 0.0 } else { 4.0 };
+            assert_eq!(tracker.get_running_count(), expected, "{}", system.name);
+            assert_eq!(tracker.get_cards_seen(), 104);
+        }
+    }
+
+    #[test]
+    fn true_count_divides_by_the_decks_left() {
+        let mut tracker = CountTracker::new(Box::new(HI_LO), 6);
+        let card = Card::new(CardValue::Number(4), Suit::Hearts);
+        for _ in 0..104 {
+            tracker.on_event(&GameEvent::CardDealt {
+                to: Recipient::Dealer,
+                card: card.clone(),
+            });
+        }
+        assert_eq!(tracker.get_running_count(), 104.0);
+        assert_eq!(tracker.get_decks_remaining(), 4.0);
+        assert_eq!(tracker.get_true_count(), 26.0);
+        tracker.on_event(&GameEvent::Shuffle);
+        assert_eq!(tracker.get_running_count(), 0.0);
+        assert_eq!(tracker.get_decks_remaining(), 6.0);
+    }
+
+    #[test]
+    fn hole_card_is_counted_once_revealed() {
+        let mut game = Game::with_seed(1, TableRules::default(), 3);
+        let tracker = CountTracker::for_game(Box::new(HI_LO), &game);
+        let tracker = Rc::new(RefCell::new(tracker));
+        game.subscribe(Box::new(tracker.clone()));
+        game.new_round();
+        game.place_bet(0, 10);
+        game.deal_initial_cards();
+        let visible = |game: &Game| -> f64 {
+            game.players[0]
+                .hand
+                .iter()
+                .chain(&game.dealer.hand[..1])
+                .map(|card| HI_LO.tag_card(card))
+                .sum()
+        };
+        assert_eq!(tracker.borrow().get_running_count(), visible(&game));
+        assert_eq!(tracker.borrow().get_cards_seen(), 3);
+        let burnt = game.rules.burn_cards as f64;
+        let dealt = burnt + 4.0;
+        assert_eq!(
+            tracker.borrow().get_decks_remaining(),
+            (6.0 * 52.0 - dealt) / 52.0
+        );
+
+        while let Some(hand) = game.current_hand() {
+            game.apply_action(hand, PlayerAction::Stand);
+        }
+        game.dealer_play();
+        let all: f64 = game.players[0]
+            .hand
+            .iter()
+            .chain(&game.dealer.hand)
+            .map(|card| HI_LO.tag_card(card))
+            .sum();
+        assert_eq!(tracker.borrow().get_running_count(), all);
+    }
+}
@@ -1,7 +1,8 @@
-use rand::seq::SliceRandom;
+use rand::{Rng, seq::SliceRandom};
 
 use crate::card::{Card, CardValue, Suit};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Deck {
     n: u16,
     pub cards: Vec<Card>,
@@ -35,8 +36,16 @@ impl Deck {
         Deck::from_vec(cards)
     }
 
+    pub fn from_decks(nb_decks: u8) -> Self {
+        let mut cards = Vec::with_capacity(CARD_GAME_SIZE * nb_decks as usize);
+        for _ in 0..nb_decks {
+            cards.append(&mut Deck::from_one_card_game().cards);
+        }
+        Deck::from_vec(cards)
+    }
+
     pub fn from_vec(cards: Vec<Card>) -> Self {
-        if cards.len() == 0 {
+        if cards.is_empty() {
             panic!("Deck cannot be empty");
         }
         Deck {
@@ -46,7 +55,11 @@ impl Deck {
     }
 
     pub fn shuffle(&mut self) {
-        self.cards.shuffle(&mut rand::rng());
+        self.shuffle_with_rng(&mut rand::rng());
+    }
+
+    pub fn shuffle_with_rng<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
     }
 
     pub fn deal(&mut self) -> Card {
@@ -91,7 +104,7 @@ mod tests {
         let mut deck = Deck::new(2);
         let _ = deck.deal();
         let _ = deck.deal();
-        assert!(deck.cards.len() == 0);
+        assert!(deck.cards.is_empty());
         let _ = deck.deal();
         assert!(deck.cards.len() == 1);
     }
@@ -102,6 +115,20 @@ mod tests {
         Deck::new(0);
     }
 
+    #[test]
+    fn test_deck_from_decks_contains_every_card_of_each_deck() {
+        let deck = Deck::from_decks(6);
+        assert_eq!(deck.cards.len(), 6 * 52);
+        let unique_cards: HashSet<&Card> = deck.cards.iter().collect();
+        assert_eq!(unique_cards.len(), 52);
+    }
+
+    #[test]
+    #[should_panic(expected = "Deck cannot be empty")]
+    fn test_deck_from_zero_decks_panics() {
+        Deck::from_decks(0);
+    }
+
     #[test]
     #[should_panic(expected = "Deck cannot be empty")]
     fn test_deck_from_vec_empty_panics() {
@@ -116,4 +143,28 @@ mod tests {
         deck.shuffle();
         assert_ne!(deck.cards, original_order);
     }
+
+    #[test]
+    fn test_shuffle_with_same_seed_gives_same_order() {
+        use rand::{SeedableRng, rngs::StdRng};
+
+        let mut first = Deck::from_one_card_game();
+        let mut second = Deck::from_one_card_game();
+        first.shuffle_with_rng(&mut StdRng::seed_from_u64(42));
+        second.shuffle_with_rng(&mut StdRng::seed_from_u64(42));
+        assert_eq!(first.cards, second.cards);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deck_json_round_trip_keeps_remaining_order() {
+        let mut deck = Deck::from_one_card_game();
+        deck.shuffle();
+        deck.deal();
+        let json = serde_json::to_string(&deck).unwrap();
+        let mut restored: Deck = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.n, 52);
+        assert_eq!(restored.cards, deck.cards);
+        assert_eq!(restored.deal(), deck.deal());
+    }
 }
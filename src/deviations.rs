@@ -0,0 +1,540 @@
+//! Playing deviations: plays that replace the strategy chart once the true count reaches
+//! an index, e.g. standing on 16 against a 10 from a true count of 0.
+//!
+//! Deviation files are CSV with one deviation per line, `hand,upcard,index,play`. Blank
+//! lines and lines starting with `#` are ignored.
+//!
+//! ```text
+//! # hand,upcard,index,play
+//! insurance,A,>=3,insure
+//! 16,T,>=0,stand
+//! TT,5,>=5,split
+//! A8,6,>=1,double
+//! 13,2,<-1,hit
+//! ```
+//!
+//! Hands are `insurance`, a hard total such as `16`, a soft total such as `A8` or a pair
+//! such as `TT`. The index is played at or above a true count with `>=` and below it with
+//! `<`. Plays are `hit`, `stand`, `double`, `split` and `surrender`, or `insure` for the
+//! insurance. A deviation is only played when it is legal, and a surrender of the chart is
+//! only replaced by a deviation below its index, such as `15,T,<0,hit`. The chart is played
+//! otherwise.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use crate::{
+    analysis::{card_name, parse_card},
+    autoplay::{PlayerStrategy, TableView, flat_bet},
+    card::Card,
+    chart::StrategyChart,
+    counting::{CountTracker, CountingSystem},
+    events::{GameEvent, GameObserver},
+    player::{Player, PlayerAction},
+};
+
+// the most valuable indices for Hi-Lo, on a shoe dealt with the usual rules
+const ILLUSTRIOUS_18: &str = "\
+insurance,A,>=3,insure
+16,T,>=0,stand
+15,T,>=4,stand
+TT,5,>=5,split
+TT,6,>=4,split
+10,T,>=4,double
+12,3,>=2,stand
+12,2,>=3,stand
+11,A,>=1,double
+9,2,>=1,double
+10,A,>=4,double
+9,7,>=3,double
+16,9,>=5,stand
+13,2,<-1,hit
+12,4,<0,hit
+12,5,<-2,hit
+12,6,<-1,hit
+13,3,<-2,hit
+";
+
+// the surrender indices for Hi-Lo, with the hit below the index of 15 against a 10 that the
+// multi-deck charts already surrender
+const FAB_4: &str = "\
+14,T,>=3,surrender
+15,T,>=0,surrender
+15,T,<0,hit
+15,9,>=2,surrender
+15,A,>=1,surrender
+";
+
+pub const DEVIATION_SETS: [&str; 2] = ["illustrious-18", "fab-4"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviationHand {
+    Insurance,
+    Hard(u8),
+    Soft(u8),
+    // value of the paired cards, 11 for aces
+    Pair(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Play {
+    Insure,
+    Action(PlayerAction),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deviation {
+    pub hand: DeviationHand,
+    pub upcard: u8,
+    pub index: f64,
+    // played below the index instead of at or above it
+    pub below: bool,
+    pub play: Play,
+}
+
+impl Deviation {
+    pub fn applies(&self, true_count: f64) -> bool {
+        if self.below {
+            true_count < self.index
+        } else {
+            true_count >= self.index
+        }
+    }
+
+    fn matches(&self, hand: &[Card], upcard: u8) -> bool {
+        if upcard != self.upcard {
+            return false;
+        }
+        let player = Player {
+            hand: hand.to_vec(),
+            ..Default::default()
+        };
+        match self.hand {
+            DeviationHand::Insurance => false,
+            DeviationHand::Pair(value) => player.is_pair() && hand[0].get_card_value() == value,
+            DeviationHand::Soft(total) => player.is_soft() && player.hand_value() == total,
+            DeviationHand::Hard(total) => !player.is_soft() && player.hand_value() == total,
+        }
+    }
+}
+
+impl fmt::Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.hand {
+            DeviationHand::Insurance => write!(f, "insurance")?,
+            DeviationHand::Hard(total) => write!(f, "{total}")?,
+            DeviationHand::Soft(total) => write!(f, "A{}", total - 11)?,
+            DeviationHand::Pair(value) => write!(f, "{0}{0}", card_name(value))?,
+        }
+        let play = match self.play {
+            Play::Insure => "insure".to_string(),
+            Play::Action(action) => action.to_string(),
+        };
+        let direction = if self.below { "<" } else { ">=" };
+        write!(
+            f,
+            " vs {}: {play} at {direction}{}",
+            card_name(self.upcard),
+            self.index
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum DeviationError {
+    Io(io::Error),
+    Syntax { line: usize, message: String },
+}
+
+impl fmt::Display for DeviationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviationError::Io(error) => write!(f, "{error}"),
+            DeviationError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DeviationError {}
+
+impl From<io::Error> for DeviationError {
+    fn from(error: io::Error) -> Self {
+        DeviationError::Io(error)
+    }
+}
+
+// deviations in the order they are looked up, the first one that applies is played
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviationSet {
+    pub deviations: Vec<Deviation>,
+}
+
+impl DeviationSet {
+    pub fn illustrious_18() -> Self {
+        DeviationSet::read_csv(ILLUSTRIOUS_18.as_bytes()).unwrap()
+    }
+
+    pub fn fab_4() -> Self {
+        DeviationSet::read_csv(FAB_4.as_bytes()).unwrap()
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "illustrious-18" => Some(DeviationSet::illustrious_18()),
+            "fab-4" => Some(DeviationSet::fab_4()),
+            _ => None,
+        }
+    }
+
+    pub fn read_csv<R: BufRead>(reader: R) -> Result<Self, DeviationError> {
+        let mut deviations = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+            if line.trim().is_empty() || cells[0].starts_with('#') {
+                continue;
+            }
+            let deviation = parse_deviation(&cells).map_err(|message| DeviationError::Syntax {
+                line: i + 1,
+                message,
+            })?;
+            deviations.push(deviation);
+        }
+        Ok(DeviationSet { deviations })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, DeviationError> {
+        DeviationSet::read_csv(BufReader::new(File::open(path)?))
+    }
+
+    pub fn extend(&mut self, other: DeviationSet) {
+        self.deviations.extend(other.deviations);
+    }
+
+    // the deviation played for the hand at the true count, if any
+    pub fn find(
+        &self,
+        chart: &StrategyChart,
+        hand: &[Card],
+        dealer_upcard: &Card,
+        true_count: f64,
+        legal: &[PlayerAction],
+    ) -> Option<&Deviation> {
+        let upcard = dealer_upcard.get_card_value();
+        let chart_action = chart.recommend(hand, dealer_upcard, legal);
+        self.deviations.iter().find(|deviation| {
+            let Play::Action(action) = deviation.play else {
+                return false;
+            };
+            // a pair the chart splits is only played differently by a pair deviation
+            let split = chart_action == PlayerAction::Split
+                && !matches!(deviation.hand, DeviationHand::Pair(_));
+            // a surrender of the chart is only given up below the index of a surrender
+            let surrender = chart_action == PlayerAction::Surrender
+                && action != PlayerAction::Surrender
+                && !deviation.below;
+            !split
+                && !surrender
+                && legal.contains(&action)
+                && deviation.matches(hand, upcard)
+                && deviation.applies(true_count)
+        })
+    }
+
+    // the play of the chart unless a deviation applies
+    pub fn recommend(
+        &self,
+        chart: &StrategyChart,
+        hand: &[Card],
+        dealer_upcard: &Card,
+        true_count: f64,
+        legal: &[PlayerAction],
+    ) -> PlayerAction {
+        match self.find(chart, hand, dealer_upcard, true_count, legal) {
+            Some(Deviation {
+                play: Play::Action(action),
+                ..
+            }) => *action,
+            _ => chart.recommend(hand, dealer_upcard, legal),
+        }
+    }
+
+    // never without an insurance index
+    pub fn insure(&self, true_count: f64) -> bool {
+        self.deviations.iter().any(|deviation| {
+            deviation.hand == DeviationHand::Insurance && deviation.applies(true_count)
+        })
+    }
+}
+
+fn parse_deviation(cells: &[&str]) -> Result<Deviation, String> {
+    let [hand, upcard, index, play] = cells else {
+        return Err("expected hand,upcard,index,play".to_string());
+    };
+    let hand = parse_deviation_hand(hand)?;
+    let upcard = parse_card(upcard)?;
+    let (below, index) = match (index.strip_prefix(">="), index.strip_prefix('<')) {
+        (Some(index), _) => (false, index),
+        (_, Some(index)) => (true, index),
+        _ => return Err(format!("Invalid index: {index}, expected >=N or <N")),
+    };
+    let index = index
+        .trim_start_matches('+')
+        .parse()
+        .map_err(|_| format!("Invalid index: {index}"))?;
+    let play = match (hand, *play) {
+        (DeviationHand::Insurance, "insure") => Play::Insure,
+        (DeviationHand::Insurance, play) => {
+            return Err(format!("Invalid play for the insurance: {play}"));
+        }
+        (_, play) => Play::Action(play.parse()?),
+    };
+    if hand == DeviationHand::Insurance && upcard != 11 {
+        return Err("The insurance is only offered against an ace".to_string());
+    }
+    Ok(Deviation {
+        hand,
+        upcard,
+        index,
+        below,
+        play,
+    })
+}
+
+fn parse_deviation_hand(hand: &str) -> Result<DeviationHand, String> {
+    let invalid = || format!("Invalid hand: {hand}");
+    if hand == "insurance" {
+        return Ok(DeviationHand::Insurance);
+    }
+    let cards: Result<Vec<u8>, String> = hand.chars().map(|c| parse_card(&c.to_string())).collect();
+    // 88 is a pair, 11 and 16 are hard totals
+    match cards.as_deref() {
+        Ok([first, second]) if first == second => return Ok(DeviationHand::Pair(*first)),
+        Ok([11, second]) if *second < 11 => return Ok(DeviationHand::Soft(11 + second)),
+        _ => {}
+    }
+    match hand.parse::<u8>() {
+        Ok(total @ 4..=21) => Ok(DeviationHand::Hard(total)),
+        _ => Err(invalid()),
+    }
+}
+
+// plays a chart with the deviations for the true count of its own count, betting flat
+pub struct DeviationStrategy {
+    pub chart: StrategyChart,
+    pub deviations: DeviationSet,
+    pub bet: u32,
+    tracker: CountTracker,
+}
+
+impl DeviationStrategy {
+    pub fn new(
+        chart: StrategyChart,
+        deviations: DeviationSet,
+        system: Box<dyn CountingSystem>,
+        nb_decks: u8,
+        bet: u32,
+    ) -> Self {
+        DeviationStrategy {
+            chart,
+            deviations,
+            bet,
+            tracker: CountTracker::new(system, nb_decks),
+        }
+    }
+}
+
+impl PlayerStrategy for DeviationStrategy {
+    fn bet(&mut self, view: &TableView) -> Option<u32> {
+        flat_bet(self.bet, view)
+    }
+
+    fn action(&mut self, view: &TableView) -> PlayerAction {
+        self.deviations.recommend(
+            &self.chart,
+            view.get_hand(),
+            view.get_dealer_upcard().unwrap(),
+            self.tracker.get_true_count(),
+            &view.legal_actions(),
+        )
+    }
+
+    fn insure(&mut self, _view: &TableView) -> bool {
+        self.deviations.insure(self.tracker.get_true_count())
+    }
+
+    fn on_event(&mut self, event: &GameEvent) {
+        self.tracker.on_event(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        card::{CardValue, Suit},
+        counting::HI_LO,
+        game::Game,
+        rules::TableRules,
+        strategy::BasicStrategy,
+    };
+
+    fn cards(values: &[u8]) -> Vec<Card> {
+        values
+            .iter()
+            .map(|value| {
+                let value = match value {
+                    11 => CardValue::Ace,
+                    _ => CardValue::Number(*value),
+                };
+                Card::new(value, Suit::Clubs)
+            })
+            .collect()
+    }
+
+    fn play(set: &DeviationSet, hand: &[u8], upcard: u8, true_count: f64) -> PlayerAction {
+        let rules = TableRules::default();
+        let legal = [
+            PlayerAction::Hit,
+            PlayerAction::Stand,
+            PlayerAction::Double,
+            PlayerAction::Split,
+            PlayerAction::Surrender,
+        ];
+        let upcard = &cards(&[upcard])[0];
+        let legal = if hand.len() == 2 {
+            &legal[..]
+        } else {
+            &legal[..2]
+        };
+        set.recommend(
+            &BasicStrategy::chart(&rules),
+            &cards(hand),
+            upcard,
+            true_count,
+            legal,
+        )
+    }
+
+    #[test]
+    fn built_in_sets_are_read() {
+        let illustrious = DeviationSet::illustrious_18();
+        assert_eq!(illustrious.deviations.len(), 18);
+        // the four indices and the hit below the one of 15 against a 10
+        assert_eq!(DeviationSet::fab_4().deviations.len(), 5);
+        assert_eq!(
+            illustrious.deviations[0].to_string(),
+            "insurance vs A: insure at >=3"
+        );
+        assert_eq!(
+            illustrious.deviations[13].to_string(),
+            "13 vs 2: hit at <-1"
+        );
+        for name in DEVIATION_SETS {
+            assert!(DeviationSet::by_name(name).is_some());
+        }
+    }
+
+    #[test]
+    fn indices_change_the_play() {
+        let set = DeviationSet::illustrious_18();
+        assert_eq!(play(&set, &[10, 6], 10, -0.5), PlayerAction::Surrender);
+        assert_eq!(play(&set, &[4, 5, 7], 10, -0.5), PlayerAction::Hit);
+        assert_eq!(play(&set, &[4, 5, 7], 10, 0.0), PlayerAction::Stand);
+        assert_eq!(play(&set, &[10, 2], 3, 1.9), PlayerAction::Hit);
+        assert_eq!(play(&set, &[10, 2], 3, 2.0), PlayerAction::Stand);
+        assert_eq!(play(&set, &[10, 3], 2, -1.0), PlayerAction::Stand);
+        assert_eq!(play(&set, &[10, 3], 2, -1.5), PlayerAction::Hit);
+        assert_eq!(play(&set, &[10, 10], 6, 4.0), PlayerAction::Split);
+        assert_eq!(play(&set, &[10, 10], 6, 3.0), PlayerAction::Stand);
+        // doubling needs two cards
+        assert_eq!(play(&set, &[2, 3, 5], 10, 6.0), PlayerAction::Hit);
+        assert_eq!(play(&set, &[6, 4], 10, 6.0), PlayerAction::Double);
+        // 66 is split against a 3 whatever the twelves do
+        assert_eq!(play(&set, &[6, 6], 3, 5.0), PlayerAction::Split);
+        assert!(set.insure(3.0));
+        assert!(!set.insure(2.9));
+        assert!(!DeviationSet::fab_4().insure(10.0));
+
+        let fab = DeviationSet::fab_4();
+        assert_eq!(play(&fab, &[10, 4], 10, 3.0), PlayerAction::Surrender);
+        assert_eq!(play(&fab, &[10, 4], 10, 2.0), PlayerAction::Hit);
+        // the chart surrenders 15 against a 10, hit below the index
+        assert_eq!(play(&fab, &[10, 5], 10, 0.0), PlayerAction::Surrender);
+        assert_eq!(play(&fab, &[10, 5], 10, -0.5), PlayerAction::Hit);
+        // a deviation at the index does not give up the surrender of the chart
+        assert_eq!(play(&set, &[10, 5], 10, 4.0), PlayerAction::Surrender);
+    }
+
+    #[test]
+    fn custom_files_are_checked() {
+        let set =
+            DeviationSet::read_csv("# soft doubles\n\nA8,6,>=+1,double\n88,T,<-5,hit\n".as_bytes())
+                .unwrap();
+        assert_eq!(
+            set.deviations[0],
+            Deviation {
+                hand: DeviationHand::Soft(19),
+                upcard: 6,
+                index: 1.0,
+                below: false,
+                play: Play::Action(PlayerAction::Double),
+            }
+        );
+        assert_eq!(set.deviations[1].to_string(), "88 vs T: hit at <-5");
+        let errors = [
+            (
+                "16,T,0,stand",
+                "line 1: Invalid index: 0, expected >=N or <N",
+            ),
+            ("16,T,>=x,stand", "line 1: Invalid index: x"),
+            ("16,X,>=0,stand", "line 1: Invalid card: X"),
+            ("T9,5,>=0,stand", "line 1: Invalid hand: T9"),
+            ("16,T,>=0", "line 1: expected hand,upcard,index,play"),
+            ("16,T,>=0,insure", "line 1: Invalid action: insure"),
+            (
+                "insurance,T,>=3,insure",
+                "line 1: The insurance is only offered against an ace",
+            ),
+            (
+                "insurance,A,>=3,stand",
+                "line 1: Invalid play for the insurance: stand",
+            ),
+        ];
+        for (line, message) in errors {
+            let error = DeviationSet::read_csv(line.as_bytes()).unwrap_err();
+            assert_eq!(error.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn strategy_insures_from_its_count() {
+        let rules = TableRules::default();
+        let mut game = Game::with_seed(1, rules.clone(), 2);
+        let strategy = DeviationStrategy::new(
+            BasicStrategy::chart(&rules),
+            DeviationSet::illustrious_18(),
+            Box::new(HI_LO),
+            rules.decks,
+            10,
+        );
+        game.set_strategy(0, Box::new(strategy));
+        let mut insured = 0;
+        for _ in 0..2000 {
+            game.new_round();
+            game.place_automated_bets().unwrap();
+            game.deal_initial_cards();
+            game.place_automated_insurance();
+            insured += (game.players[0].insurance > 0) as u32;
+            game.play_automated_hands();
+            game.dealer_play();
+            game.settle();
+        }
+        // only at high counts, against a few of the aces
+        assert!(insured > 0 && insured < 2000 / 13, "{insured}");
+    }
+}
@@ -0,0 +1,312 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use crate::{
+    counting::{CountTracker, TagSystem},
+    game::{Game, Phase},
+    player::{PlayerAction, PlayerOutcome},
+    rules::TableRules,
+    simulation::{ACTIONS, SIMULATION_BANKROLL},
+};
+
+// what the agent sees of the hand to play
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Observation {
+    pub player_total: u8,
+    pub soft: bool,
+    // from 2 to 11 for an ace
+    pub dealer_upcard: u8,
+    // in the order of simulation::ACTIONS, all false once the round is over or when it is
+    // settled on the deal
+    pub legal_actions: [bool; 5],
+    // before the cards of the round, None without a counting system
+    pub true_count: Option<f64>,
+}
+
+impl Observation {
+    pub fn is_legal(&self, action: PlayerAction) -> bool {
+        ACTIONS
+            .iter()
+            .position(|legal| *legal == action)
+            .is_some_and(|index| self.legal_actions[index])
+    }
+
+    pub fn legal(&self) -> impl Iterator<Item = PlayerAction> + '_ {
+        ACTIONS
+            .into_iter()
+            .zip(self.legal_actions)
+            .filter_map(|(action, legal)| legal.then_some(action))
+    }
+}
+
+// the round once it is over, empty before
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepInfo {
+    // in chips, one per hand of the seat after the splits
+    pub nets: Vec<i64>,
+    pub outcomes: Vec<PlayerOutcome>,
+}
+
+// a step the environment cannot play, it is left as it was
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepError {
+    // not a legal action of the hand to play
+    IllegalAction(PlayerAction),
+    // no round under way, the environment has to be reset
+    RoundOver,
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepError::IllegalAction(action) => write!(f, "Cannot {action} now"),
+            StepError::RoundOver => write!(f, "The round is over, reset the environment"),
+        }
+    }
+}
+
+impl std::error::Error for StepError {}
+
+// a gym style environment, one seat playing a fixed bet against the dealer a round per episode
+pub struct Environment {
+    game: Game,
+    bet: u32,
+    count: Option<TagSystem>,
+    tracker: Option<Rc<RefCell<CountTracker>>>,
+    // the true count before the cards of the round
+    true_count: Option<f64>,
+    // the hand of the last observation
+    hand: usize,
+}
+
+impl Environment {
+    // seeded with 0 until reset with another seed
+    pub fn new(rules: TableRules, bet: u32, count: Option<TagSystem>) -> Self {
+        if bet == 0 {
+            panic!("Bet must be positive");
+        }
+        let mut environment = Environment {
+            game: Game::with_seed(1, rules, 0),
+            bet,
+            count,
+            tracker: None,
+            true_count: None,
+            hand: 0,
+        };
+        environment.setup();
+        environment
+    }
+
+    fn setup(&mut self) {
+        self.game.set_bankroll(0, SIMULATION_BANKROLL);
+        self.tracker = self.count.map(|system| {
+            let tracker = CountTracker::for_game(Box::new(system), &self.game);
+            Rc::new(RefCell::new(tracker))
+        });
+        if let Some(tracker) = &self.tracker {
+            self.game.subscribe(Box::new(tracker.clone()));
+        }
+    }
+
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn get_bet(&self) -> u32 {
+        self.bet
+    }
+
+    // a new shoe with a seed, else the next round of the shoe. an unfinished round is called
+    // off and its bet given back, so the rewards still add up to the bankroll. the insurance
+    // is never taken.
+    pub fn reset(&mut self, seed: Option<u64>) -> Observation {
+        if matches!(self.game.phase(), Phase::PlayerTurn(_) | Phase::DealerTurn) {
+            let staked: i64 = self
+                .game
+                .players
+                .iter()
+                .map(|player| player.bet as i64 + player.insurance as i64)
+                .sum();
+            self.game
+                .set_bankroll(0, self.game.get_bankroll(0) + staked);
+        }
+        if let Some(seed) = seed {
+            self.game = Game::with_seed(1, self.game.rules.clone(), seed);
+            self.setup();
+        }
+        self.game.new_round();
+        self.true_count = self
+            .tracker
+            .as_ref()
+            .map(|tracker| tracker.borrow().get_true_count());
+        self.game.place_bet(0, self.bet);
+        self.game.deal_initial_cards();
+        self.hand = self.game.current_hand().unwrap_or(0);
+        self.observe(self.hand, self.game.current_hand().is_some())
+    }
+
+    // the reward is the net result of the round in bets, zero until it is over. a round
+    // settled on the deal, a natural or a dealer blackjack, observes no legal action and
+    // its next step ends it whatever the action.
+    pub fn step(
+        &mut self,
+        action: PlayerAction,
+    ) -> Result<(Observation, f64, bool, StepInfo), StepError> {
+        if matches!(self.game.phase(), Phase::Betting | Phase::Settled) {
+            return Err(StepError::RoundOver);
+        }
+        if let Some(hand) = self.game.current_hand() {
+            if !self.game.legal_actions(hand).contains(&action) {
+                return Err(StepError::IllegalAction(action));
+            }
+            self.game.apply_action(hand, action);
+            if let Some(next) = self.game.current_hand() {
+                self.hand = next;
+                return Ok((self.observe(next, true), 0.0, false, StepInfo::default()));
+            }
+        }
+        self.game.dealer_play();
+        let nets = self.game.settle();
+        let outcomes = (0..nets.len())
+            .map(|hand| self.game.get_outcome(hand))
+            .collect();
+        let reward = nets.iter().sum::<i64>() as f64 / self.bet as f64;
+        let info = StepInfo { nets, outcomes };
+        Ok((self.observe(self.hand, false), reward, true, info))
+    }
+
+    fn observe(&self, hand: usize, playing: bool) -> Observation {
+        let mut legal_actions = [false; 5];
+        if playing {
+            for action in self.game.legal_actions(hand) {
+                if let Some(index) = ACTIONS.iter().position(|legal| *legal == action) {
+                    legal_actions[index] = true;
+                }
+            }
+        }
+        Observation {
+            player_total: self.game.get_player_hand_value(hand),
+            soft: self.game.players[hand].is_soft(),
+            dealer_upcard: self.game.dealer.hand[0].get_card_value(),
+            legal_actions,
+            true_count: self.true_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{counting::HI_LO, strategy::BasicStrategy};
+
+    // the basic strategy over the episodes, with the chips won, the rewards and the episodes
+    // settled on the deal
+    fn play(environment: &mut Environment, seed: u64, episodes: u32) -> (i64, f64, u32) {
+        let mut net = 0;
+        let mut rewards = 0.0;
+        let mut settled = 0;
+        for episode in 0..episodes {
+            let mut observation = environment.reset((episode == 0).then_some(seed));
+            assert!((2..=11).contains(&observation.dealer_upcard));
+            if observation.legal().next().is_none() {
+                settled += 1;
+            }
+            loop {
+                let action = match environment.get_game().current_hand() {
+                    Some(hand) => BasicStrategy::recommend_hand(environment.get_game(), hand),
+                    None => PlayerAction::Stand,
+                };
+                let (next, reward, done, info) = environment.step(action).unwrap();
+                rewards += reward;
+                observation = next;
+                if done {
+                    assert_eq!(info.nets.len(), info.outcomes.len());
+                    assert!(observation.legal().next().is_none());
+                    net += info.nets.iter().sum::<i64>();
+                    break;
+                }
+                assert!(observation.is_legal(PlayerAction::Stand));
+                assert_eq!(reward, 0.0);
+                assert_eq!(info, StepInfo::default());
+            }
+        }
+        (net, rewards, settled)
+    }
+
+    #[test]
+    fn rewards_add_up_to_the_bankroll() {
+        let mut environment = Environment::new(TableRules::default(), 10, None);
+        let (net, rewards, settled) = play(&mut environment, 7, 2000);
+        assert_eq!(
+            SIMULATION_BANKROLL + net,
+            environment.get_game().get_bankroll(0)
+        );
+        assert_eq!(net, (rewards * 10.0).round() as i64);
+        assert!(settled > 0);
+
+        let mut again = Environment::new(TableRules::default(), 10, None);
+        assert_eq!(play(&mut again, 7, 2000), (net, rewards, settled));
+        assert!(again.reset(None).true_count.is_none());
+    }
+
+    #[test]
+    fn observations_follow_the_hand() {
+        let mut environment = Environment::new(TableRules::default(), 10, Some(HI_LO));
+        for seed in 0..50 {
+            let observation = environment.reset(Some(seed));
+            let game = environment.get_game();
+            let Some(hand) = game.current_hand() else {
+                continue;
+            };
+            assert_eq!(observation.player_total, game.get_player_hand_value(hand));
+            assert_eq!(observation.soft, game.players[hand].is_soft());
+            assert_eq!(
+                observation.legal().collect::<Vec<_>>(),
+                game.legal_actions(hand)
+            );
+            assert_eq!(
+                observation.is_legal(PlayerAction::Split),
+                game.players[hand].is_pair()
+            );
+            assert!(observation.true_count.is_some());
+        }
+    }
+
+    #[test]
+    fn steps_that_cannot_be_played_are_refused() {
+        let mut environment = Environment::new(TableRules::default(), 10, None);
+        assert_eq!(
+            environment.step(PlayerAction::Hit),
+            Err(StepError::RoundOver)
+        );
+        let observation = environment.reset(Some(1));
+        assert!(!observation.is_legal(PlayerAction::Split));
+        assert_eq!(
+            environment.step(PlayerAction::Split),
+            Err(StepError::IllegalAction(PlayerAction::Split))
+        );
+        while !environment.step(PlayerAction::Stand).unwrap().2 {}
+        let error = environment.step(PlayerAction::Stand).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The round is over, reset the environment"
+        );
+    }
+
+    #[test]
+    fn unfinished_round_gives_its_bet_back() {
+        let mut environment = Environment::new(TableRules::default(), 10, None);
+        for _ in 0..20 {
+            environment.reset(None);
+            assert_eq!(
+                environment.get_game().get_bankroll(0),
+                SIMULATION_BANKROLL - 10
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Bet must be positive")]
+    fn bet_must_be_positive() {
+        Environment::new(TableRules::default(), 0, None);
+    }
+}
@@ -0,0 +1,110 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    card::Card,
+    player::{PlayerAction, PlayerOutcome},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recipient {
+    Hand { hand: usize, seat: usize },
+    Dealer,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    RoundStarted {
+        round: u64,
+        bankrolls: Vec<i64>,
+    },
+    Shuffle,
+    // a card burned face down after the shuffle, nobody sees it
+    Burn,
+    BetPlaced {
+        seat: usize,
+        amount: u32,
+    },
+    // a card dealt face up
+    CardDealt {
+        to: Recipient,
+        card: Card,
+    },
+    // the dealer hole card is dealt face down, its card is only known once revealed when the
+    // dealer plays
+    HoleCardDealt,
+    HoleCardRevealed(Card),
+    // half the bet on the dealer having a blackjack behind an ace, paid 2 to 1
+    Insurance {
+        seat: usize,
+        amount: u32,
+    },
+    Action {
+        hand: usize,
+        seat: usize,
+        action: PlayerAction,
+    },
+    Outcome {
+        hand: usize,
+        seat: usize,
+        outcome: PlayerOutcome,
+    },
+    Payout {
+        hand: usize,
+        seat: usize,
+        net: i64,
+    },
+    RoundSettled,
+}
+
+pub trait GameObserver {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+// lets the caller keep a handle on an observer registered on a game
+impl<T: GameObserver> GameObserver for Rc<RefCell<T>> {
+    fn on_event(&mut self, event: &GameEvent) {
+        self.borrow_mut().on_event(event);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct EventLog {
+    pub events: Vec<GameEvent>,
+}
+
+impl GameObserver for EventLog {
+    fn on_event(&mut self, event: &GameEvent) {
+        self.events.push(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{CardValue, Suit};
+
+    #[test]
+    fn event_log_records_events_in_order() {
+        let mut log = EventLog::default();
+        log.on_event(&GameEvent::Shuffle);
+        log.on_event(&GameEvent::Burn);
+        let ace = Card::new(CardValue::Ace, Suit::Clubs);
+        log.on_event(&GameEvent::HoleCardRevealed(ace.clone()));
+        assert_eq!(
+            log.events,
+            vec![
+                GameEvent::Shuffle,
+                GameEvent::Burn,
+                GameEvent::HoleCardRevealed(ace)
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_observer_stays_readable() {
+        let log = Rc::new(RefCell::new(EventLog::default()));
+        let mut observer: Box<dyn GameObserver> = Box::new(log.clone());
+        observer.on_event(&GameEvent::Shuffle);
+        assert_eq!(log.borrow().events, vec![GameEvent::Shuffle]);
+    }
+}
@@ -0,0 +1,208 @@
+//! Machine readable results of simulations: a JSON summary of every run and the results by
+//! true count as CSV, one row per run and true count.
+//!
+//! ```text
+//! run,true_count,hands,frequency,wagered,net,edge,edge_ci95
+//! ramp,-1,25113,0.251130,251130,-1560,-0.006212,0.011281
+//! ```
+//!
+//! Frequencies are fractions of the hands of the run and the edge is the net result over
+//! the initial bets, with the half width of its 95% confidence interval.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use crate::{
+    risk::RiskReport,
+    rules::TableRules,
+    simulation::{ACTIONS, OUTCOMES, SimulationConfig, SimulationStats},
+};
+
+pub const TRUE_COUNT_HEADER: &str = "run,true_count,hands,frequency,wagered,net,edge,edge_ci95";
+
+// results of the hands played at a true count
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TrueCountRow {
+    pub true_count: i32,
+    pub hands: u64,
+    pub frequency: f64,
+    pub wagered: u64,
+    pub net: i64,
+    pub edge: f64,
+    pub edge_confidence: f64,
+}
+
+// one simulation of the strategies of the seats, named after what it plays or bets
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RunSummary {
+    pub name: String,
+    pub seed: u64,
+    pub seats: u8,
+    pub rules: TableRules,
+    pub count: String,
+    pub rounds: u64,
+    pub hands: u64,
+    pub wagered: u64,
+    pub net: i64,
+    pub ev_per_hand: f64,
+    pub edge: f64,
+    pub edge_confidence: f64,
+    pub standard_deviation: f64,
+    pub hands_per_shoe: Option<f64>,
+    pub outcomes: BTreeMap<String, u64>,
+    pub actions: BTreeMap<String, u64>,
+    pub true_counts: Vec<TrueCountRow>,
+    pub risk: RiskReport,
+}
+
+impl RunSummary {
+    pub fn new(
+        name: &str,
+        config: &SimulationConfig,
+        stats: &SimulationStats,
+        risk: RiskReport,
+    ) -> Self {
+        let true_counts = stats
+            .true_counts
+            .iter()
+            .map(|(true_count, by_count)| TrueCountRow {
+                true_count: *true_count,
+                hands: by_count.hands,
+                frequency: by_count.hands as f64 / stats.hands.max(1) as f64,
+                wagered: by_count.wagered,
+                net: by_count.net,
+                edge: by_count.edge(),
+                edge_confidence: by_count.edge_confidence(),
+            })
+            .collect();
+        RunSummary {
+            name: name.to_string(),
+            seed: config.seed,
+            seats: config.seats,
+            rules: config.rules.clone(),
+            count: config.count.name.to_string(),
+            rounds: stats.rounds,
+            hands: stats.hands,
+            wagered: stats.wagered,
+            net: stats.net,
+            ev_per_hand: stats.ev_per_hand(),
+            edge: stats.edge(),
+            edge_confidence: stats.edge_confidence(),
+            standard_deviation: stats.standard_deviation(),
+            hands_per_shoe: stats.hands_per_shoe(),
+            outcomes: OUTCOMES
+                .iter()
+                .map(|outcome| (outcome.to_string(), stats.outcome_count(*outcome)))
+                .collect(),
+            actions: ACTIONS
+                .iter()
+                .map(|action| (action.to_string(), stats.action_count(*action)))
+                .collect(),
+            true_counts,
+            risk,
+        }
+    }
+}
+
+pub fn write_true_count_csv<W: Write>(runs: &[RunSummary], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "{TRUE_COUNT_HEADER}")?;
+    for run in runs {
+        for row in &run.true_counts {
+            writeln!(
+                writer,
+                "{},{},{},{:.6},{},{},{:.6},{:.6}",
+                run.name,
+                row.true_count,
+                row.hands,
+                row.frequency,
+                row.wagered,
+                row.net,
+                row.edge,
+                row.edge_confidence
+            )?;
+        }
+    }
+    writer.flush()
+}
+
+// an array of the runs, infinite values such as the confidence of a single hand are null
+#[cfg(feature = "serde")]
+pub fn write_json<W: Write>(runs: &[RunSummary], mut writer: W) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut writer, runs)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+#[cfg(not(feature = "serde"))]
+pub fn write_json<W: Write>(_runs: &[RunSummary], _writer: W) -> io::Result<()> {
+    Err(io::Error::other("JSON exports need the serde feature"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        autoplay::ChartStrategy,
+        counting::HI_LO,
+        risk::Horizon,
+        simulation::{StopCondition, simulate},
+    };
+
+    fn summary() -> RunSummary {
+        let config = SimulationConfig {
+            rules: TableRules::default(),
+            seats: 2,
+            seed: 3,
+            stop: StopCondition::Rounds(2000),
+            count: HI_LO,
+        };
+        let stats = simulate(&config, |_, _| {
+            Box::new(ChartStrategy::basic(&config.rules, 10))
+        });
+        let risk = RiskReport::new(&stats, 1000, Horizon::Trip(100), 0.05, 50, 3, 1);
+        RunSummary::new("basic", &config, &stats, risk)
+    }
+
+    #[test]
+    fn true_counts_add_up_to_the_run() {
+        let summary = summary();
+        assert!(summary.true_counts.len() > 3);
+        let hands: u64 = summary.true_counts.iter().map(|row| row.hands).sum();
+        let net: i64 = summary.true_counts.iter().map(|row| row.net).sum();
+        assert_eq!(hands, summary.hands);
+        assert_eq!(net, summary.net);
+
+        let mut csv = Vec::new();
+        write_true_count_csv(&[summary.clone(), summary.clone()], &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], TRUE_COUNT_HEADER);
+        assert_eq!(lines.len(), 1 + 2 * summary.true_counts.len());
+        let row = summary.true_counts[0];
+        assert!(lines[1].starts_with(&format!("basic,{},{},", row.true_count, row.hands)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn summary_is_written_as_json() {
+        let summary = summary();
+        let mut json = Vec::new();
+        write_json(std::slice::from_ref(&summary), &mut json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let run = &value[0];
+        assert_eq!(run["name"], "basic");
+        assert_eq!(run["rounds"], 2000);
+        assert_eq!(run["rules"]["decks"], 6);
+        assert_eq!(run["outcomes"]["blackjack"], summary.outcomes["blackjack"]);
+        assert_eq!(run["risk"]["trip"], 100);
+        assert_eq!(run["risk"]["simulated"]["sessions"], 50);
+        assert_eq!(
+            run["true_counts"].as_array().unwrap().len(),
+            summary.true_counts.len()
+        );
+    }
+}
@@ -1,67 +1,521 @@
+use std::fmt;
+
+use rand::{SeedableRng, rngs::StdRng};
+
 use crate::{
+    autoplay::{PlayerStrategy, TableView},
     card::Card,
     deck::Deck,
-    player::{Dealer, Player, PlayerOutcome},
+    events::{GameEvent, GameObserver, Recipient},
+    player::{Dealer, Player, PlayerAction, PlayerOutcome},
+    rules::TableRules,
 };
 
+pub const DEFAULT_BANKROLL: i64 = 1000;
+pub const MAX_PLAYERS: u8 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Betting,
+    PlayerTurn(usize),
+    DealerTurn,
+    Settled,
+}
+
+// an automated seat whose strategy has no chips left to bet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfChips {
+    pub seat: usize,
+}
+
+impl fmt::Display for OutOfChips {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Player {} is out of chips", self.seat + 1)
+    }
+}
+
+impl std::error::Error for OutOfChips {}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
     pub players: Vec<Player>,
     pub dealer: Dealer,
     pub deck: Deck,
+    pub discards: Vec<Card>,
+    pub rules: TableRules,
+    bankrolls: Vec<i64>,
+    settled: bool,
+    seed: u64,
+    shuffles: u64,
+    round: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    observers: Vec<Box<dyn GameObserver>>,
+    // one entry per seat, None for the seats played from outside
+    #[cfg_attr(feature = "serde", serde(skip))]
+    strategies: Vec<Option<Box<dyn PlayerStrategy>>>,
 }
 
 impl Game {
+    // a deck of random cards, there is no point in burning any of them
     pub fn new(nb_players: u8, nb_cards: u16) -> Self {
+        let deck = Deck::new(nb_cards);
+        let rules = TableRules {
+            burn_cards: 0,
+            ..Default::default()
+        };
+        Game::with_deck(nb_players, deck, rules, rand::random())
+    }
+
+    pub fn with_rules(nb_players: u8, rules: TableRules) -> Self {
+        Game::with_seed(nb_players, rules, rand::random())
+    }
+
+    // every shuffle of the shoe is derived from the seed, so the same seed deals the same cards
+    pub fn with_seed(nb_players: u8, rules: TableRules, seed: u64) -> Self {
+        let deck = Deck::from_decks(rules.decks);
+        Game::with_deck(nb_players, deck, rules, seed)
+    }
+
+    fn with_deck(nb_players: u8, deck: Deck, rules: TableRules, seed: u64) -> Self {
         if nb_players == 0 {
             panic!("There must be at least one player");
         }
 
         let mut players = Vec::new();
-        for _ in 0..nb_players {
-            players.push(Player::default());
+        for seat in 0..nb_players as usize {
+            players.push(Player {
+                seat,
+                ..Default::default()
+            });
         }
 
-        let dealer = Dealer::default();
-        let mut deck = Deck::new(nb_cards);
-        deck.shuffle();
-
-        Game {
+        let mut game = Game {
             players,
-            dealer,
+            dealer: Dealer::default(),
             deck,
+            discards: Vec::new(),
+            rules,
+            bankrolls: vec![DEFAULT_BANKROLL; nb_players as usize],
+            settled: false,
+            seed,
+            shuffles: 0,
+            round: 0,
+            observers: Vec::new(),
+            strategies: Vec::new(),
+        };
+        game.reshuffle();
+        game
+    }
+
+    pub fn subscribe(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
+    // observers are not saved with the game, they are moved to a loaded game with this
+    pub fn take_observers(&mut self) -> Vec<Box<dyn GameObserver>> {
+        std::mem::take(&mut self.observers)
+    }
+
+    pub fn set_strategy(&mut self, seat: usize, strategy: Box<dyn PlayerStrategy>) {
+        if seat >= self.nb_seats() {
+            panic!("Invalid seat index");
+        }
+        self.strategies.resize_with(self.nb_seats(), || None);
+        self.strategies[seat] = Some(strategy);
+    }
+
+    // strategies are not saved either, they are moved to a loaded game with this
+    pub fn take_strategies(&mut self) -> Vec<Option<Box<dyn PlayerStrategy>>> {
+        std::mem::take(&mut self.strategies)
+    }
+
+    pub fn is_automated(&self, seat: usize) -> bool {
+        matches!(self.strategies.get(seat), Some(Some(_)))
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    // number of times the shoe was shuffled, the first shuffle included
+    pub fn get_shuffles(&self) -> u64 {
+        self.shuffles
+    }
+
+    pub fn get_round(&self) -> u64 {
+        self.round
+    }
+
+    pub fn nb_seats(&self) -> usize {
+        self.bankrolls.len()
+    }
+
+    pub fn get_bankroll(&self, seat: usize) -> i64 {
+        if seat >= self.nb_seats() {
+            panic!("Invalid seat index");
+        }
+        self.bankrolls[seat]
+    }
+
+    pub fn set_bankroll(&mut self, seat: usize, bankroll: i64) {
+        if seat >= self.nb_seats() {
+            panic!("Invalid seat index");
+        }
+        self.bankrolls[seat] = bankroll;
+    }
+
+    // clears the table into the discard tray and reshuffles once the cut card is reached
+    pub fn new_round(&mut self) {
+        for player in self.players.drain(..) {
+            self.discards.extend(player.hand);
+        }
+        self.discards.append(&mut self.dealer.hand);
+        for seat in 0..self.nb_seats() {
+            self.players.push(Player {
+                seat,
+                ..Default::default()
+            });
+        }
+        self.dealer.hide_hole_card();
+        self.settled = false;
+        self.round += 1;
+        self.emit(GameEvent::RoundStarted {
+            round: self.round,
+            bankrolls: self.bankrolls.clone(),
+        });
+
+        let shoe_size = self.deck.cards.len() + self.discards.len();
+        if self.discards.len() as f32 >= shoe_size as f32 * self.rules.penetration {
+            self.reshuffle();
+        }
+    }
+
+    pub fn place_bet(&mut self, seat: usize, amount: u32) {
+        if seat >= self.nb_seats() {
+            panic!("Invalid seat index");
+        }
+        if amount == 0 {
+            panic!("Bet must be positive");
+        }
+        let player = &mut self.players[seat];
+        if !player.hand.is_empty() {
+            panic!("Bets must be placed before dealing");
+        }
+        // a new bet replaces the previous one, which is given back first
+        let available = self.bankrolls[seat] + player.bet as i64;
+        if amount as i64 > available {
+            panic!("Insufficient bankroll");
+        }
+        self.bankrolls[seat] = available - amount as i64;
+        player.bet = amount;
+        self.emit(GameEvent::BetPlaced { seat, amount });
+    }
+
+    // asks the strategy of every automated seat without a bet for its bet, no bet is placed
+    // once a seat is out of chips
+    pub fn place_automated_bets(&mut self) -> Result<(), OutOfChips> {
+        let mut bets = Vec::new();
+        for seat in 0..self.nb_seats() {
+            if !self.is_automated(seat) || self.players[seat].bet > 0 {
+                continue;
+            }
+            let mut strategy = self.strategies[seat].take().unwrap();
+            let amount = strategy.bet(&TableView::new(self, seat, None));
+            self.strategies[seat] = Some(strategy);
+            bets.push((seat, amount.ok_or(OutOfChips { seat })?));
+        }
+        for (seat, amount) in bets {
+            self.place_bet(seat, amount);
+        }
+        Ok(())
+    }
+
+    // the dealer shows an ace and no hand has played yet
+    pub fn insurance_offered(&self) -> bool {
+        !self.settled
+            && self.dealer.hand.len() == 2
+            && self.dealer.hand[0].get_card_value() == 11
+            && self.players.len() == self.nb_seats()
+            && self.players.iter().all(|player| {
+                player.hand.len() == 2 && !player.stood && !player.doubled && !player.surrendered
+            })
+    }
+
+    pub fn insure(&mut self, seat: usize) {
+        if seat >= self.nb_seats() {
+            panic!("Invalid seat index");
+        }
+        if !self.insurance_offered() {
+            panic!("Insurance is not offered");
+        }
+        let player = &mut self.players[seat];
+        // half the bet rounded down, there are no half chips
+        let amount = player.bet / 2;
+        if player.insurance > 0 || amount == 0 {
+            panic!("The seat cannot insure its bet");
+        }
+        if amount as i64 > self.bankrolls[seat] {
+            panic!("Insufficient bankroll");
+        }
+        player.insurance = amount;
+        self.bankrolls[seat] -= amount as i64;
+        self.emit(GameEvent::Insurance { seat, amount });
+    }
+
+    // asks the strategy of every automated seat that can afford it whether to insure
+    pub fn place_automated_insurance(&mut self) {
+        if !self.insurance_offered() {
+            return;
+        }
+        for seat in 0..self.nb_seats() {
+            let amount = self.players[seat].bet / 2;
+            if !self.is_automated(seat) || amount == 0 || amount as i64 > self.bankrolls[seat] {
+                continue;
+            }
+            let mut strategy = self.strategies[seat].take().unwrap();
+            // no hand is split yet, the first hand of the seat has its index
+            let insure = strategy.insure(&TableView::new(self, seat, Some(seat)));
+            self.strategies[seat] = Some(strategy);
+            if insure {
+                self.insure(seat);
+            }
         }
     }
 
     pub fn deal_initial_cards(&mut self) {
-        for player in &mut self.players {
-            player.add_card(self.deck.deal());
-            player.add_card(self.deck.deal());
+        for i in 0..self.players.len() {
+            self.player_hit(i);
+            self.player_hit(i);
         }
-        self.dealer.add_card(self.deck.deal());
-        self.dealer.add_card(self.deck.deal());
+        self.dealer.hide_hole_card();
+        self.deal_to_dealer(true);
+        self.deal_to_dealer(false);
     }
 
     pub fn player_hit(&mut self, player_index: usize) {
         if player_index >= self.players.len() {
             panic!("Invalid player index");
         }
+        let card = self.draw();
+        let to = Recipient::Hand {
+            hand: player_index,
+            seat: self.players[player_index].seat,
+        };
+        self.emit(GameEvent::CardDealt {
+            to,
+            card: card.clone(),
+        });
+        self.players[player_index].add_card(card);
+    }
+
+    pub fn player_stand(&mut self, player_index: usize) {
+        self.check_action(player_index, PlayerAction::Stand);
+        self.emit_action(player_index, PlayerAction::Stand);
+        self.players[player_index].stood = true;
+    }
+
+    pub fn player_double(&mut self, player_index: usize) {
+        self.check_action(player_index, PlayerAction::Double);
+        self.emit_action(player_index, PlayerAction::Double);
         let player = &mut self.players[player_index];
-        let card = self.deck.deal();
-        player.add_card(card);
+        self.bankrolls[player.seat] -= player.bet as i64;
+        player.bet *= 2;
+        player.doubled = true;
+        self.player_hit(player_index);
+    }
+
+    pub fn player_split(&mut self, player_index: usize) {
+        self.check_action(player_index, PlayerAction::Split);
+        self.emit_action(player_index, PlayerAction::Split);
+        let player = &mut self.players[player_index];
+        let second_card = player.hand.pop().unwrap();
+        player.split = true;
+        let split_hand = Player {
+            hand: vec![second_card],
+            seat: player.seat,
+            bet: player.bet,
+            split: true,
+            ..Default::default()
+        };
+        self.bankrolls[player.seat] -= player.bet as i64;
+        self.players.insert(player_index + 1, split_hand);
+        self.player_hit(player_index);
+        self.player_hit(player_index + 1);
+    }
+
+    pub fn player_surrender(&mut self, player_index: usize) {
+        self.check_action(player_index, PlayerAction::Surrender);
+        self.emit_action(player_index, PlayerAction::Surrender);
+        self.players[player_index].surrendered = true;
+    }
+
+    pub fn apply_action(&mut self, player_index: usize, action: PlayerAction) {
+        match action {
+            PlayerAction::Hit => {
+                self.check_action(player_index, action);
+                self.emit_action(player_index, action);
+                self.player_hit(player_index);
+            }
+            PlayerAction::Stand => self.player_stand(player_index),
+            PlayerAction::Double => self.player_double(player_index),
+            PlayerAction::Split => self.player_split(player_index),
+            PlayerAction::Surrender => self.player_surrender(player_index),
+        }
+    }
+
+    // plays one action of the hand that has to act when its seat is automated
+    pub fn play_automated_action(&mut self) -> Option<(usize, PlayerAction)> {
+        let Phase::PlayerTurn(hand) = self.phase() else {
+            return None;
+        };
+        let seat = self.players[hand].seat;
+        let mut strategy = self.strategies.get_mut(seat)?.take()?;
+        let action = strategy.action(&TableView::new(self, seat, Some(hand)));
+        self.strategies[seat] = Some(strategy);
+        self.apply_action(hand, action);
+        Some((hand, action))
+    }
+
+    // plays until every hand is done or a hand of a seat without strategy has to act
+    pub fn play_automated_hands(&mut self) {
+        while self.play_automated_action().is_some() {}
+    }
+
+    // plays a whole round when every seat is automated, returns the net result of each hand
+    pub fn play_round(&mut self) -> Result<Vec<i64>, OutOfChips> {
+        if !(0..self.nb_seats()).all(|seat| self.is_automated(seat)) {
+            panic!("Every seat must be automated");
+        }
+        self.new_round();
+        self.place_automated_bets()?;
+        self.deal_initial_cards();
+        self.place_automated_insurance();
+        self.play_automated_hands();
+        self.dealer_play();
+        Ok(self.settle())
+    }
+
+    pub fn legal_actions(&self, player_index: usize) -> Vec<PlayerAction> {
+        if player_index >= self.players.len() {
+            panic!("Invalid player index");
+        }
+        let player = &self.players[player_index];
+        if player.hand.len() < 2 || player.is_done() || self.dealer_has_blackjack() {
+            return Vec::new();
+        }
+        let mut actions = vec![PlayerAction::Hit, PlayerAction::Stand];
+        let can_afford = self.bankrolls[player.seat] >= player.bet as i64;
+        if player.hand.len() == 2 && can_afford && (!player.split || self.rules.double_after_split)
+        {
+            actions.push(PlayerAction::Double);
+        }
+        let seat_hands = self
+            .players
+            .iter()
+            .filter(|p| p.seat == player.seat)
+            .count();
+        if player.is_pair() && can_afford && seat_hands < self.rules.max_hands as usize {
+            actions.push(PlayerAction::Split);
+        }
+        if self.rules.surrender && player.hand.len() == 2 && !player.split {
+            actions.push(PlayerAction::Surrender);
+        }
+        actions
+    }
+
+    // the hand that has to act next, None once every hand is done or the dealer peeked a blackjack
+    pub fn current_hand(&self) -> Option<usize> {
+        if self.dealer.hand.is_empty() || self.dealer_has_blackjack() {
+            return None;
+        }
+        self.players.iter().position(|player| !player.is_done())
+    }
+
+    pub fn phase(&self) -> Phase {
+        if self.settled {
+            Phase::Settled
+        } else if self.dealer.hand.is_empty() {
+            Phase::Betting
+        } else if let Some(hand) = self.current_hand() {
+            Phase::PlayerTurn(hand)
+        } else {
+            Phase::DealerTurn
+        }
+    }
+
+    pub fn dealer_has_blackjack(&self) -> bool {
+        self.dealer.is_blackjack()
     }
 
     pub fn dealer_play(&mut self) {
-        while self.dealer.hand_value() < 17 {
-            let card = self.deck.deal();
-            self.dealer.add_card(card);
+        if self.dealer.is_hole_card_hidden() {
+            self.emit(GameEvent::HoleCardRevealed(self.dealer.hand[1].clone()));
+        }
+        self.dealer.reveal_hole_card();
+        while self.dealer.hand_value() < 17
+            || (self.rules.dealer_hits_soft_17
+                && self.dealer.hand_value() == 17
+                && self.dealer.is_soft())
+        {
+            self.deal_to_dealer(true);
         }
     }
 
+    // pays every hand once the dealer played, returns the net result of each hand
+    pub fn settle(&mut self) -> Vec<i64> {
+        if self.settled {
+            panic!("Round already settled");
+        }
+        self.settled = true;
+        let mut results = Vec::with_capacity(self.players.len());
+        for i in 0..self.players.len() {
+            let bet = self.players[i].bet;
+            let seat = self.players[i].seat;
+            let outcome = self.get_outcome(i);
+            let returned = match outcome {
+                PlayerOutcome::Win => 2 * bet,
+                PlayerOutcome::Blackjack => bet + self.rules.blackjack_win(bet),
+                PlayerOutcome::Push => bet,
+                // half the bet rounded down, the odd chip of an odd bet stays with the house
+                PlayerOutcome::Surrender => bet / 2,
+                PlayerOutcome::Lose | PlayerOutcome::Bust => 0,
+            };
+            let insurance = self.players[i].insurance;
+            let insurance_returned = if self.dealer_has_blackjack() {
+                3 * insurance
+            } else {
+                0
+            };
+            let returned = returned + insurance_returned;
+            let net = returned as i64 - bet as i64 - insurance as i64;
+            self.bankrolls[seat] += returned as i64;
+            self.emit(GameEvent::Outcome {
+                hand: i,
+                seat,
+                outcome,
+            });
+            self.emit(GameEvent::Payout { hand: i, seat, net });
+            results.push(net);
+        }
+        self.emit(GameEvent::RoundSettled);
+        results
+    }
+
     pub fn get_outcome(&self, player_index: usize) -> PlayerOutcome {
         if player_index >= self.players.len() {
             panic!("Invalid player index");
         }
-        self.players[player_index].get_outcome(self.dealer.hand_value())
+        let player = &self.players[player_index];
+        if player.surrendered || player.is_bust() {
+            return player.get_outcome(self.dealer.hand_value());
+        }
+        if self.dealer_has_blackjack() && !player.is_blackjack() {
+            return PlayerOutcome::Lose;
+        }
+        if player.is_blackjack() && !self.dealer_has_blackjack() {
+            return PlayerOutcome::Blackjack;
+        }
+        player.get_outcome(self.dealer.hand_value())
     }
 
     pub fn get_player_hand(&self, player_index: usize) -> &Vec<Card> {
@@ -85,6 +539,66 @@ impl Game {
     pub fn get_dealer_hand_value(&self) -> u8 {
         self.dealer.hand_value()
     }
+
+    // the discards are shuffled back in when the shoe runs out in the middle of a round
+    fn draw(&mut self) -> Card {
+        if self.deck.cards.is_empty() && !self.discards.is_empty() {
+            self.reshuffle();
+        }
+        self.deck.deal()
+    }
+
+    fn deal_to_dealer(&mut self, face_up: bool) {
+        let card = self.draw();
+        if face_up {
+            self.emit(GameEvent::CardDealt {
+                to: Recipient::Dealer,
+                card: card.clone(),
+            });
+        } else {
+            self.emit(GameEvent::HoleCardDealt);
+        }
+        self.dealer.add_card(card);
+    }
+
+    fn reshuffle(&mut self) {
+        let mut key = [0; 32];
+        key[..8].copy_from_slice(&self.seed.to_le_bytes());
+        key[8..16].copy_from_slice(&self.shuffles.to_le_bytes());
+        self.shuffles += 1;
+        self.deck.cards.append(&mut self.discards);
+        self.deck.shuffle_with_rng(&mut StdRng::from_seed(key));
+        self.emit(GameEvent::Shuffle);
+        for _ in 0..self.rules.burn_cards {
+            if let Some(card) = self.deck.cards.pop() {
+                self.emit(GameEvent::Burn);
+                self.discards.push(card);
+            }
+        }
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        for observer in &mut self.observers {
+            observer.on_event(&event);
+        }
+        for strategy in self.strategies.iter_mut().flatten() {
+            strategy.on_event(&event);
+        }
+    }
+
+    fn emit_action(&mut self, player_index: usize, action: PlayerAction) {
+        self.emit(GameEvent::Action {
+            hand: player_index,
+            seat: self.players[player_index].seat,
+            action,
+        });
+    }
+
+    fn check_action(&self, player_index: usize, action: PlayerAction) {
+        if !self.legal_actions(player_index).contains(&action) {
+            panic!("Illegal action");
+        }
+    }
 }
 
 #[cfg(test)]
@@ -96,6 +610,120 @@ mod tests {
         Card::new(value, Suit::get_random_suit())
     }
 
+    // the deck deals from the end, the values are given in dealing order
+    fn stack_deck(game: &mut Game, values: Vec<CardValue>) {
+        game.deck = Deck::from_vec(values.into_iter().rev().map(card).collect());
+    }
+
+    #[test]
+    fn automated_seats_play_whole_rounds() {
+        let mut game = Game::with_seed(4, TableRules::default(), 9);
+        for (seat, name) in crate::autoplay::STRATEGIES.into_iter().enumerate() {
+            let strategy = crate::autoplay::strategy_by_name(name, &game.rules, 10, 9).unwrap();
+            game.set_strategy(seat, strategy);
+        }
+        let mut net = vec![0; 4];
+        for _ in 0..200 {
+            let results = game.play_round().unwrap();
+            assert_eq!(game.phase(), Phase::Settled);
+            for (hand, result) in results.into_iter().enumerate() {
+                net[game.players[hand].seat] += result;
+            }
+        }
+        for (seat, net) in net.into_iter().enumerate() {
+            assert_eq!(game.get_bankroll(seat), DEFAULT_BANKROLL + net);
+        }
+    }
+
+    #[test]
+    fn automated_hands_wait_for_the_human_seats() {
+        let mut game = Game::with_seed(2, TableRules::default(), 9);
+        game.set_strategy(1, Box::new(crate::autoplay::NeverBust { bet: 5 }));
+        assert!(!game.is_automated(0));
+        assert!(game.is_automated(1));
+        game.new_round();
+        game.place_bet(0, 10);
+        game.place_automated_bets().unwrap();
+        assert_eq!(game.players[1].bet, 5);
+        stack_deck(
+            &mut game,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(2),
+                CardValue::Number(9),
+                CardValue::Number(8),
+            ],
+        );
+        game.deal_initial_cards();
+        assert_eq!(game.play_automated_action(), None);
+        game.player_stand(0);
+        assert_eq!(game.play_automated_action(), Some((1, PlayerAction::Stand)));
+        assert_eq!(game.phase(), Phase::DealerTurn);
+    }
+
+    #[test]
+    fn strategies_see_the_events() {
+        use std::{cell::RefCell, rc::Rc};
+
+        struct Counting {
+            cards: Rc<RefCell<u32>>,
+        }
+        impl PlayerStrategy for Counting {
+            fn bet(&mut self, _view: &TableView) -> Option<u32> {
+                Some(10)
+            }
+            fn action(&mut self, _view: &TableView) -> PlayerAction {
+                PlayerAction::Stand
+            }
+            fn on_event(&mut self, event: &GameEvent) {
+                if let GameEvent::CardDealt { .. } | GameEvent::HoleCardRevealed(_) = event {
+                    *self.cards.borrow_mut() += 1;
+                }
+            }
+        }
+        let cards = Rc::new(RefCell::new(0));
+        let mut game = Game::with_seed(1, TableRules::default(), 9);
+        game.set_strategy(
+            0,
+            Box::new(Counting {
+                cards: cards.clone(),
+            }),
+        );
+        game.play_round().unwrap();
+        let dealt = game.players[0].hand.len() + game.dealer.hand.len();
+        assert_eq!(*cards.borrow() as usize, dealt);
+    }
+
+    #[test]
+    fn automated_seat_stops_once_out_of_chips() {
+        let mut game = Game::with_seed(2, TableRules::default(), 9);
+        game.set_bankroll(1, 30);
+        for seat in 0..2 {
+            game.set_strategy(seat, Box::new(crate::autoplay::NeverBust { bet: 10 }));
+        }
+        let error = loop {
+            match game.play_round() {
+                Ok(_) => assert!(game.get_bankroll(1) >= 0),
+                Err(error) => break error,
+            }
+        };
+        assert_eq!(error, OutOfChips { seat: 1 });
+        assert_eq!(error.to_string(), "Player 2 is out of chips");
+        assert_eq!(game.get_bankroll(1), 0);
+        assert!(game.players.iter().all(|player| player.bet == 0));
+        assert_eq!(game.phase(), Phase::Betting);
+    }
+
+    #[test]
+    #[should_panic(expected = "Every seat must be automated")]
+    fn play_round_needs_every_seat_automated() {
+        let mut game = Game::with_seed(2, TableRules::default(), 9);
+        game.set_strategy(1, Box::new(crate::autoplay::NeverBust { bet: 5 }));
+        game.play_round().unwrap();
+    }
+
     #[test]
     fn test_game_initialization() {
         let game = Game::new(2, 10);
@@ -245,6 +873,493 @@ mod tests {
         assert_eq!(game.dealer.hand.len(), 0);
         game.dealer_play();
         assert!(game.dealer.hand_value() >= 17);
-        assert!(game.dealer.hand.len() > 0);
+        assert!(!game.dealer.hand.is_empty());
+    }
+
+    #[test]
+    fn dealer_hole_card_hidden_until_dealer_play() {
+        let mut game = Game::new(1, 20);
+        game.deal_initial_cards();
+        assert!(game.dealer.is_hole_card_hidden());
+        game.dealer_play();
+        assert!(!game.dealer.is_hole_card_hidden());
+    }
+
+    #[test]
+    fn same_seed_deals_same_cards() {
+        let rules = TableRules {
+            decks: 1,
+            ..Default::default()
+        };
+        let mut first = Game::with_seed(2, rules.clone(), 42);
+        let mut second = Game::with_seed(2, rules.clone(), 42);
+        let other = Game::with_seed(2, rules, 43);
+        assert_eq!(first.deck.cards, second.deck.cards);
+        assert_ne!(first.deck.cards, other.deck.cards);
+
+        // reshuffles are seeded as well
+        for _ in 0..20 {
+            first.new_round();
+            first.deal_initial_cards();
+            second.new_round();
+            second.deal_initial_cards();
+            assert_eq!(first.players[0].hand, second.players[0].hand);
+        }
+        assert!(first.shuffles > 1);
+    }
+
+    #[test]
+    fn phase_follows_the_round() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        stack_deck(
+            &mut game,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(7),
+            ],
+        );
+        assert_eq!(game.phase(), Phase::Betting);
+        game.place_bet(0, 10);
+        game.deal_initial_cards();
+        assert_eq!(game.phase(), Phase::PlayerTurn(0));
+        game.player_stand(0);
+        assert_eq!(game.phase(), Phase::DealerTurn);
+        game.dealer_play();
+        game.settle();
+        assert_eq!(game.phase(), Phase::Settled);
+        game.new_round();
+        assert_eq!(game.phase(), Phase::Betting);
+    }
+
+    #[test]
+    fn place_bet_takes_from_bankroll() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.place_bet(0, 10);
+        assert_eq!(game.get_bankroll(0), DEFAULT_BANKROLL - 10);
+        game.place_bet(0, 25);
+        assert_eq!(game.get_bankroll(0), DEFAULT_BANKROLL - 25);
+        assert_eq!(game.players[0].bet, 25);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient bankroll")]
+    fn place_bet_over_bankroll_panics() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.set_bankroll(0, 5);
+        game.place_bet(0, 10);
+    }
+
+    #[test]
+    fn double_doubles_bet_and_draws_one_card() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        stack_deck(
+            &mut game,
+            vec![
+                CardValue::Number(5),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(7),
+                CardValue::Number(3),
+            ],
+        );
+        game.place_bet(0, 10);
+        game.deal_initial_cards();
+        assert!(game.legal_actions(0).contains(&PlayerAction::Double));
+        game.apply_action(0, PlayerAction::Double);
+        assert_eq!(game.players[0].bet, 20);
+        assert_eq!(game.get_player_hand_value(0), 14);
+        assert_eq!(game.current_hand(), None);
+        assert_eq!(game.get_bankroll(0), DEFAULT_BANKROLL - 20);
+    }
+
+    #[test]
+    fn split_creates_second_hand_for_the_same_seat() {
+        let mut game = Game::with_rules(2, TableRules::default());
+        stack_deck(
+            &mut game,
+            vec![
+                CardValue::Number(8),
+                CardValue::Number(8),
+                CardValue::Number(10),
+                CardValue::Number(9),
+                CardValue::Number(10),
+                CardValue::Number(7),
+                CardValue::Number(3),
+                CardValue::Number(2),
+            ],
+        );
+        game.place_bet(0, 10);
+        game.deal_initial_cards();
+        assert!(game.legal_actions(0).contains(&PlayerAction::Split));
+        game.apply_action(0, PlayerAction::Split);
+
+        assert_eq!(game.players.len(), 3);
+        assert_eq!(game.players[1].seat, 0);
+        assert_eq!(game.players[2].seat, 1);
+        assert_eq!(game.get_player_hand_value(0), 11);
+        assert_eq!(game.get_player_hand_value(1), 10);
+        assert_eq!(game.get_bankroll(0), DEFAULT_BANKROLL - 20);
+        assert!(!game.legal_actions(1).contains(&PlayerAction::Surrender));
+    }
+
+    #[test]
+    fn split_hand_with_21_is_not_blackjack() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.players[0].hand = vec![card(CardValue::Ace), card(CardValue::King)];
+        game.players[0].split = true;
+        game.dealer.hand = vec![card(CardValue::Number(10)), card(CardValue::Number(8))];
+        assert_eq!(game.get_outcome(0), PlayerOutcome::Win);
+    }
+
+    #[test]
+    fn surrender_gives_back_half_the_bet() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        stack_deck(
+            &mut game,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(9),
+            ],
+        );
+        game.place_bet(0, 10);
+        game.deal_initial_cards();
+        game.apply_action(0, PlayerAction::Surrender);
+        game.dealer_play();
+        assert_eq!(game.get_outcome(0), PlayerOutcome::Surrender);
+        assert_eq!(game.settle(), vec![-5]);
+        assert_eq!(game.get_bankroll(0), DEFAULT_BANKROLL - 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Illegal action")]
+    fn surrender_not_allowed_by_rules_panics() {
+        let rules = TableRules {
+            surrender: false,
+            ..Default::default()
+        };
+        let mut game = Game::with_rules(1, rules);
+        game.players[0].hand = vec![card(CardValue::Number(10)), card(CardValue::Number(6))];
+        game.dealer.hand = vec![card(CardValue::Number(10)), card(CardValue::Number(9))];
+        game.player_surrender(0);
+    }
+
+    #[test]
+    fn settle_pays_blackjack_three_to_two() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.place_bet(0, 10);
+        game.players[0].hand = vec![card(CardValue::Ace), card(CardValue::King)];
+        game.dealer.hand = vec![
+            card(CardValue::Number(10)),
+            card(CardValue::Number(5)),
+            card(CardValue::Number(6)),
+        ];
+        assert_eq!(game.get_outcome(0), PlayerOutcome::Blackjack);
+        assert_eq!(game.settle(), vec![15]);
+        assert_eq!(game.get_bankroll(0), DEFAULT_BANKROLL + 15);
+    }
+
+    #[test]
+    #[should_panic(expected = "Round already settled")]
+    fn settle_twice_panics() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.settle();
+        game.settle();
+    }
+
+    #[test]
+    fn dealer_blackjack_beats_player_21() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.players[0].hand = vec![
+            card(CardValue::Number(7)),
+            card(CardValue::Number(7)),
+            card(CardValue::Number(7)),
+        ];
+        game.dealer.hand = vec![card(CardValue::Ace), card(CardValue::Queen)];
+        assert_eq!(game.get_outcome(0), PlayerOutcome::Lose);
+    }
+
+    #[test]
+    fn bust_is_reported_even_against_dealer_blackjack() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.players[0].hand = vec![
+            card(CardValue::Number(10)),
+            card(CardValue::Number(10)),
+            card(CardValue::Number(10)),
+        ];
+        game.dealer.hand = vec![card(CardValue::Ace), card(CardValue::Queen)];
+        assert_eq!(game.get_outcome(0), PlayerOutcome::Bust);
+    }
+
+    #[test]
+    fn no_hand_acts_when_dealer_has_blackjack() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.players[0].hand = vec![card(CardValue::Number(10)), card(CardValue::Number(6))];
+        game.dealer.hand = vec![card(CardValue::Ace), card(CardValue::Queen)];
+        assert_eq!(game.current_hand(), None);
+        assert!(game.legal_actions(0).is_empty());
+    }
+
+    #[test]
+    fn insurance_pays_two_to_one() {
+        let mut game = Game::with_rules(2, TableRules::default());
+        game.place_bet(0, 10);
+        game.place_bet(1, 20);
+        game.players[0].hand = vec![card(CardValue::Number(10)), card(CardValue::Number(6))];
+        game.players[1].hand = vec![card(CardValue::Number(9)), card(CardValue::Number(9))];
+        game.dealer.hand = vec![card(CardValue::Ace), card(CardValue::Queen)];
+        assert!(game.insurance_offered());
+        game.insure(0);
+        assert_eq!(game.get_bankroll(0), DEFAULT_BANKROLL - 15);
+        assert_eq!(game.settle(), vec![0, -20]);
+        assert_eq!(game.get_bankroll(0), DEFAULT_BANKROLL);
+
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.place_bet(0, 10);
+        game.players[0].hand = vec![card(CardValue::Number(10)), card(CardValue::Number(9))];
+        game.dealer.hand = vec![card(CardValue::Ace), card(CardValue::Number(7))];
+        game.insure(0);
+        game.players[0].stood = true;
+        assert!(!game.insurance_offered());
+        assert_eq!(game.settle(), vec![5]);
+    }
+
+    #[test]
+    fn odd_bets_are_halved_rounding_down() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.place_bet(0, 15);
+        game.players[0].hand = vec![card(CardValue::Number(10)), card(CardValue::Number(6))];
+        game.dealer.hand = vec![card(CardValue::Ace), card(CardValue::Number(7))];
+        game.insure(0);
+        assert_eq!(game.players[0].insurance, 7);
+        game.apply_action(0, PlayerAction::Surrender);
+        assert_eq!(game.settle(), vec![-8 - 7]);
+        assert_eq!(game.get_bankroll(0), DEFAULT_BANKROLL - 15);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insurance is not offered")]
+    fn insurance_needs_an_ace() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.place_bet(0, 10);
+        game.players[0].hand = vec![card(CardValue::Number(10)), card(CardValue::Number(6))];
+        game.dealer.hand = vec![card(CardValue::Number(10)), card(CardValue::Ace)];
+        game.insure(0);
+    }
+
+    #[test]
+    fn dealer_hits_soft_17_when_rules_say_so() {
+        let rules = TableRules {
+            dealer_hits_soft_17: true,
+            ..Default::default()
+        };
+        let mut game = Game::with_rules(1, rules);
+        stack_deck(&mut game, vec![CardValue::Number(2)]);
+        game.dealer.hand = vec![card(CardValue::Ace), card(CardValue::Number(6))];
+        game.dealer_play();
+        assert_eq!(game.get_dealer_hand_value(), 19);
+    }
+
+    #[test]
+    fn dealer_stands_on_soft_17_by_default() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.dealer.hand = vec![card(CardValue::Ace), card(CardValue::Number(6))];
+        game.dealer_play();
+        assert_eq!(game.dealer.hand.len(), 2);
+    }
+
+    #[test]
+    fn new_round_moves_cards_to_discards() {
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.discards.clear();
+        game.deal_initial_cards();
+        game.players[0].hand.push(card(CardValue::Number(2)));
+        game.players.push(Player {
+            hand: vec![card(CardValue::Number(3))],
+            ..Default::default()
+        });
+        game.new_round();
+        assert_eq!(game.discards.len(), 6);
+        assert_eq!(game.players.len(), 1);
+        assert!(game.players[0].hand.is_empty());
+        assert!(game.dealer.hand.is_empty());
+    }
+
+    #[test]
+    fn new_round_reshuffles_past_penetration() {
+        let rules = TableRules {
+            decks: 1,
+            penetration: 0.5,
+            ..Default::default()
+        };
+        let mut game = Game::with_rules(1, rules);
+        for _ in 0..6 {
+            game.new_round();
+            game.deal_initial_cards();
+            for _ in 0..3 {
+                game.player_hit(0);
+            }
+        }
+        game.new_round();
+        assert!(game.discards.len() < 26);
+        assert_eq!(game.deck.cards.len() + game.discards.len(), 52);
+    }
+
+    #[test]
+    fn draw_reshuffles_discards_when_shoe_is_empty() {
+        let rules = TableRules {
+            burn_cards: 0,
+            ..Default::default()
+        };
+        let mut game = Game::with_rules(1, rules);
+        stack_deck(&mut game, vec![CardValue::Number(2)]);
+        game.discards = vec![card(CardValue::Number(9))];
+        game.player_hit(0);
+        game.player_hit(0);
+        assert_eq!(game.get_player_hand_value(0), 11);
+        assert!(game.discards.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn saved_mid_round_game_resumes_identically() {
+        let mut game = Game::with_seed(2, TableRules::default(), 7);
+        game.new_round();
+        game.place_bet(0, 10);
+        game.place_bet(1, 20);
+        game.deal_initial_cards();
+        game.player_hit(0);
+
+        let json = serde_json::to_string(&game).unwrap();
+        let mut restored: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+
+        // play the rest of the session on both games, including reshuffles
+        for current in [&mut game, &mut restored] {
+            for _ in 0..50 {
+                while let Some(hand) = current.current_hand() {
+                    current.player_stand(hand);
+                }
+                current.dealer_play();
+                current.settle();
+                current.new_round();
+                current.place_bet(0, 1);
+                current.deal_initial_cards();
+            }
+        }
+        assert!(game.shuffles > 1);
+        assert_eq!(
+            serde_json::to_string(&restored).unwrap(),
+            serde_json::to_string(&game).unwrap()
+        );
+    }
+
+    #[test]
+    fn shuffle_burns_cards() {
+        let game = Game::with_rules(1, TableRules::default());
+        assert_eq!(game.discards.len(), 1);
+        assert_eq!(game.deck.cards.len(), 6 * 52 - 1);
+    }
+
+    #[test]
+    fn observers_see_the_whole_round() {
+        use crate::events::EventLog;
+        use std::{cell::RefCell, rc::Rc};
+
+        let log = Rc::new(RefCell::new(EventLog::default()));
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.subscribe(Box::new(log.clone()));
+        game.new_round();
+        stack_deck(
+            &mut game,
+            vec![
+                CardValue::Number(10),
+                CardValue::Number(6),
+                CardValue::Number(10),
+                CardValue::Number(7),
+                CardValue::Number(5),
+            ],
+        );
+        game.place_bet(0, 10);
+        game.deal_initial_cards();
+        game.apply_action(0, PlayerAction::Hit);
+        game.dealer_play();
+        game.settle();
+
+        let hand = Recipient::Hand { hand: 0, seat: 0 };
+        let dealt = |to, value| GameEvent::CardDealt {
+            to,
+            card: Card::new(value, Suit::Spades),
+        };
+        let events: Vec<GameEvent> = log
+            .borrow()
+            .events
+            .iter()
+            .map(|event| match event {
+                // suits are random in the stacked deck
+                GameEvent::CardDealt { to, card } => GameEvent::CardDealt {
+                    to: *to,
+                    card: Card::new(card.value.clone(), Suit::Spades),
+                },
+                GameEvent::HoleCardRevealed(card) => {
+                    GameEvent::HoleCardRevealed(Card::new(card.value.clone(), Suit::Spades))
+                }
+                other => other.clone(),
+            })
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                GameEvent::RoundStarted {
+                    round: 1,
+                    bankrolls: vec![DEFAULT_BANKROLL]
+                },
+                GameEvent::BetPlaced {
+                    seat: 0,
+                    amount: 10
+                },
+                dealt(hand, CardValue::Number(10)),
+                dealt(hand, CardValue::Number(6)),
+                dealt(Recipient::Dealer, CardValue::Number(10)),
+                GameEvent::HoleCardDealt,
+                GameEvent::Action {
+                    hand: 0,
+                    seat: 0,
+                    action: PlayerAction::Hit
+                },
+                dealt(hand, CardValue::Number(5)),
+                GameEvent::HoleCardRevealed(Card::new(CardValue::Number(7), Suit::Spades)),
+                GameEvent::Outcome {
+                    hand: 0,
+                    seat: 0,
+                    outcome: PlayerOutcome::Win
+                },
+                GameEvent::Payout {
+                    hand: 0,
+                    seat: 0,
+                    net: 10
+                },
+                GameEvent::RoundSettled,
+            ]
+        );
+    }
+
+    #[test]
+    fn observers_see_shuffle_and_burn() {
+        use crate::events::EventLog;
+        use std::{cell::RefCell, rc::Rc};
+
+        let log = Rc::new(RefCell::new(EventLog::default()));
+        let mut game = Game::with_rules(1, TableRules::default());
+        game.subscribe(Box::new(log.clone()));
+        game.discards = game.deck.cards.drain(..).collect();
+        game.new_round();
+
+        let events = &log.borrow().events;
+        assert_eq!(events[1], GameEvent::Shuffle);
+        assert_eq!(events[2], GameEvent::Burn);
     }
 }
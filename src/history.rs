@@ -0,0 +1,638 @@
+//! Hand history, one record per round, written as lines of text.
+//!
+//! Every line is a keyword followed by space separated fields. Seats and hands
+//! are numbered from 1 and cards use the short form of `Card`, e.g. `10H` or `AS`.
+//! Hands are numbered within their seat: the hand dealt is 1 and a hand split off
+//! another takes the next number of the seat, so a hand keeps its number all round.
+//!
+//! ```text
+//! ROUND <round>                  starts a record
+//! SEAT <seat> <bankroll>         one line per seat, bankroll before betting
+//! SHUFFLE
+//! BURN                           a card burned face down
+//! BET <seat> <amount>
+//! DEAL <seat> <hand> <card>      card dealt to a player hand
+//! DEAL dealer <card>             card dealt face up to the dealer
+//! DEAL dealer down               the hole card, dealt face down
+//! INSURE <seat> <amount>         insurance against a dealer ace
+//! REVEAL <card>                  the dealer turns the hole card over
+//! ACTION <seat> <hand> <action>  hit, stand, double, split or surrender
+//! OUTCOME <seat> <hand> <outcome>
+//!                                win, lose, bust, blackjack, push or surrender
+//! PAYOUT <seat> <hand> <net>     net result of the hand, e.g. +15 or -10, with the
+//!                                insurance of the seat on its first hand
+//! END                            ends the record
+//! ```
+//!
+//! Blank lines and lines starting with `#` are ignored.
+
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+use crate::{
+    events::{GameEvent, GameObserver, Recipient},
+    player::PlayerAction,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandRecord {
+    pub round: u64,
+    pub bankrolls: Vec<i64>,
+    // every event of the round between its start and its settlement, with the hands
+    // numbered within their seat
+    pub events: Vec<GameEvent>,
+}
+
+impl fmt::Display for HandRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ROUND {}", self.round)?;
+        for (seat, bankroll) in self.bankrolls.iter().enumerate() {
+            writeln!(f, "SEAT {} {bankroll}", seat + 1)?;
+        }
+        for event in &self.events {
+            match event {
+                GameEvent::Shuffle => writeln!(f, "SHUFFLE")?,
+                GameEvent::Burn => writeln!(f, "BURN")?,
+                GameEvent::BetPlaced { seat, amount } => writeln!(f, "BET {} {amount}", seat + 1)?,
+                GameEvent::CardDealt {
+                    to: Recipient::Hand { hand, seat },
+                    card,
+                } => writeln!(f, "DEAL {} {} {card}", seat + 1, hand + 1)?,
+                GameEvent::CardDealt {
+                    to: Recipient::Dealer,
+                    card,
+                } => writeln!(f, "DEAL dealer {card}")?,
+                GameEvent::HoleCardDealt => writeln!(f, "DEAL dealer down")?,
+                GameEvent::HoleCardRevealed(card) => writeln!(f, "REVEAL {card}")?,
+                GameEvent::Insurance { seat, amount } => {
+                    writeln!(f, "INSURE {} {amount}", seat + 1)?
+                }
+                GameEvent::Action { hand, seat, action } => {
+                    writeln!(f, "ACTION {} {} {action}", seat + 1, hand + 1)?
+                }
+                GameEvent::Outcome {
+                    hand,
+                    seat,
+                    outcome,
+                } => writeln!(f, "OUTCOME {} {} {outcome}", seat + 1, hand + 1)?,
+                GameEvent::Payout { hand, seat, net } => {
+                    writeln!(f, "PAYOUT {} {} {net:+}", seat + 1, hand + 1)?
+                }
+                GameEvent::RoundStarted { .. } | GameEvent::RoundSettled => {}
+            }
+        }
+        writeln!(f, "END")
+    }
+}
+
+#[derive(Debug)]
+pub enum HistoryError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::Io(error) => write!(f, "{error}"),
+            HistoryError::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+impl From<io::Error> for HistoryError {
+    fn from(error: io::Error) -> Self {
+        HistoryError::Io(error)
+    }
+}
+
+// observer writing a record to the writer every time a round is settled
+pub struct HandHistoryWriter<W: Write> {
+    writer: W,
+    current: Option<HandRecord>,
+    // the seat and the number within it of every hand of the table, in playing order
+    hands: Vec<(usize, usize)>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> HandHistoryWriter<W> {
+    pub fn new(writer: W) -> Self {
+        HandHistoryWriter {
+            writer,
+            current: None,
+            hands: Vec::new(),
+            error: None,
+        }
+    }
+
+    // the first write error, the following records are dropped once writing failed
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    fn write_record(&mut self, record: &HandRecord) -> io::Result<()> {
+        writeln!(self.writer, "{record}")?;
+        self.writer.flush()
+    }
+
+    // follows the hands of the table to renumber the event within its seat, the events of
+    // the game count the hands over the whole table, which shifts them on a split. a hand
+    // whose deal was not seen, in a game loaded mid round, keeps its table index.
+    fn renumber(&mut self, event: &GameEvent) -> GameEvent {
+        let mut event = event.clone();
+        match &mut event {
+            GameEvent::CardDealt {
+                to: Recipient::Hand { hand, seat },
+                ..
+            } => {
+                // the first card of the hand of the seat, dealt with the round
+                if *hand == self.hands.len() {
+                    self.hands.push((*seat, 0));
+                }
+                *hand = self.number(*hand);
+            }
+            GameEvent::Action { hand, seat, action } => {
+                if *action == PlayerAction::Split && *hand < self.hands.len() {
+                    let next = self.hands.iter().filter(|(of, _)| of == seat).count();
+                    self.hands.insert(*hand + 1, (*seat, next));
+                }
+                *hand = self.number(*hand);
+            }
+            GameEvent::Outcome { hand, .. } | GameEvent::Payout { hand, .. } => {
+                *hand = self.number(*hand);
+            }
+            _ => {}
+        }
+        event
+    }
+
+    fn number(&self, hand: usize) -> usize {
+        self.hands.get(hand).map_or(hand, |(_, number)| *number)
+    }
+}
+
+impl<W: Write> GameObserver for HandHistoryWriter<W> {
+    fn on_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::RoundStarted { round, bankrolls } => {
+                self.hands.clear();
+                self.current = Some(HandRecord {
+                    round: *round,
+                    bankrolls: bankrolls.clone(),
+                    events: Vec::new(),
+                });
+            }
+            GameEvent::RoundSettled => {
+                if let Some(record) = self.current.take()
+                    && self.error.is_none()
+                    && let Err(error) = self.write_record(&record)
+                {
+                    self.error = Some(error);
+                }
+            }
+            event => {
+                if self.current.is_some() {
+                    let event = self.renumber(event);
+                    self.current.as_mut().unwrap().events.push(event);
+                }
+            }
+        }
+    }
+}
+
+pub fn parse_history<R: BufRead>(reader: R) -> Result<Vec<HandRecord>, HistoryError> {
+    let mut records = Vec::new();
+    let mut current: Option<HandRecord> = None;
+    let mut number = 0;
+    for line in reader.lines() {
+        let line = line?;
+        number += 1;
+        let error = |message: String| HistoryError::Parse {
+            line: number,
+            message,
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() || words[0].starts_with('#') {
+            continue;
+        }
+
+        let record = match (words[0], &mut current) {
+            ("ROUND", None) => {
+                let [_, round] = words[..] else {
+                    return Err(error("expected ROUND <round>".to_string()));
+                };
+                current = Some(HandRecord {
+                    round: parse_field(round).map_err(error)?,
+                    bankrolls: Vec::new(),
+                    events: Vec::new(),
+                });
+                continue;
+            }
+            (_, None) => return Err(error(format!("{} outside of a round", words[0]))),
+            ("ROUND", Some(_)) => return Err(error("ROUND before END".to_string())),
+            ("END", Some(_)) => {
+                records.push(current.take().unwrap());
+                continue;
+            }
+            (_, Some(record)) => record,
+        };
+
+        match words[..] {
+            ["SEAT", seat, bankroll] => {
+                let seat = parse_number(seat).map_err(error)?;
+                if seat != record.bankrolls.len() {
+                    return Err(error(format!(
+                        "expected seat {}",
+                        record.bankrolls.len() + 1
+                    )));
+                }
+                record.bankrolls.push(parse_field(bankroll).map_err(error)?);
+            }
+            _ => {
+                let event = parse_event(&words).map_err(error)?;
+                record.events.push(event);
+            }
+        }
+    }
+    if current.is_some() {
+        return Err(HistoryError::Parse {
+            line: number,
+            message: "last round has no END".to_string(),
+        });
+    }
+    Ok(records)
+}
+
+// bankroll of every seat before each round and after the last one, seats missing from a
+// record are skipped
+pub fn seat_bankrolls(records: &[HandRecord]) -> Vec<Vec<i64>> {
+    let seats = records.iter().map(|r| r.bankrolls.len()).max().unwrap_or(0);
+    let mut bankrolls = vec![Vec::new(); seats];
+    for record in records {
+        for (seat, bankroll) in record.bankrolls.iter().enumerate() {
+            bankrolls[seat].push(*bankroll);
+        }
+    }
+    if let Some(last) = records.last() {
+        for (seat, bankroll) in last.bankrolls.iter().enumerate() {
+            let net: i64 = last
+                .events
+                .iter()
+                .map(|event| match event {
+                    GameEvent::Payout { seat: s, net, .. } if *s == seat => *net,
+                    _ => 0,
+                })
+                .sum();
+            bankrolls[seat].push(bankroll + net);
+        }
+    }
+    bankrolls
+}
+
+fn parse_event(words: &[&str]) -> Result<GameEvent, String> {
+    let event = match *words {
+        ["SHUFFLE"] => GameEvent::Shuffle,
+        ["BURN"] => GameEvent::Burn,
+        ["BET", seat, amount] => GameEvent::BetPlaced {
+            seat: parse_number(seat)?,
+            amount: parse_field(amount)?,
+        },
+        ["DEAL", "dealer", "down"] => GameEvent::HoleCardDealt,
+        ["DEAL", "dealer", card] => GameEvent::CardDealt {
+            to: Recipient::Dealer,
+            card: parse_field(card)?,
+        },
+        ["DEAL", seat, hand, card] => GameEvent::CardDealt {
+            to: Recipient::Hand {
+                hand: parse_number(hand)?,
+                seat: parse_number(seat)?,
+            },
+            card: parse_field(card)?,
+        },
+        ["REVEAL", card] => GameEvent::HoleCardRevealed(parse_field(card)?),
+        ["INSURE", seat, amount] => GameEvent::Insurance {
+            seat: parse_number(seat)?,
+            amount: parse_field(amount)?,
+        },
+        ["ACTION", seat, hand, action] => GameEvent::Action {
+            hand: parse_number(hand)?,
+            seat: parse_number(seat)?,
+            action: parse_field(action)?,
+        },
+        ["OUTCOME", seat, hand, outcome] => GameEvent::Outcome {
+            hand: parse_number(hand)?,
+            seat: parse_number(seat)?,
+            outcome: parse_field(outcome)?,
+        },
+        ["PAYOUT", seat, hand, net] => GameEvent::Payout {
+            hand: parse_number(hand)?,
+            seat: parse_number(seat)?,
+            net: parse_field(net)?,
+        },
+        _ => return Err(format!("invalid line: {}", words.join(" "))),
+    };
+    Ok(event)
+}
+
+fn parse_field<T: FromStr>(field: &str) -> Result<T, String> {
+    field.parse().map_err(|_| format!("invalid field: {field}"))
+}
+
+// seats and hands are written from 1
+fn parse_number(field: &str) -> Result<usize, String> {
+    match field.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n - 1),
+        _ => Err(format!("invalid seat or hand: {field}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::{
+        card::Card,
+        deck::Deck,
+        game::Game,
+        player::{PlayerAction, PlayerOutcome},
+        rules::TableRules,
+    };
+
+    // shares its buffer so the test can read what the writer wrote
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn play_rounds(game: &mut Game, rounds: usize) {
+        for _ in 0..rounds {
+            game.new_round();
+            for seat in 0..game.nb_seats() {
+                game.place_bet(seat, 10);
+            }
+            game.deal_initial_cards();
+            while let Some(hand) = game.current_hand() {
+                let actions = game.legal_actions(hand);
+                let action = if actions.contains(&PlayerAction::Split) {
+                    PlayerAction::Split
+                } else if game.get_player_hand_value(hand) < 12 {
+                    PlayerAction::Double
+                } else {
+                    PlayerAction::Stand
+                };
+                game.apply_action(hand, action);
+            }
+            game.dealer_play();
+            game.settle();
+        }
+    }
+
+    #[test]
+    fn record_is_written_as_documented() {
+        let card = |text: &str| text.parse::<Card>().unwrap();
+        let record = HandRecord {
+            round: 3,
+            bankrolls: vec![1000, 500],
+            events: vec![
+                GameEvent::Shuffle,
+                GameEvent::Burn,
+                GameEvent::BetPlaced {
+                    seat: 1,
+                    amount: 20,
+                },
+                GameEvent::CardDealt {
+                    to: Recipient::Hand { hand: 1, seat: 1 },
+                    card: card("10H"),
+                },
+                GameEvent::CardDealt {
+                    to: Recipient::Dealer,
+                    card: card("AH"),
+                },
+                GameEvent::HoleCardDealt,
+                GameEvent::Insurance {
+                    seat: 1,
+                    amount: 10,
+                },
+                GameEvent::HoleCardRevealed(card("AS")),
+                GameEvent::Action {
+                    hand: 1,
+                    seat: 1,
+                    action: PlayerAction::Double,
+                },
+                GameEvent::Outcome {
+                    hand: 1,
+                    seat: 1,
+                    outcome: PlayerOutcome::Win,
+                },
+                GameEvent::Payout {
+                    hand: 1,
+                    seat: 1,
+                    net: 40,
+                },
+            ],
+        };
+        let expected = "\
+ROUND 3
+SEAT 1 1000
+SEAT 2 500
+SHUFFLE
+BURN
+BET 2 20
+DEAL 2 2 10H
+DEAL dealer AH
+DEAL dealer down
+INSURE 2 10
+REVEAL AS
+ACTION 2 2 double
+OUTCOME 2 2 win
+PAYOUT 2 2 +40
+END
+";
+        assert_eq!(record.to_string(), expected);
+        assert_eq!(parse_history(expected.as_bytes()).unwrap(), vec![record]);
+    }
+
+    #[test]
+    fn history_written_from_game_is_parsed_back() {
+        let buffer = SharedBuffer::default();
+        let events = Rc::new(RefCell::new(crate::events::EventLog::default()));
+        let mut game = Game::with_seed(3, TableRules::default(), 11);
+        game.subscribe(Box::new(HandHistoryWriter::new(buffer.clone())));
+        game.subscribe(Box::new(events.clone()));
+        play_rounds(&mut game, 30);
+
+        let text = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        let records = parse_history(text.as_bytes()).unwrap();
+        assert_eq!(records.len(), 30);
+        assert_eq!(records[29].round, 30);
+        assert_eq!(records[0].bankrolls, vec![1000, 1000, 1000]);
+        let bankrolls = seat_bankrolls(&records);
+        assert_eq!(bankrolls.len(), 3);
+        for (seat, bankrolls) in bankrolls.iter().enumerate() {
+            assert_eq!(bankrolls.len(), 31);
+            assert_eq!(bankrolls[30], game.get_bankroll(seat));
+        }
+
+        // the records hold the events seen by any other observer, the same until a split
+        // numbers the hands within their seat
+        let expected: Vec<GameEvent> = events
+            .borrow()
+            .events
+            .iter()
+            .filter(|event| {
+                !matches!(
+                    event,
+                    GameEvent::RoundStarted { .. } | GameEvent::RoundSettled
+                )
+            })
+            .cloned()
+            .collect();
+        let split = |record: &HandRecord| {
+            record.events.iter().any(|event| {
+                matches!(
+                    event,
+                    GameEvent::Action {
+                        action: PlayerAction::Split,
+                        ..
+                    }
+                )
+            })
+        };
+        assert!(records.iter().any(split));
+        let recorded: Vec<GameEvent> = records.iter().flat_map(|r| r.events.clone()).collect();
+        assert_eq!(recorded.len(), expected.len());
+        let first_split = records.iter().position(split).unwrap();
+        let before: usize = records[..first_split].iter().map(|r| r.events.len()).sum();
+        assert_eq!(recorded[..before], expected[..before]);
+    }
+
+    #[test]
+    fn split_hands_keep_their_number() {
+        let buffer = SharedBuffer::default();
+        let mut game = Game::with_rules(2, TableRules::default());
+        game.subscribe(Box::new(HandHistoryWriter::new(buffer.clone())));
+        game.new_round();
+        let cards = [
+            "8S", "8D", "10H", "7C", "10D", "6S", "3H", "2C", "10C", "10S",
+        ];
+        game.deck = Deck::from_vec(
+            cards
+                .iter()
+                .rev()
+                .map(|card| card.parse().unwrap())
+                .collect(),
+        );
+        game.place_bet(0, 10);
+        game.place_bet(1, 10);
+        game.deal_initial_cards();
+        game.apply_action(0, PlayerAction::Split);
+        game.apply_action(0, PlayerAction::Stand);
+        game.apply_action(1, PlayerAction::Stand);
+        game.apply_action(2, PlayerAction::Stand);
+        game.dealer_play();
+        game.settle();
+
+        let text = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        for line in [
+            "DEAL 2 1 10H",
+            "DEAL 2 1 7C",
+            "ACTION 1 1 split",
+            "DEAL 1 1 3H",
+            "DEAL 1 2 2C",
+            "ACTION 2 1 stand",
+            "PAYOUT 2 1 +10",
+        ] {
+            assert!(text.contains(line), "{line} in\n{text}");
+        }
+        assert!(!text.contains(" 2 2 "));
+    }
+
+    #[test]
+    fn game_loaded_mid_round_is_recorded() {
+        let buffer = SharedBuffer::default();
+        let mut game = Game::with_seed(1, TableRules::default(), 5);
+        game.subscribe(Box::new(HandHistoryWriter::new(buffer.clone())));
+        game.new_round();
+        game.place_bet(0, 10);
+        game.deal_initial_cards();
+
+        // the writer only saw the deal of the first game
+        let mut loaded = Game::with_rules(3, TableRules::default());
+        loaded.new_round();
+        let cards = [
+            "10H", "7C", "10D", "6S", "9C", "9D", "10C", "7S", "2H", "3H",
+        ];
+        loaded.deck = Deck::from_vec(
+            cards
+                .iter()
+                .rev()
+                .map(|card| card.parse().unwrap())
+                .collect(),
+        );
+        for seat in 0..3 {
+            loaded.place_bet(seat, 10);
+        }
+        loaded.deal_initial_cards();
+        for observer in game.take_observers() {
+            loaded.subscribe(observer);
+        }
+        loaded.apply_action(0, PlayerAction::Stand);
+        loaded.apply_action(1, PlayerAction::Stand);
+        loaded.apply_action(2, PlayerAction::Split);
+        loaded.apply_action(2, PlayerAction::Stand);
+        loaded.apply_action(3, PlayerAction::Stand);
+        loaded.dealer_play();
+        loaded.settle();
+
+        let text = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        assert_eq!(text.matches("PAYOUT 3 ").count(), 2, "{text}");
+        assert!(text.trim_end().ends_with("END"));
+        assert_eq!(parse_history(text.as_bytes()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_ignored() {
+        let text = "# blackjack\n\nROUND 1\nSEAT 1 100\n\nEND\n# done\n";
+        let records = parse_history(text.as_bytes()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].bankrolls, vec![100]);
+        assert!(records[0].events.is_empty());
+    }
+
+    #[test]
+    fn parse_errors_give_the_line() {
+        let cases = [
+            ("SHUFFLE\n", 1, "SHUFFLE outside of a round"),
+            ("ROUND 1\nROUND 2\n", 2, "ROUND before END"),
+            ("ROUND x\n", 1, "invalid field: x"),
+            ("ROUND 1\nSEAT 2 100\n", 2, "expected seat 1"),
+            ("ROUND 1\nDEAL 1 1 1Z\n", 2, "invalid field: 1Z"),
+            ("ROUND 1\nACTION 0 1 hit\n", 2, "invalid seat or hand: 0"),
+            ("ROUND 1\nACTION 1 1 fold\n", 2, "invalid field: fold"),
+            ("ROUND 1\nHIT 1\n", 2, "invalid line: HIT 1"),
+            ("ROUND 1\nSHUFFLE\n", 2, "last round has no END"),
+        ];
+        for (text, expected_line, expected_message) in cases {
+            match parse_history(text.as_bytes()) {
+                Err(HistoryError::Parse { line, message }) => {
+                    assert_eq!((line, message.as_str()), (expected_line, expected_message))
+                }
+                other => panic!("{text:?} gave {other:?}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Blackjack engine: cards, shoe, table rules and the game itself.
+//!
+//! `use blackjack::prelude::*;` brings the types needed to play a round.
+//!
+//! The optional `serde` feature serializes the cards, decks, players and game, and brings save
+//! files, TOML charts and JSON exports along.
+
+pub mod analysis;
+pub mod autoplay;
+pub mod betting;
+pub mod card;
+pub mod chart;
+pub mod counting;
+pub mod deck;
+pub mod deviations;
+pub mod env;
+pub mod events;
+pub mod export;
+pub mod game;
+pub mod history;
+pub mod player;
+pub mod probability;
+pub mod render;
+pub mod replay;
+pub mod risk;
+pub mod rules;
+#[cfg(feature = "serde")]
+pub mod save;
+pub mod simulation;
+pub mod strategy;
+pub mod svg;
+
+pub use autoplay::{PlayerStrategy, TableView};
+pub use card::{Card, CardValue, Suit};
+pub use deck::Deck;
+pub use events::{EventLog, GameEvent, GameObserver, Recipient};
+pub use game::Game;
+pub use player::{Dealer, Player, PlayerAction, PlayerOutcome};
+pub use render::Renderer;
+pub use rules::TableRules;
+pub use strategy::BasicStrategy;
+
+pub mod prelude {
+    pub use crate::{
+        BasicStrategy, Card, CardValue, Dealer, Deck, EventLog, Game, GameEvent, GameObserver,
+        Player, PlayerAction, PlayerOutcome, PlayerStrategy, Recipient, Renderer, Suit, TableRules,
+        TableView,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn prelude_is_enough_to_play_a_round() {
+        let mut game = Game::with_seed(1, TableRules::default(), 1);
+        game.new_round();
+        game.place_bet(0, 10);
+        game.deal_initial_cards();
+        while let Some(hand) = game.current_hand() {
+            game.apply_action(hand, PlayerAction::Stand);
+        }
+        game.dealer_play();
+        let results = game.settle();
+        assert_eq!(results.len(), 1);
+        assert_ne!(game.get_outcome(0), PlayerOutcome::Surrender);
+    }
+}
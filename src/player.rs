@@ -1,22 +1,107 @@
+use std::{fmt, str::FromStr};
+
 use crate::card::{Card, CardValue};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PlayerOutcome {
     Win,
     Lose,
     Bust,
     Blackjack,
     Push,
+    Surrender,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlayerAction {
+    Hit,
+    Stand,
+    Double,
+    Split,
+    Surrender,
+}
+
+impl fmt::Display for PlayerOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PlayerOutcome::Win => "win",
+            PlayerOutcome::Lose => "lose",
+            PlayerOutcome::Bust => "bust",
+            PlayerOutcome::Blackjack => "blackjack",
+            PlayerOutcome::Push => "push",
+            PlayerOutcome::Surrender => "surrender",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for PlayerOutcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "win" => Ok(PlayerOutcome::Win),
+            "lose" => Ok(PlayerOutcome::Lose),
+            "bust" => Ok(PlayerOutcome::Bust),
+            "blackjack" => Ok(PlayerOutcome::Blackjack),
+            "push" => Ok(PlayerOutcome::Push),
+            "surrender" => Ok(PlayerOutcome::Surrender),
+            _ => Err(format!("Invalid outcome: {s}")),
+        }
+    }
 }
 
+impl fmt::Display for PlayerAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PlayerAction::Hit => "hit",
+            PlayerAction::Stand => "stand",
+            PlayerAction::Double => "double",
+            PlayerAction::Split => "split",
+            PlayerAction::Surrender => "surrender",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for PlayerAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hit" => Ok(PlayerAction::Hit),
+            "stand" => Ok(PlayerAction::Stand),
+            "double" => Ok(PlayerAction::Double),
+            "split" => Ok(PlayerAction::Split),
+            "surrender" => Ok(PlayerAction::Surrender),
+            _ => Err(format!("Invalid action: {s}")),
+        }
+    }
+}
+
+// a player is one hand on the table, a seat owns several hands after a split
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Player {
     pub hand: Vec<Card>,
+    pub seat: usize,
+    pub bet: u32,
+    pub stood: bool,
+    pub doubled: bool,
+    pub surrendered: bool,
+    pub split: bool,
+    // insurance of the seat, on its first hand
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub insurance: u32,
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dealer {
     pub hand: Vec<Card>,
+    hole_card_revealed: bool,
 }
 
 trait HandHolder {
@@ -35,6 +120,18 @@ trait HandHolder {
         }
         hand_value
     }
+
+    // soft when an ace is still counted as 11
+    fn is_soft_hand(&self, hand: &Vec<Card>) -> bool {
+        let hard_value: u8 = hand
+            .iter()
+            .map(|card| match card.value {
+                CardValue::Ace => 1,
+                _ => card.get_card_value(),
+            })
+            .sum();
+        hard_value != self.calculate_hand_value(hand)
+    }
 }
 
 impl HandHolder for Player {}
@@ -44,9 +141,15 @@ impl Player {
         self.calculate_hand_value(&self.hand)
     }
 
+    pub fn is_soft(&self) -> bool {
+        self.is_soft_hand(&self.hand)
+    }
+
     pub fn get_outcome(&self, dealer_value: u8) -> PlayerOutcome {
         let player_value = self.hand_value();
-        if self.is_bust() {
+        if self.surrendered {
+            PlayerOutcome::Surrender
+        } else if self.is_bust() {
             PlayerOutcome::Bust
         } else if dealer_value == player_value {
             PlayerOutcome::Push
@@ -63,12 +166,26 @@ impl Player {
         self.hand.push(card);
     }
 
-    fn is_bust(&self) -> bool {
+    pub fn is_bust(&self) -> bool {
         self.hand_value() > 21
     }
 
-    fn is_blackjack(&self) -> bool {
-        self.hand_value() == 21 && self.hand.len() == 2
+    // 21 on a split hand is not a blackjack
+    pub fn is_blackjack(&self) -> bool {
+        self.hand_value() == 21 && self.hand.len() == 2 && !self.split
+    }
+
+    pub fn is_pair(&self) -> bool {
+        self.hand.len() == 2 && self.hand[0].get_card_value() == self.hand[1].get_card_value()
+    }
+
+    // split aces only receive one card each
+    pub fn is_done(&self) -> bool {
+        self.stood
+            || self.doubled
+            || self.surrendered
+            || self.hand_value() >= 21
+            || (self.split && self.hand.len() == 2 && self.hand[0].value == CardValue::Ace)
     }
 }
 
@@ -82,13 +199,34 @@ impl Dealer {
     pub fn add_card(&mut self, card: Card) {
         self.hand.push(card);
     }
+
+    pub fn is_soft(&self) -> bool {
+        self.is_soft_hand(&self.hand)
+    }
+
+    pub fn is_blackjack(&self) -> bool {
+        self.hand_value() == 21 && self.hand.len() == 2
+    }
+
+    pub fn hide_hole_card(&mut self) {
+        self.hole_card_revealed = false;
+    }
+
+    pub fn reveal_hole_card(&mut self) {
+        self.hole_card_revealed = true;
+    }
+
+    // the hole card is the second card of the dealer, face down until the dealer plays
+    pub fn is_hole_card_hidden(&self) -> bool {
+        !self.hole_card_revealed && self.hand.len() >= 2
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
         card::{Card, CardValue, Suit},
-        player::{HandHolder, Player, PlayerOutcome},
+        player::{Dealer, HandHolder, Player, PlayerAction, PlayerOutcome},
     };
 
     fn card(value: CardValue) -> Card {
@@ -103,6 +241,7 @@ mod test {
                 card(CardValue::Number(5)),
                 card(CardValue::Number(7)),
             ],
+            ..Default::default()
         };
         assert!(player.is_bust());
     }
@@ -111,6 +250,7 @@ mod test {
     fn is_not_bust_when_player_21_or_under() {
         let player = Player {
             hand: vec![card(CardValue::Number(10)), card(CardValue::Number(5))],
+            ..Default::default()
         };
         assert!(!player.is_bust());
     }
@@ -119,6 +259,7 @@ mod test {
     fn is_blackjack_when_player_21_with_two_cards() {
         let player = Player {
             hand: vec![card(CardValue::Ace), card(CardValue::King)],
+            ..Default::default()
         };
         assert!(player.is_blackjack());
     }
@@ -131,6 +272,7 @@ mod test {
                 card(CardValue::Number(7)),
                 card(CardValue::Number(7)),
             ],
+            ..Default::default()
         };
         assert!(!player.is_blackjack());
     }
@@ -139,6 +281,7 @@ mod test {
     fn player_wins_when_hand_greater_than_dealer() {
         let player = Player {
             hand: vec![card(CardValue::Number(10)), card(CardValue::Number(8))],
+            ..Default::default()
         };
         let dealer_value = 17;
         assert_eq!(player.get_outcome(dealer_value), PlayerOutcome::Win);
@@ -148,6 +291,7 @@ mod test {
     fn player_loses_when_hand_less_than_dealer() {
         let player = Player {
             hand: vec![card(CardValue::Number(10)), card(CardValue::Number(6))],
+            ..Default::default()
         };
         let dealer_value = 17;
         assert_eq!(player.get_outcome(dealer_value), PlayerOutcome::Lose);
@@ -157,6 +301,7 @@ mod test {
     fn player_pushes_when_hand_equals_dealer() {
         let player = Player {
             hand: vec![card(CardValue::Number(10)), card(CardValue::Number(7))],
+            ..Default::default()
         };
         let dealer_value = 17;
         assert_eq!(player.get_outcome(dealer_value), PlayerOutcome::Push);
@@ -170,6 +315,7 @@ mod test {
                 card(CardValue::Number(5)),
                 card(CardValue::Number(7)),
             ],
+            ..Default::default()
         };
         let dealer_value = 17;
         assert_eq!(player.get_outcome(dealer_value), PlayerOutcome::Bust);
@@ -179,6 +325,7 @@ mod test {
     fn player_outcome_is_blackjack_when_hand_is_blackjack() {
         let player = Player {
             hand: vec![card(CardValue::Ace), card(CardValue::King)],
+            ..Default::default()
         };
         let dealer_value = 20;
         assert_eq!(
@@ -195,6 +342,7 @@ mod test {
                 card(CardValue::Number(9)),
                 card(CardValue::Number(5)),
             ],
+            ..Default::default()
         };
         assert_eq!(player.hand_value(), 15);
     }
@@ -207,6 +355,7 @@ mod test {
                 card(CardValue::Ace),
                 card(CardValue::Number(10)),
             ],
+            ..Default::default()
         };
         assert_eq!(player.hand_value(), 12);
     }
@@ -256,4 +405,118 @@ mod test {
         // j'utilise vraiment calculate_hand_value et pas ma fonction qui l'appelle dans l'implémentation de mon trait
         assert_eq!(player.calculate_hand_value(&player.hand), 21);
     }
+
+    #[test]
+    fn dealer_hole_card_hidden_until_revealed() {
+        let mut dealer = Dealer::default();
+        dealer.add_card(card(CardValue::Number(10)));
+        assert!(!dealer.is_hole_card_hidden());
+        dealer.add_card(card(CardValue::Number(7)));
+        assert!(dealer.is_hole_card_hidden());
+        dealer.reveal_hole_card();
+        assert!(!dealer.is_hole_card_hidden());
+        dealer.hide_hole_card();
+        assert!(dealer.is_hole_card_hidden());
+    }
+
+    #[test]
+    fn soft_hand_counts_an_ace_as_11() {
+        let mut player = Player::default();
+        player.add_card(card(CardValue::Ace));
+        player.add_card(card(CardValue::Number(6)));
+        assert!(player.is_soft());
+        player.add_card(card(CardValue::Number(10)));
+        assert!(!player.is_soft());
+    }
+
+    #[test]
+    fn pair_is_two_cards_of_the_same_value() {
+        let mut player = Player::default();
+        player.add_card(card(CardValue::King));
+        player.add_card(card(CardValue::Queen));
+        assert!(player.is_pair());
+        player.add_card(card(CardValue::Number(2)));
+        assert!(!player.is_pair());
+    }
+
+    #[test]
+    fn surrendered_outcome_ignores_hand() {
+        let player = Player {
+            hand: vec![card(CardValue::Number(10)), card(CardValue::Number(6))],
+            surrendered: true,
+            ..Default::default()
+        };
+        assert_eq!(player.get_outcome(22), PlayerOutcome::Surrender);
+    }
+
+    #[test]
+    fn split_aces_are_done_after_one_card() {
+        let player = Player {
+            hand: vec![card(CardValue::Ace), card(CardValue::Number(5))],
+            split: true,
+            ..Default::default()
+        };
+        assert!(player.is_done());
+        assert!(!player.is_blackjack());
+    }
+
+    #[test]
+    fn hand_is_done_at_21() {
+        let player = Player {
+            hand: vec![
+                card(CardValue::Number(7)),
+                card(CardValue::Number(7)),
+                card(CardValue::Number(7)),
+            ],
+            ..Default::default()
+        };
+        assert!(player.is_done());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn player_and_outcome_json_round_trip() {
+        let player = Player {
+            hand: vec![card(CardValue::Ace), card(CardValue::Number(6))],
+            seat: 2,
+            bet: 10,
+            doubled: true,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&player).unwrap();
+        let restored: Player = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.hand, player.hand);
+        assert_eq!(restored.seat, 2);
+        assert_eq!(restored.bet, 10);
+        assert!(restored.doubled);
+
+        let json = serde_json::to_string(&PlayerOutcome::Blackjack).unwrap();
+        let outcome: PlayerOutcome = serde_json::from_str(&json).unwrap();
+        assert_eq!(outcome, PlayerOutcome::Blackjack);
+    }
+
+    #[test]
+    fn action_and_outcome_text_round_trip() {
+        for action in [
+            PlayerAction::Hit,
+            PlayerAction::Stand,
+            PlayerAction::Double,
+            PlayerAction::Split,
+            PlayerAction::Surrender,
+        ] {
+            assert_eq!(action.to_string().parse(), Ok(action));
+        }
+        for outcome in [
+            PlayerOutcome::Win,
+            PlayerOutcome::Lose,
+            PlayerOutcome::Bust,
+            PlayerOutcome::Blackjack,
+            PlayerOutcome::Push,
+            PlayerOutcome::Surrender,
+        ] {
+            assert_eq!(outcome.to_string().parse(), Ok(outcome));
+        }
+        assert!("fold".parse::<PlayerAction>().is_err());
+        assert!("tie".parse::<PlayerOutcome>().is_err());
+    }
 }
@@ -0,0 +1,357 @@
+//! Exact probabilities worked out from the composition of the shoe instead of sampled.
+//!
+//! Card values follow `Card::get_card_value`: 2 to 10, every ten valued card is 10 and
+//! an ace is 11.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{BuildHasherDefault, Hash, Hasher},
+};
+
+use crate::{card::Card, game::Game, rules::TableRules};
+
+// cards left for each value, from 2 to 11 for the aces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShoeComposition {
+    counts: [u16; 10],
+}
+
+// memo tables keyed by shoes, looked up for every card drawn
+pub(crate) type ShoeMap<V> = HashMap<ShoeComposition, V, BuildHasherDefault<ShoeHasher>>;
+
+impl Hash for ShoeComposition {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let packed = self
+            .counts
+            .iter()
+            .fold(0u64, |packed, count| packed.rotate_left(7) ^ *count as u64);
+        state.write_u64(packed);
+    }
+}
+
+// multiply and rotate, much faster than the default hasher on these small keys
+#[derive(Default)]
+pub(crate) struct ShoeHasher(u64);
+
+impl Hasher for ShoeHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.write_u64(*byte as u64);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl ShoeComposition {
+    pub fn from_cards(cards: &[Card]) -> Self {
+        let mut shoe = ShoeComposition { counts: [0; 10] };
+        for card in cards {
+            shoe.add(card.get_card_value());
+        }
+        shoe
+    }
+
+    pub fn from_decks(nb_decks: u8) -> Self {
+        let mut counts = [4 * nb_decks as u16; 10];
+        counts[8] = 16 * nb_decks as u16;
+        ShoeComposition { counts }
+    }
+
+    // what a player cannot see at the table: the cards left in the shoe and the hole card
+    pub fn unseen(game: &Game) -> Self {
+        let mut shoe = ShoeComposition::from_cards(&game.deck.cards);
+        if game.dealer.is_hole_card_hidden() {
+            shoe.add(game.dealer.hand[1].get_card_value());
+        }
+        shoe
+    }
+
+    pub fn get(&self, value: u8) -> u16 {
+        self.counts[index(value)]
+    }
+
+    pub fn total(&self) -> u16 {
+        self.counts.iter().sum()
+    }
+
+    pub fn add(&mut self, value: u8) {
+        self.counts[index(value)] += 1;
+    }
+
+    pub fn remove(&mut self, value: u8) {
+        let count = &mut self.counts[index(value)];
+        if *count == 0 {
+            panic!("No card of value {value} left in the shoe");
+        }
+        *count -= 1;
+    }
+
+    // chance that the next card has this value
+    pub fn probability(&self, value: u8) -> f64 {
+        self.get(value) as f64 / self.total() as f64
+    }
+
+    // values still in the shoe with their probability to come next
+    pub fn draws(&self) -> impl Iterator<Item = (u8, f64)> + '_ {
+        let total = self.total() as f64;
+        (2..=11)
+            .filter(|value| self.get(*value) > 0)
+            .map(move |value| (value, self.get(value) as f64 / total))
+    }
+}
+
+fn index(value: u8) -> usize {
+    if !(2..=11).contains(&value) {
+        panic!("Card value must be between 2 and 11");
+    }
+    value as usize - 2
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DealerProbabilities {
+    // final totals of 17 to 21, blackjack excluded
+    pub totals: [f64; 5],
+    pub blackjack: f64,
+    pub bust: f64,
+}
+
+impl DealerProbabilities {
+    // chance to finish on a total between 17 and 21 without a blackjack
+    pub fn get_total(&self, total: u8) -> f64 {
+        match total {
+            17..=21 => self.totals[total as usize - 17],
+            _ => 0.0,
+        }
+    }
+
+    // the probabilities once the dealer is known not to have a blackjack
+    pub fn without_blackjack(&self) -> DealerProbabilities {
+        let remaining = 1.0 - self.blackjack;
+        if remaining <= 0.0 {
+            panic!("The dealer always has a blackjack");
+        }
+        DealerProbabilities {
+            totals: self.totals.map(|p| p / remaining),
+            blackjack: 0.0,
+            bust: self.bust / remaining,
+        }
+    }
+}
+
+impl fmt::Display for DealerProbabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (total, p) in (17..=21).zip(self.totals) {
+            write!(f, "{total}: {:.4}%, ", p * 100.0)?;
+        }
+        write!(
+            f,
+            "blackjack: {:.4}%, bust: {:.4}%",
+            self.blackjack * 100.0,
+            self.bust * 100.0
+        )
+    }
+}
+
+// final total of the dealer holding the upcard, the hole card and every card drawn coming
+// from the shoe. the shoe must not contain the upcard.
+pub fn dealer_probabilities(
+    shoe: &ShoeComposition,
+    upcard: u8,
+    rules: &TableRules,
+) -> DealerProbabilities {
+    let mut solver = DealerSolver {
+        dealer_hits_soft_17: rules.dealer_hits_soft_17,
+        memo: ShoeMap::default(),
+    };
+    let ace = upcard == 11;
+    let p = solver.solve(&mut shoe.clone(), upcard - 10 * ace as u8, ace, 1);
+    DealerProbabilities {
+        totals: [p[0], p[1], p[2], p[3], p[4]],
+        blackjack: p[5],
+        bust: p[6],
+    }
+}
+
+// chance that the hole card makes a blackjack with the upcard
+pub fn blackjack_probability(shoe: &ShoeComposition, upcard: u8) -> f64 {
+    match upcard {
+        10 => shoe.probability(11),
+        11 => shoe.probability(10),
+        _ => 0.0,
+    }
+}
+
+// the shoe left tells which cards the dealer drew, so it is enough to memoize on it
+struct DealerSolver {
+    dealer_hits_soft_17: bool,
+    memo: ShoeMap<[f64; 7]>,
+}
+
+impl DealerSolver {
+    // hard counts every ace as 1, probabilities of 17 to 21, blackjack then bust
+    fn solve(&mut self, shoe: &mut ShoeComposition, hard: u8, ace: bool, cards: u8) -> [f64; 7] {
+        let soft = ace && hard + 10 <= 21;
+        let value = if soft { hard + 10 } else { hard };
+        let mut result = [0.0; 7];
+        if value > 21 {
+            result[6] = 1.0;
+            return result;
+        }
+        if cards == 2 && value == 21 {
+            result[5] = 1.0;
+            return result;
+        }
+        if value > 17 || (value == 17 && !(soft && self.dealer_hits_soft_17)) {
+            result[value as usize - 17] = 1.0;
+            return result;
+        }
+        if let Some(result) = self.memo.get(shoe) {
+            return *result;
+        }
+        if shoe.total() == 0 {
+            panic!("Not enough cards in the shoe for the dealer");
+        }
+        let total = shoe.total() as f64;
+        for value in 2..=11 {
+            let count = shoe.get(value);
+            if count == 0 {
+                continue;
+            }
+            let p = count as f64 / total;
+            shoe.remove(value);
+            let drawn = if value == 11 { 1 } else { value };
+            let next = self.solve(shoe, hard + drawn, ace || value == 11, cards + 1);
+            shoe.add(value);
+            for (result, next) in result.iter_mut().zip(next) {
+                *result += p * next;
+            }
+        }
+        self.memo.insert(*shoe, result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{CardValue, Suit};
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} is not {expected} ± {tolerance}"
+        );
+    }
+
+    fn sum(p: &DealerProbabilities) -> f64 {
+        p.totals.iter().sum::<f64>() + p.blackjack + p.bust
+    }
+
+    #[test]
+    fn composition_counts_every_value() {
+        let shoe = ShoeComposition::from_decks(2);
+        assert_eq!(shoe.total(), 104);
+        assert_eq!(shoe.get(10), 32);
+        assert_eq!(shoe.get(11), 8);
+        assert_eq!(
+            ShoeComposition::from_cards(&crate::deck::Deck::from_decks(2).cards),
+            shoe
+        );
+        assert_close(shoe.draws().map(|(_, p)| p).sum(), 1.0, 1e-12);
+    }
+
+    #[test]
+    fn blackjack_comes_from_the_hole_card() {
+        let mut shoe = ShoeComposition::from_decks(6);
+        shoe.remove(11);
+        let rules = TableRules::default();
+        let ace = dealer_probabilities(&shoe, 11, &rules);
+        assert_close(ace.blackjack, 96.0 / 311.0, 1e-12);
+        assert_close(blackjack_probability(&shoe, 11), ace.blackjack, 1e-12);
+        assert_close(sum(&ace), 1.0, 1e-12);
+        let without = ace.without_blackjack();
+        assert_eq!(without.blackjack, 0.0);
+        assert_close(sum(&without), 1.0, 1e-12);
+
+        let mut shoe = ShoeComposition::from_decks(6);
+        shoe.remove(6);
+        let six = dealer_probabilities(&shoe, 6, &rules);
+        assert_eq!(six.blackjack, 0.0);
+        // close to the 42.32% of an infinite shoe
+        assert_close(six.bust, 0.4232, 0.001);
+    }
+
+    #[test]
+    fn hitting_soft_17_changes_the_totals() {
+        let mut shoe = ShoeComposition::from_decks(6);
+        shoe.remove(6);
+        let s17 = dealer_probabilities(&shoe, 6, &TableRules::default());
+        let h17_rules = TableRules {
+            dealer_hits_soft_17: true,
+            ..Default::default()
+        };
+        let h17 = dealer_probabilities(&shoe, 6, &h17_rules);
+        assert!(h17.get_total(17) < s17.get_total(17));
+        assert!(h17.bust > s17.bust);
+        assert_close(sum(&h17), 1.0, 1e-12);
+    }
+
+    #[test]
+    fn unseen_cards_include_the_hole_card() {
+        let mut game = Game::with_seed(1, TableRules::default(), 4);
+        game.new_round();
+        game.place_bet(0, 10);
+        game.deal_initial_cards();
+        let unseen = ShoeComposition::unseen(&game);
+        assert_eq!(unseen.total() as usize, game.deck.cards.len() + 1);
+    }
+
+    // the dealer of the engine playing from a fresh single deck shoe
+    #[test]
+    fn agrees_with_the_game() {
+        let rules = TableRules {
+            decks: 1,
+            burn_cards: 0,
+            dealer_hits_soft_17: true,
+            ..Default::default()
+        };
+        let upcard = Card::new(CardValue::Number(7), Suit::Spades);
+        let mut counts = [0u32; 7];
+        let samples = 40_000;
+        for seed in 0..samples {
+            let mut game = Game::with_seed(1, rules.clone(), seed);
+            let position = game.deck.cards.iter().position(|c| *c == upcard).unwrap();
+            game.deck.cards.remove(position);
+            game.dealer.hand = vec![upcard.clone(), game.deck.deal()];
+            game.dealer_play();
+            let value = game.get_dealer_hand_value();
+            let outcome = match value {
+                _ if game.dealer_has_blackjack() => 5,
+                17..=21 => value as usize - 17,
+                _ => 6,
+            };
+            counts[outcome] += 1;
+        }
+
+        let mut shoe = ShoeComposition::from_decks(1);
+        shoe.remove(7);
+        let exact = dealer_probabilities(&shoe, 7, &rules);
+        let expected = [exact.totals.as_slice(), &[exact.blackjack, exact.bust]].concat();
+        for (count, p) in counts.into_iter().zip(expected) {
+            let standard_error = (p * (1.0 - p) / samples as f64).sqrt();
+            assert_close(
+                count as f64 / samples as f64,
+                p,
+                4.5 * standard_error + 1e-9,
+            );
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::io::IsTerminal;
+
+use crate::{
+    card::{Card, CardValue, Suit},
+    game::Game,
+};
+
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+const CARD_WIDTH: usize = 5;
+
+pub struct Renderer {
+    pub unicode: bool,
+    pub color: bool,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer {
+            unicode: true,
+            color: true,
+        }
+    }
+}
+
+impl Renderer {
+    pub fn plain() -> Self {
+        Renderer {
+            unicode: false,
+            color: false,
+        }
+    }
+
+    // colors are only used when writing to a terminal and NO_COLOR is not set
+    #[cfg_attr(test, mutants::skip)]
+    pub fn for_terminal() -> Self {
+        Renderer {
+            unicode: true,
+            color: std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        }
+    }
+
+    pub fn suit_symbol(&self, suit: Suit) -> &'static str {
+        match (suit, self.unicode) {
+            (Suit::Spades, true) => "♠",
+            (Suit::Hearts, true) => "♥",
+            (Suit::Diamonds, true) => "♦",
+            (Suit::Clubs, true) => "♣",
+            (Suit::Spades, false) => "S",
+            (Suit::Hearts, false) => "H",
+            (Suit::Diamonds, false) => "D",
+            (Suit::Clubs, false) => "C",
+        }
+    }
+
+    pub fn render_card(&self, card: &Card) -> String {
+        let text = format!(
+            "{}{}",
+            rank_label(&card.value),
+            self.suit_symbol(card.get_suit())
+        );
+        self.paint(card.get_suit(), &text)
+    }
+
+    pub fn render_hand(&self, hand: &[Card], hide_hole_card: bool) -> String {
+        if hand.is_empty() {
+            return String::new();
+        }
+        let mut lines = vec![String::new(); 5];
+        for (i, card) in hand.iter().enumerate() {
+            let card_lines = if hide_hole_card && i == 1 {
+                self.card_back_lines()
+            } else {
+                self.card_lines(card)
+            };
+            for (line, card_line) in lines.iter_mut().zip(card_lines) {
+                if i > 0 {
+                    line.push(' ');
+                }
+                line.push_str(&card_line);
+            }
+        }
+        lines.join("\n")
+    }
+
+    pub fn render_table(&self, game: &Game) -> String {
+        let hidden = game.dealer.is_hole_card_hidden();
+        let mut out = String::new();
+        if hidden {
+            out.push_str(&format!(
+                "Dealer (showing {})\n",
+                game.dealer.hand[0].get_card_value()
+            ));
+        } else {
+            out.push_str(&format!("Dealer ({})\n", game.get_dealer_hand_value()));
+        }
+        out.push_str(&self.render_hand(game.get_dealer_hand(), hidden));
+        for (i, player) in game.players.iter().enumerate() {
+            out.push_str(&format!("\nPlayer {}", player.seat + 1));
+            if game
+                .players
+                .iter()
+                .filter(|p| p.seat == player.seat)
+                .count()
+                > 1
+            {
+                let hand_number = game.players[..i]
+                    .iter()
+                    .filter(|p| p.seat == player.seat)
+                    .count();
+                out.push_str(&format!(" hand {}", hand_number + 1));
+            }
+            out.push_str(&format!(" ({})", player.hand_value()));
+            if player.bet > 0 {
+                out.push_str(&format!(" bet {}", player.bet));
+            }
+            out.push('\n');
+            out.push_str(&self.render_hand(&player.hand, false));
+        }
+        out
+    }
+
+    fn card_lines(&self, card: &Card) -> [String; 5] {
+        let label = rank_label(&card.value);
+        let suit = card.get_suit();
+        let symbol = self.suit_symbol(suit);
+        let top = format!("{label:<CARD_WIDTH$}");
+        let bottom = format!("{label:>CARD_WIDTH$}");
+        let middle = format!("  {symbol}  ");
+        [
+            self.border(),
+            format!("|{}|", self.paint(suit, &top)),
+            format!("|{}|", self.paint(suit, &middle)),
+            format!("|{}|", self.paint(suit, &bottom)),
+            self.border(),
+        ]
+    }
+
+    fn card_back_lines(&self) -> [String; 5] {
+        let back = format!("|{}|", "#".repeat(CARD_WIDTH));
+        [
+            self.border(),
+            back.clone(),
+            back.clone(),
+            back,
+            self.border(),
+        ]
+    }
+
+    fn border(&self) -> String {
+        format!("+{}+", "-".repeat(CARD_WIDTH))
+    }
+
+    fn paint(&self, suit: Suit, text: &str) -> String {
+        if self.color && matches!(suit, Suit::Hearts | Suit::Diamonds) {
+            format!("{RED}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn rank_label(value: &CardValue) -> String {
+    match value {
+        CardValue::Ace => "A".to_string(),
+        CardValue::King => "K".to_string(),
+        CardValue::Queen => "Q".to_string(),
+        CardValue::Jack => "J".to_string(),
+        CardValue::Number(n) => n.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{Card, CardValue, Suit};
+
+    #[test]
+    fn unicode_card_uses_suit_glyph() {
+        let renderer = Renderer {
+            unicode: true,
+            color: false,
+        };
+        let card = Card::new(CardValue::Ace, Suit::Spades);
+        assert_eq!(renderer.render_card(&card), "A♠");
+        let card = Card::new(CardValue::Number(10), Suit::Hearts);
+        assert_eq!(renderer.render_card(&card), "10♥");
+    }
+
+    #[test]
+    fn plain_card_uses_suit_letter() {
+        let renderer = Renderer::plain();
+        let card = Card::new(CardValue::Queen, Suit::Diamonds);
+        assert_eq!(renderer.render_card(&card), "QD");
+    }
+
+    #[test]
+    fn red_suits_are_colored() {
+        let renderer = Renderer::default();
+        let heart = Card::new(CardValue::King, Suit::Hearts);
+        let club = Card::new(CardValue::King, Suit::Clubs);
+        assert_eq!(renderer.render_card(&heart), format!("{RED}K♥{RESET}"));
+        assert_eq!(renderer.render_card(&club), "K♣");
+    }
+
+    #[test]
+    fn hand_is_drawn_as_boxes() {
+        let renderer = Renderer::plain();
+        let hand = vec![
+            Card::new(CardValue::Ace, Suit::Spades),
+            Card::new(CardValue::Number(10), Suit::Hearts),
+        ];
+        let expected = [
+            "+-----+ +-----+",
+            "|A    | |10   |",
+            "|  S  | |  H  |",
+            "|    A| |   10|",
+            "+-----+ +-----+",
+        ]
+        .join("\n");
+        assert_eq!(renderer.render_hand(&hand, false), expected);
+    }
+
+    #[test]
+    fn hidden_hole_card_is_drawn_face_down() {
+        let renderer = Renderer::plain();
+        let hand = vec![
+            Card::new(CardValue::Ace, Suit::Spades),
+            Card::new(CardValue::Number(10), Suit::Hearts),
+        ];
+        let rendered = renderer.render_hand(&hand, true);
+        assert!(rendered.contains("|#####|"));
+        assert!(!rendered.contains("10"));
+    }
+
+    #[test]
+    fn table_hides_dealer_value_until_dealer_play() {
+        let renderer = Renderer::plain();
+        let mut game = Game::new(2, 20);
+        game.deal_initial_cards();
+        let table = renderer.render_table(&game);
+        assert!(table.starts_with("Dealer (showing "));
+        assert!(table.contains("|#####|"));
+        assert!(table.contains("Player 2"));
+
+        game.dealer_play();
+        let table = renderer.render_table(&game);
+        assert!(table.starts_with(&format!("Dealer ({})", game.get_dealer_hand_value())));
+        assert!(!table.contains("|#####|"));
+    }
+
+    #[test]
+    fn table_labels_split_hands_and_bets() {
+        let renderer = Renderer::plain();
+        let mut game = Game::new(1, 20);
+        game.players[0].hand = vec![Card::new(CardValue::Number(8), Suit::Clubs)];
+        game.players[0].bet = 10;
+        game.players.push(crate::player::Player {
+            hand: vec![Card::new(CardValue::Number(8), Suit::Hearts)],
+            bet: 10,
+            ..Default::default()
+        });
+        let table = renderer.render_table(&game);
+        assert!(table.contains("Player 1 hand 1 (8) bet 10"));
+        assert!(table.contains("Player 1 hand 2 (8) bet 10"));
+    }
+}
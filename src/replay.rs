@@ -0,0 +1,280 @@
+use std::fmt;
+
+use crate::{
+    game::{Game, Phase},
+    player::PlayerAction,
+    rules::TableRules,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    // step is the index of the action in the log
+    IllegalAction {
+        step: usize,
+        action: PlayerAction,
+        legal: Vec<PlayerAction>,
+    },
+    OutOfChips {
+        seat: usize,
+        bet: u32,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::IllegalAction {
+                step,
+                action,
+                legal,
+            } => {
+                let legal: Vec<String> = legal.iter().map(PlayerAction::to_string).collect();
+                write!(
+                    f,
+                    "step {}: cannot {action}, legal actions are {}",
+                    step + 1,
+                    legal.join(", ")
+                )
+            }
+            ReplayError::OutOfChips { seat, bet } => {
+                write!(f, "player {} cannot cover a bet of {bet}", seat + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+// actions separated by spaces or commas, as letters (H S D P R) or full names
+pub fn parse_actions(log: &str) -> Result<Vec<PlayerAction>, String> {
+    log.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty())
+        .map(|word| match word.to_lowercase().as_str() {
+            "h" => Ok(PlayerAction::Hit),
+            "s" => Ok(PlayerAction::Stand),
+            "d" => Ok(PlayerAction::Double),
+            "p" => Ok(PlayerAction::Split),
+            "r" => Ok(PlayerAction::Surrender),
+            name => name.parse(),
+        })
+        .collect()
+}
+
+// plays a recorded action log on a seeded game, every seat betting the same amount each round.
+// rounds are started and dealt as needed, so after every step the game waits for
+// the next action or the round is settled.
+pub struct Replay {
+    game: Game,
+    bet: u32,
+    actions: Vec<PlayerAction>,
+    played: usize,
+    // net result of each hand in the last settled round
+    results: Vec<i64>,
+}
+
+impl Replay {
+    // the game is expected fresh from Game::with_seed, observers included
+    pub fn new(mut game: Game, bet: u32, actions: Vec<PlayerAction>) -> Result<Self, ReplayError> {
+        game.new_round();
+        let mut replay = Replay {
+            game,
+            bet,
+            actions,
+            played: 0,
+            results: Vec::new(),
+        };
+        replay.play_until_decision()?;
+        Ok(replay)
+    }
+
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+
+    pub fn get_played(&self) -> usize {
+        self.played
+    }
+
+    pub fn remaining(&self) -> &[PlayerAction] {
+        &self.actions[self.played..]
+    }
+
+    pub fn get_results(&self) -> &[i64] {
+        &self.results
+    }
+
+    // plays the next action of the log, None once the log is exhausted
+    pub fn step(&mut self) -> Result<Option<(usize, PlayerAction)>, ReplayError> {
+        let Some(&action) = self.actions.get(self.played) else {
+            return Ok(None);
+        };
+        let hand = loop {
+            match self.game.phase() {
+                Phase::PlayerTurn(hand) => break hand,
+                Phase::Settled => self.game.new_round(),
+                Phase::Betting | Phase::DealerTurn => self.play_until_decision()?,
+            }
+        };
+        let legal = self.game.legal_actions(hand);
+        if !legal.contains(&action) {
+            return Err(ReplayError::IllegalAction {
+                step: self.played,
+                action,
+                legal,
+            });
+        }
+        self.game.apply_action(hand, action);
+        self.played += 1;
+        self.play_until_decision()?;
+        Ok(Some((hand, action)))
+    }
+
+    pub fn run(mut self) -> Result<Game, ReplayError> {
+        while self.step()?.is_some() {}
+        Ok(self.game)
+    }
+
+    fn play_until_decision(&mut self) -> Result<(), ReplayError> {
+        loop {
+            match self.game.phase() {
+                Phase::Betting => {
+                    for seat in 0..self.game.nb_seats() {
+                        if self.game.get_bankroll(seat) < self.bet as i64 {
+                            return Err(ReplayError::OutOfChips {
+                                seat,
+                                bet: self.bet,
+                            });
+                        }
+                        self.game.place_bet(seat, self.bet);
+                    }
+                    self.game.deal_initial_cards();
+                }
+                Phase::DealerTurn => {
+                    self.game.dealer_play();
+                    self.results = self.game.settle();
+                }
+                Phase::PlayerTurn(_) | Phase::Settled => return Ok(()),
+            }
+        }
+    }
+}
+
+// rebuilds the game reached after playing the whole log
+pub fn replay(
+    nb_players: u8,
+    rules: TableRules,
+    seed: u64,
+    bet: u32,
+    actions: Vec<PlayerAction>,
+) -> Result<Game, ReplayError> {
+    Replay::new(Game::with_seed(nb_players, rules, seed), bet, actions)?.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // plays rounds by hand, standing on 17 and hitting below, and returns the log
+    fn play_by_hand(game: &mut Game, rounds: usize) -> Vec<PlayerAction> {
+        let mut actions = Vec::new();
+        for _ in 0..rounds {
+            game.new_round();
+            for seat in 0..game.nb_seats() {
+                game.place_bet(seat, 10);
+            }
+            game.deal_initial_cards();
+            while let Some(hand) = game.current_hand() {
+                let action = if game.get_player_hand_value(hand) < 17 {
+                    PlayerAction::Hit
+                } else {
+                    PlayerAction::Stand
+                };
+                game.apply_action(hand, action);
+                actions.push(action);
+            }
+            game.dealer_play();
+            game.settle();
+        }
+        actions
+    }
+
+    #[test]
+    fn action_log_is_parsed() {
+        assert_eq!(
+            parse_actions("H s,D  split r"),
+            Ok(vec![
+                PlayerAction::Hit,
+                PlayerAction::Stand,
+                PlayerAction::Double,
+                PlayerAction::Split,
+                PlayerAction::Surrender,
+            ])
+        );
+        assert_eq!(parse_actions(""), Ok(Vec::new()));
+        assert_eq!(parse_actions("H X"), Err("Invalid action: x".to_string()));
+    }
+
+    #[test]
+    fn replay_rebuilds_the_game_played_by_hand() {
+        let mut expected = Game::with_seed(2, TableRules::default(), 42);
+        let actions = play_by_hand(&mut expected, 20);
+
+        let game = replay(2, TableRules::default(), 42, 10, actions).unwrap();
+        assert_eq!(game.get_round(), 20);
+        assert_eq!(game.phase(), Phase::Settled);
+        assert_eq!(game.get_dealer_hand(), expected.get_dealer_hand());
+        assert_eq!(game.deck.cards, expected.deck.cards);
+        for seat in 0..2 {
+            assert_eq!(game.get_bankroll(seat), expected.get_bankroll(seat));
+        }
+    }
+
+    #[test]
+    fn every_step_waits_for_the_next_action() {
+        let actions = play_by_hand(&mut Game::with_seed(1, TableRules::default(), 7), 5);
+
+        let game = Game::with_seed(1, TableRules::default(), 7);
+        let mut replay = Replay::new(game, 10, actions.clone()).unwrap();
+        while let Some((hand, action)) = replay.step().unwrap() {
+            assert_eq!(action, actions[replay.get_played() - 1]);
+            assert!(hand < replay.get_game().players.len());
+            assert!(matches!(
+                replay.get_game().phase(),
+                Phase::PlayerTurn(_) | Phase::Settled
+            ));
+        }
+        assert!(replay.remaining().is_empty());
+        assert_eq!(replay.get_played(), actions.len());
+        assert_eq!(replay.get_results().len(), 1);
+    }
+
+    #[test]
+    fn illegal_action_is_reported_with_its_step() {
+        let rules = TableRules {
+            surrender: false,
+            ..Default::default()
+        };
+        let mut actions = vec![PlayerAction::Surrender; 50];
+        actions[0] = PlayerAction::Stand;
+        let error = replay(1, rules, 3, 10, actions).err().unwrap();
+        let ReplayError::IllegalAction { step, action, .. } = &error else {
+            panic!("unexpected error: {error}");
+        };
+        assert_eq!(*step, 1);
+        assert_eq!(*action, PlayerAction::Surrender);
+        assert!(error.to_string().starts_with("step 2: cannot surrender"));
+    }
+
+    #[test]
+    fn bet_larger_than_the_bankroll_is_reported() {
+        let mut game = Game::with_seed(2, TableRules::default(), 1);
+        game.set_bankroll(1, 5);
+        let error = Replay::new(game, 10, Vec::new()).err().unwrap();
+        assert_eq!(error, ReplayError::OutOfChips { seat: 1, bet: 10 });
+        assert_eq!(error.to_string(), "player 2 cannot cover a bet of 10");
+    }
+}
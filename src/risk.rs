@@ -0,0 +1,517 @@
+use std::thread;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::simulation::{SimulationStats, batch_seed};
+
+// a lifetime session stops once the formula leaves less than that risk to its bankroll
+pub const LIFETIME_RISK_LEFT: f64 = 0.0001;
+
+// or after that many hands without ruin
+pub const LIFETIME_HANDS: u64 = 100_000;
+
+// sessions of every batch of a simulation, each batch with its own seed
+const SESSION_BATCH: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Horizon {
+    // ruin within that many hands
+    Trip(u64),
+    // ruin at any time, playing on forever
+    Lifetime,
+}
+
+impl std::fmt::Display for Horizon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Horizon::Trip(hands) => write!(f, "trip of {hands} hands"),
+            Horizon::Lifetime => write!(f, "lifetime"),
+        }
+    }
+}
+
+// win rate and standard deviation per hand, in chips
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RiskModel {
+    pub ev: f64,
+    pub standard_deviation: f64,
+}
+
+impl RiskModel {
+    pub fn from_stats(stats: &SimulationStats) -> Self {
+        RiskModel {
+            ev: stats.ev_per_hand(),
+            standard_deviation: stats.standard_deviation(),
+        }
+    }
+
+    pub fn variance(&self) -> f64 {
+        self.standard_deviation * self.standard_deviation
+    }
+
+    // chance for the bankroll to reach zero within the horizon, taken as a brownian motion
+    // drifting by the ev per hand. certain ruin without an edge over a lifetime.
+    pub fn risk_of_ruin(&self, bankroll: f64, horizon: Horizon) -> f64 {
+        let variance = self.variance();
+        if bankroll <= 0.0 || !variance.is_finite() {
+            return 1.0;
+        }
+        match horizon {
+            Horizon::Lifetime if self.ev <= 0.0 => 1.0,
+            Horizon::Lifetime => (-2.0 * self.ev * bankroll / variance).exp().min(1.0),
+            Horizon::Trip(hands) if variance == 0.0 => {
+                f64::from(bankroll + self.ev * hands as f64 <= 0.0)
+            }
+            Horizon::Trip(hands) => {
+                let drift = self.ev * hands as f64;
+                let spread = self.standard_deviation * (hands as f64).sqrt();
+                let ends_ruined = normal_cdf((-bankroll - drift) / spread);
+                // paths touching zero and coming back above it
+                let comes_back = (-2.0 * self.ev * bankroll / variance
+                    + ln_normal_cdf((-bankroll + drift) / spread))
+                .exp();
+                (ends_ruined + comes_back).min(1.0)
+            }
+        }
+    }
+
+    // smallest bankroll whose risk of ruin is at most the target, None when no bankroll
+    // is enough
+    pub fn bankroll_for_risk(&self, risk: f64, horizon: Horizon) -> Option<f64> {
+        if risk >= 1.0 {
+            return Some(0.0);
+        }
+        if risk <= 0.0 || !self.variance().is_finite() {
+            return None;
+        }
+        match horizon {
+            Horizon::Lifetime if self.ev <= 0.0 => None,
+            Horizon::Lifetime => Some(-self.variance() * risk.ln() / (2.0 * self.ev)),
+            Horizon::Trip(hands) => {
+                let mut low = 0.0;
+                let mut high = (self.standard_deviation * (hands as f64).sqrt()
+                    + self.ev.abs() * hands as f64)
+                    .max(1.0);
+                while self.risk_of_ruin(high, horizon) > risk {
+                    low = high;
+                    high *= 2.0;
+                }
+                for _ in 0..100 {
+                    let middle = (low + high) / 2.0;
+                    if self.risk_of_ruin(middle, horizon) > risk {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+                Some(high)
+            }
+        }
+    }
+
+    // hands to play before the expected win equals one standard deviation of the results,
+    // None without an edge
+    pub fn n0(&self) -> Option<f64> {
+        (self.ev > 0.0).then(|| self.variance() / (self.ev * self.ev))
+    }
+}
+
+// what a simulation says of the risk of a bankroll
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RiskReport {
+    pub bankroll: i64,
+    // in hands, none for a lifetime
+    pub trip: Option<u64>,
+    pub risk_of_ruin: f64,
+    // over the sessions drawn from the hands of the simulation, none without any hand
+    pub simulated: Option<SessionRisk>,
+    pub target_risk: f64,
+    pub bankroll_for_target: Option<f64>,
+    pub n0: Option<f64>,
+}
+
+impl RiskReport {
+    pub fn new(
+        stats: &SimulationStats,
+        bankroll: i64,
+        horizon: Horizon,
+        target_risk: f64,
+        sessions: u64,
+        seed: u64,
+        threads: usize,
+    ) -> Self {
+        let model = RiskModel::from_stats(stats);
+        RiskReport {
+            bankroll,
+            trip: match horizon {
+                Horizon::Trip(hands) => Some(hands),
+                Horizon::Lifetime => None,
+            },
+            risk_of_ruin: model.risk_of_ruin(bankroll as f64, horizon),
+            simulated: NetDistribution::from_stats(stats).map(|distribution| {
+                distribution.simulate_sessions(bankroll, horizon, sessions, seed, threads)
+            }),
+            target_risk,
+            bankroll_for_target: model.bankroll_for_risk(target_risk, horizon),
+            n0: model.n0(),
+        }
+    }
+
+    pub fn horizon(&self) -> Horizon {
+        self.trip.map_or(Horizon::Lifetime, Horizon::Trip)
+    }
+}
+
+// natural log of the standard normal distribution function, accurate far in the tails
+fn ln_normal_cdf(x: f64) -> f64 {
+    let z = -x / std::f64::consts::SQRT_2;
+    if z > 0.0 {
+        0.5f64.ln() + ln_erfc(z)
+    } else {
+        (1.0 - 0.5 * ln_erfc(-z).exp()).ln()
+    }
+}
+
+fn normal_cdf(x: f64) -> f64 {
+    ln_normal_cdf(x).exp()
+}
+
+// complementary error function for z >= 0, relative error below 1.2e-7 (Numerical Recipes)
+fn ln_erfc(z: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = [
+        -1.265_512_23,
+        1.000_023_68,
+        0.374_091_96,
+        0.096_784_18,
+        -0.186_288_06,
+        0.278_868_07,
+        -1.135_203_98,
+        1.488_515_87,
+        -0.822_152_23,
+        0.170_872_77,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0, |sum, coefficient| sum * t + coefficient);
+    t.ln() - z * z + polynomial
+}
+
+// sessions simulated from a starting bankroll
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SessionRisk {
+    pub sessions: u64,
+    pub ruined: u64,
+    pub hands: u64,
+}
+
+impl SessionRisk {
+    pub fn risk(&self) -> f64 {
+        if self.sessions == 0 {
+            return 0.0;
+        }
+        self.ruined as f64 / self.sessions as f64
+    }
+
+    // half width of the 95% confidence interval of the risk
+    pub fn confidence(&self) -> f64 {
+        let risk = self.risk();
+        1.96 * (risk * (1.0 - risk) / self.sessions.max(1) as f64).sqrt()
+    }
+
+    fn merge(&mut self, other: &SessionRisk) {
+        self.sessions += other.sessions;
+        self.ruined += other.ruined;
+        self.hands += other.hands;
+    }
+}
+
+// net results of the hands of a simulation, drawn at random with their frequency, each hand
+// independent of the others
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetDistribution {
+    nets: Vec<i64>,
+    // hands with a net up to the one at the same index
+    cumulative: Vec<u64>,
+}
+
+impl NetDistribution {
+    // None without any hand
+    pub fn from_stats(stats: &SimulationStats) -> Option<Self> {
+        let mut nets = Vec::new();
+        let mut cumulative = Vec::new();
+        let mut hands = 0;
+        for (net, count) in &stats.nets {
+            hands += count;
+            nets.push(*net);
+            cumulative.push(hands);
+        }
+        (hands > 0).then_some(NetDistribution { nets, cumulative })
+    }
+
+    pub fn model(&self) -> RiskModel {
+        let hands = *self.cumulative.last().unwrap() as f64;
+        let mut previous = 0;
+        let (mut sum, mut squares) = (0.0, 0.0);
+        for (net, cumulative) in self.nets.iter().zip(&self.cumulative) {
+            let count = (cumulative - previous) as f64;
+            previous = *cumulative;
+            sum += *net as f64 * count;
+            squares += (*net as f64).powi(2) * count;
+        }
+        let ev = sum / hands;
+        RiskModel {
+            ev,
+            standard_deviation: (squares / hands - ev * ev).max(0.0).sqrt(),
+        }
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> i64 {
+        let draw = rng.random_range(0..*self.cumulative.last().unwrap());
+        self.nets[self.cumulative.partition_point(|hands| *hands <= draw)]
+    }
+
+    // sessions from the bankroll until ruin or the end of the horizon, on all the threads,
+    // 0 for one per core. the results only depend on the seed.
+    pub fn simulate_sessions(
+        &self,
+        bankroll: i64,
+        horizon: Horizon,
+        sessions: u64,
+        seed: u64,
+        threads: usize,
+    ) -> SessionRisk {
+        let threads = match threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            threads => threads,
+        };
+        // no risk is left to a lifetime bankroll that grew that far
+        let safe = match horizon {
+            Horizon::Trip(_) => None,
+            Horizon::Lifetime => self
+                .model()
+                .bankroll_for_risk(LIFETIME_RISK_LEFT, horizon)
+                .map(|bankroll| bankroll.ceil() as i64),
+        };
+        let max_hands = match horizon {
+            Horizon::Trip(hands) => hands,
+            Horizon::Lifetime => LIFETIME_HANDS,
+        };
+        let batches = sessions.div_ceil(SESSION_BATCH);
+        let results: Vec<SessionRisk> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads as u64)
+                .map(|worker| {
+                    scope.spawn(move || {
+                        let mut risk = SessionRisk::default();
+                        for batch in (worker..batches).step_by(threads) {
+                            let mut rng = StdRng::seed_from_u64(batch_seed(seed, batch));
+                            let count = (sessions - batch * SESSION_BATCH).min(SESSION_BATCH);
+                            for _ in 0..count {
+                                let session = self.session(&mut rng, bankroll, max_hands, safe);
+                                risk.merge(&session);
+                            }
+                        }
+                        risk
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect()
+        });
+        let mut total = SessionRisk::default();
+        for risk in &results {
+            total.merge(risk);
+        }
+        total
+    }
+
+    // bankroll before every hand and after the last one, a ruined session stays at its last
+    // bankroll
+    pub fn trajectory(&self, bankroll: i64, hands: u64, seed: u64) -> Vec<i64> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.trajectory_with(&mut rng, bankroll, hands)
+    }
+
+    // the percentiles, e.g. 5.0 and 50.0, of the bankrolls of the sessions after every hand
+    // of the points, sessions as played by trajectory. returns the hands of the points, from 0
+    // to hands, and the bankrolls of every percentile at the points.
+    pub fn percentiles(
+        &self,
+        bankroll: i64,
+        hands: u64,
+        sessions: u64,
+        seed: u64,
+        percentiles: &[f64],
+        points: usize,
+    ) -> (Vec<u64>, Vec<Vec<f64>>) {
+        let points = points.clamp(1, hands.max(1) as usize);
+        let at: Vec<u64> = (0..=points)
+            .map(|point| hands * point as u64 / points as u64)
+            .collect();
+        let mut bankrolls = vec![Vec::with_capacity(sessions as usize); at.len()];
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..sessions {
+            let trajectory = self.trajectory_with(&mut rng, bankroll, hands);
+            for (point, hand) in at.iter().enumerate() {
+                bankrolls[point].push(trajectory[*hand as usize]);
+            }
+        }
+        let values = percentiles
+            .iter()
+            .map(|percentile| {
+                bankrolls
+                    .iter_mut()
+                    .map(|at_point| {
+                        at_point.sort_unstable();
+                        let rank = (percentile / 100.0 * (at_point.len() - 1) as f64).round();
+                        at_point.get(rank as usize).copied().unwrap_or(bankroll) as f64
+                    })
+                    .collect()
+            })
+            .collect();
+        (at, values)
+    }
+
+    fn trajectory_with<R: Rng>(&self, rng: &mut R, mut bankroll: i64, hands: u64) -> Vec<i64> {
+        let mut trajectory = Vec::with_capacity(hands as usize + 1);
+        trajectory.push(bankroll);
+        for _ in 0..hands {
+            if bankroll > 0 {
+                bankroll += self.sample(rng);
+            }
+            trajectory.push(bankroll);
+        }
+        trajectory
+    }
+
+    fn session<R: Rng>(
+        &self,
+        rng: &mut R,
+        mut bankroll: i64,
+        max_hands: u64,
+        safe: Option<i64>,
+    ) -> SessionRisk {
+        let mut session = SessionRisk {
+            sessions: 1,
+            ..Default::default()
+        };
+        while bankroll > 0 && session.hands < max_hands {
+            if safe.is_some_and(|safe| bankroll >= safe) {
+                break;
+            }
+            bankroll += self.sample(rng);
+            session.hands += 1;
+        }
+        session.ruined = u64::from(bankroll <= 0);
+        session
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} is not {expected}"
+        );
+    }
+
+    // +1 chip per hand with a standard deviation of 10
+    const MODEL: RiskModel = RiskModel {
+        ev: 1.0,
+        standard_deviation: 10.0,
+    };
+
+    #[test]
+    fn normal_distribution_function() {
+        assert_close(normal_cdf(0.0), 0.5, 1e-7);
+        assert_close(normal_cdf(1.96), 0.975, 1e-4);
+        assert_close(normal_cdf(-1.0), 0.158_655, 1e-6);
+        assert_close(ln_normal_cdf(-20.0), -203.917_155, 1e-4);
+    }
+
+    #[test]
+    fn formulas_and_their_inverse() {
+        assert_close(
+            MODEL.risk_of_ruin(100.0, Horizon::Lifetime),
+            (-2.0f64).exp(),
+            1e-12,
+        );
+        // a trip is never riskier than a lifetime and gets riskier the longer it is
+        let short = MODEL.risk_of_ruin(100.0, Horizon::Trip(100));
+        let long = MODEL.risk_of_ruin(100.0, Horizon::Trip(10_000));
+        assert!(short < long && long <= (-2.0f64).exp(), "{short} {long}");
+        assert_close(long, (-2.0f64).exp(), 1e-6);
+        // no edge: twice the chance to end a trip below zero
+        let even = RiskModel { ev: 0.0, ..MODEL };
+        assert_close(
+            even.risk_of_ruin(100.0, Horizon::Trip(100)),
+            2.0 * normal_cdf(-1.0),
+            1e-6,
+        );
+        assert_eq!(even.risk_of_ruin(1e9, Horizon::Lifetime), 1.0);
+
+        let bankroll = MODEL.bankroll_for_risk(0.05, Horizon::Lifetime).unwrap();
+        assert_close(MODEL.risk_of_ruin(bankroll, Horizon::Lifetime), 0.05, 1e-12);
+        let bankroll = even.bankroll_for_risk(0.05, Horizon::Trip(1000)).unwrap();
+        assert_close(even.risk_of_ruin(bankroll, Horizon::Trip(1000)), 0.05, 1e-9);
+        assert_eq!(even.bankroll_for_risk(0.05, Horizon::Lifetime), None);
+        assert_eq!(MODEL.n0(), Some(100.0));
+        assert_eq!(even.n0(), None);
+    }
+
+    #[test]
+    fn sessions_agree_with_the_formula() {
+        // win or lose 10 chips, winning 55% of the hands
+        let stats = SimulationStats {
+            nets: [(-10, 45), (10, 55)].into_iter().collect(),
+            ..Default::default()
+        };
+        let distribution = NetDistribution::from_stats(&stats).unwrap();
+        let model = distribution.model();
+        assert_close(model.ev, 1.0, 1e-12);
+        assert_close(model.standard_deviation, 99f64.sqrt(), 1e-12);
+
+        for horizon in [Horizon::Trip(200), Horizon::Lifetime] {
+            let sessions = distribution.simulate_sessions(60, horizon, 4000, 7, 3);
+            assert_eq!(sessions.sessions, 4000);
+            assert_eq!(
+                sessions,
+                distribution.simulate_sessions(60, horizon, 4000, 7, 1)
+            );
+            let formula = model.risk_of_ruin(60.0, horizon);
+            // the formula misses the overshoot of the last losing hand below zero
+            assert_close(sessions.risk(), formula, 0.05);
+        }
+        assert_eq!(
+            NetDistribution::from_stats(&SimulationStats::default()),
+            None
+        );
+
+        let trajectory = distribution.trajectory(30, 500, 1);
+        assert_eq!(trajectory.len(), 501);
+        assert_eq!(trajectory[0], 30);
+        assert!(
+            trajectory
+                .windows(2)
+                .all(|pair| pair[0] <= 0 && pair[1] == pair[0] || (pair[1] - pair[0]).abs() == 10)
+        );
+        let (at, values) = distribution.percentiles(60, 200, 1000, 2, &[5.0, 50.0, 95.0], 50);
+        assert_eq!(at.len(), 51);
+        assert_eq!((at[0], at[50]), (0, 200));
+        assert!(values.iter().all(|values| values[0] == 60.0));
+        // +1 chip per hand, the median grows and the worst sessions are ruined
+        assert!(
+            values[1][50] > 150.0 && values[1][50] < 300.0,
+            "{}",
+            values[1][50]
+        );
+        assert!(values[0][50] <= 0.0 && values[2][50] > values[1][50]);
+    }
+}
@@ -0,0 +1,114 @@
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TableRules {
+    pub decks: u8,
+    pub dealer_hits_soft_17: bool,
+    pub double_after_split: bool,
+    pub surrender: bool,
+    // blackjack pays numerator:denominator, 3:2 by default
+    pub blackjack_payout: (u32, u32),
+    // fraction of the shoe dealt before the discards are shuffled back in
+    pub penetration: f32,
+    pub max_hands: u8,
+    // cards discarded face down after every shuffle
+    pub burn_cards: u8,
+}
+
+impl Default for TableRules {
+    fn default() -> Self {
+        TableRules {
+            decks: 6,
+            dealer_hits_soft_17: false,
+            double_after_split: true,
+            surrender: true,
+            blackjack_payout: (3, 2),
+            penetration: 0.75,
+            max_hands: 4,
+            burn_cards: 1,
+        }
+    }
+}
+
+pub const PRESETS: [&str; 5] = [
+    "default",
+    "vegas-strip",
+    "atlantic-city",
+    "downtown",
+    "single-deck",
+];
+
+impl TableRules {
+    pub fn preset(name: &str) -> Option<TableRules> {
+        let default = TableRules::default();
+        let rules = match name {
+            "default" => default,
+            "vegas-strip" => TableRules {
+                decks: 4,
+                ..default
+            },
+            "atlantic-city" => TableRules {
+                decks: 8,
+                ..default
+            },
+            "downtown" => TableRules {
+                decks: 2,
+                dealer_hits_soft_17: true,
+                surrender: false,
+                ..default
+            },
+            "single-deck" => TableRules {
+                decks: 1,
+                dealer_hits_soft_17: true,
+                double_after_split: false,
+                surrender: false,
+                blackjack_payout: (6, 5),
+                penetration: 0.6,
+                ..default
+            },
+            _ => return None,
+        };
+        Some(rules)
+    }
+
+    pub fn blackjack_win(&self, bet: u32) -> u32 {
+        let (numerator, denominator) = self.blackjack_payout;
+        bet * numerator / denominator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blackjack_pays_three_to_two_by_default() {
+        let rules = TableRules::default();
+        assert_eq!(rules.blackjack_win(10), 15);
+    }
+
+    #[test]
+    fn every_preset_is_known() {
+        for name in PRESETS {
+            assert!(TableRules::preset(name).is_some(), "{name}");
+        }
+        assert_eq!(TableRules::preset("default"), Some(TableRules::default()));
+        assert_eq!(TableRules::preset("macau"), None);
+    }
+
+    #[test]
+    fn downtown_dealer_hits_soft_17() {
+        let rules = TableRules::preset("downtown").unwrap();
+        assert_eq!(rules.decks, 2);
+        assert!(rules.dealer_hits_soft_17);
+    }
+
+    #[test]
+    fn blackjack_win_is_rounded_down() {
+        let rules = TableRules {
+            blackjack_payout: (6, 5),
+            ..Default::default()
+        };
+        assert_eq!(rules.blackjack_win(10), 12);
+        assert_eq!(rules.blackjack_win(3), 3);
+    }
+}
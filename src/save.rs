@@ -0,0 +1,252 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    card::CardValue,
+    game::{Game, Phase},
+};
+
+// first line of every save file, followed by the game as JSON
+const SAVE_HEADER: &str = "blackjack-save";
+pub const SAVE_VERSION: u32 = 2;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    NotASave,
+    Version { found: String },
+    Corrupted(serde_json::Error),
+    // well formed but not a game this build could have saved
+    Invalid(&'static str),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "{error}"),
+            SaveError::NotASave => write!(f, "not a blackjack save file"),
+            SaveError::Version { found } => write!(
+                f,
+                "incompatible save version {found}, this build reads version {SAVE_VERSION}"
+            ),
+            SaveError::Corrupted(error) => write!(f, "corrupted save file: {error}"),
+            SaveError::Invalid(reason) => write!(f, "corrupted save file: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(error: io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+pub fn write_game<W: Write>(game: &Game, mut writer: W) -> Result<(), SaveError> {
+    writeln!(writer, "{SAVE_HEADER} {SAVE_VERSION}")?;
+    serde_json::to_writer(&mut writer, game).map_err(SaveError::Corrupted)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn read_game<R: BufRead>(mut reader: R) -> Result<Game, SaveError> {
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    let version = match header.trim_end().split_once(' ') {
+        Some((SAVE_HEADER, version)) => version,
+        _ => return Err(SaveError::NotASave),
+    };
+    if version != SAVE_VERSION.to_string() {
+        return Err(SaveError::Version {
+            found: version.to_string(),
+        });
+    }
+    let game = serde_json::from_reader(reader).map_err(SaveError::Corrupted)?;
+    validate(&game).map_err(SaveError::Invalid)?;
+    Ok(game)
+}
+
+// the deserialized game skips the checks of the constructors, what they forbid is refused here
+// rather than panicking later in the round
+fn validate(game: &Game) -> Result<(), &'static str> {
+    if game.nb_seats() == 0 {
+        return Err("the game has no seat");
+    }
+    let hands = game.players.iter().map(|player| &player.hand);
+    let mut cards = game
+        .deck
+        .cards
+        .iter()
+        .chain(&game.discards)
+        .chain(&game.dealer.hand)
+        .chain(hands.flatten());
+    if cards.any(|card| matches!(card.value, CardValue::Number(n) if !(2..=10).contains(&n))) {
+        return Err("a card number is not between 2 and 10");
+    }
+    if game.deck.cards.is_empty() && game.discards.is_empty() {
+        return Err("the shoe is empty");
+    }
+    // every seat in order, the hands split from a seat follow it
+    let mut next_seat = 0;
+    for player in &game.players {
+        if player.seat == next_seat {
+            next_seat += 1;
+        } else if player.seat + 1 != next_seat {
+            return Err("the hands do not match the seats");
+        }
+    }
+    if next_seat != game.nb_seats() {
+        return Err("the hands do not match the seats");
+    }
+    let consistent = if game.phase() == Phase::Betting {
+        game.players.iter().all(|player| player.hand.is_empty())
+    } else {
+        game.dealer.hand.len() >= 2 && game.players.iter().all(|player| player.hand.len() >= 2)
+    };
+    if !consistent {
+        return Err("the cards dealt do not match the phase of the round");
+    }
+    Ok(())
+}
+
+pub fn save_game<P: AsRef<Path>>(game: &Game, path: P) -> Result<(), SaveError> {
+    write_game(game, BufWriter::new(File::create(path)?))
+}
+
+pub fn load_game<P: AsRef<Path>>(path: P) -> Result<Game, SaveError> {
+    read_game(BufReader::new(File::open(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::rules::TableRules;
+
+    fn mid_round_game() -> Game {
+        let mut game = Game::with_seed(2, TableRules::default(), 3);
+        game.new_round();
+        game.place_bet(0, 10);
+        game.place_bet(1, 25);
+        game.deal_initial_cards();
+        game
+    }
+
+    #[test]
+    fn saved_game_is_loaded_back() {
+        let game = mid_round_game();
+        let mut buffer = Vec::new();
+        write_game(&game, &mut buffer).unwrap();
+        assert!(buffer.starts_with(b"blackjack-save 2\n"));
+
+        let loaded = read_game(buffer.as_slice()).unwrap();
+        assert_eq!(loaded.deck.cards, game.deck.cards);
+        assert_eq!(loaded.discards, game.discards);
+        assert_eq!(loaded.phase(), game.phase());
+        assert_eq!(loaded.get_bankroll(1), game.get_bankroll(1));
+        assert_eq!(loaded.players[1].bet, 25);
+    }
+
+    #[test]
+    fn settled_phase_is_kept() {
+        let mut game = mid_round_game();
+        while let Some(hand) = game.current_hand() {
+            game.player_stand(hand);
+        }
+        game.dealer_play();
+        game.settle();
+        let mut buffer = Vec::new();
+        write_game(&game, &mut buffer).unwrap();
+        assert_eq!(
+            read_game(buffer.as_slice()).unwrap().phase(),
+            Phase::Settled
+        );
+    }
+
+    #[test]
+    fn other_version_is_rejected() {
+        let game = mid_round_game();
+        let mut buffer = Vec::new();
+        write_game(&game, &mut buffer).unwrap();
+        let buffer =
+            String::from_utf8(buffer)
+                .unwrap()
+                .replacen("blackjack-save 2", "blackjack-save 1", 1);
+
+        let error = read_game(buffer.as_bytes()).err().unwrap();
+        assert!(matches!(&error, SaveError::Version { found } if found == "1"));
+        assert_eq!(
+            error.to_string(),
+            "incompatible save version 1, this build reads version 2"
+        );
+    }
+
+    #[test]
+    fn file_without_header_is_rejected() {
+        let error = read_game("{\"players\": []}".as_bytes()).err().unwrap();
+        assert!(matches!(error, SaveError::NotASave));
+    }
+
+    #[test]
+    fn truncated_save_is_corrupted() {
+        let error = read_game("blackjack-save 2\n{\"players\":".as_bytes())
+            .err()
+            .unwrap();
+        assert!(matches!(error, SaveError::Corrupted(_)));
+    }
+
+    // the error reading the mid round game back once edited
+    fn read_tampered(edit: impl FnOnce(&mut serde_json::Value)) -> SaveError {
+        let mut json = serde_json::to_value(mid_round_game()).unwrap();
+        edit(&mut json);
+        let save = format!("blackjack-save 2\n{json}");
+        read_game(save.as_bytes()).err().unwrap()
+    }
+
+    #[test]
+    fn inconsistent_save_is_corrupted() {
+        let error = read_tampered(|json| json["deck"]["cards"][0]["value"] = json!({"Number": 1}));
+        assert!(matches!(error, SaveError::Invalid(_)));
+        assert_eq!(
+            error.to_string(),
+            "corrupted save file: a card number is not between 2 and 10"
+        );
+
+        let error = read_tampered(|json| json["players"][1]["seat"] = json!(2));
+        assert_eq!(
+            error.to_string(),
+            "corrupted save file: the hands do not match the seats"
+        );
+
+        let error = read_tampered(|json| {
+            json["deck"]["cards"] = json!([]);
+            json["discards"] = json!([]);
+        });
+        assert_eq!(error.to_string(), "corrupted save file: the shoe is empty");
+
+        let error = read_tampered(|json| json["dealer"]["hand"] = json!([]));
+        assert_eq!(
+            error.to_string(),
+            "corrupted save file: the cards dealt do not match the phase of the round"
+        );
+    }
+
+    #[test]
+    fn save_and_load_from_disk() {
+        let path = std::env::temp_dir().join(format!("blackjack-save-{}.txt", std::process::id()));
+        let game = mid_round_game();
+        save_game(&game, &path).unwrap();
+        let loaded = load_game(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.deck.cards, game.deck.cards);
+
+        assert!(matches!(load_game(&path), Err(SaveError::Io(_))));
+    }
+}
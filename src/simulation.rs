@@ -0,0 +1,723 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt,
+    io::{self, Write},
+    rc::Rc,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread,
+};
+
+use crate::{
+    autoplay::PlayerStrategy,
+    counting::{CountTracker, TagSystem},
+    game::Game,
+    player::{PlayerAction, PlayerOutcome},
+    risk::{Horizon, RiskModel},
+    rules::TableRules,
+};
+
+pub const OUTCOMES: [PlayerOutcome; 6] = [
+    PlayerOutcome::Win,
+    PlayerOutcome::Lose,
+    PlayerOutcome::Bust,
+    PlayerOutcome::Blackjack,
+    PlayerOutcome::Push,
+    PlayerOutcome::Surrender,
+];
+
+pub const ACTIONS: [PlayerAction; 5] = [
+    PlayerAction::Hit,
+    PlayerAction::Stand,
+    PlayerAction::Double,
+    PlayerAction::Split,
+    PlayerAction::Surrender,
+];
+
+// seats never run out of chips during a simulation
+pub(crate) const SIMULATION_BANKROLL: i64 = i64::MAX / 4;
+
+// z score of a 95% confidence interval
+const Z_95: f64 = 1.96;
+
+// the confidence interval is only checked every that many rounds
+const CHECK_INTERVAL: u64 = 1000;
+
+// rounds of every batch of a parallel simulation, each batch starts with a new shoe
+pub const BATCH_ROUNDS: u64 = 10_000;
+
+// columns of the hand log, seats and hands numbered from 1 as in the hand history, cards
+// and actions separated by spaces
+pub const HAND_LOG_HEADER: &str = "round,seat,hand,true_count,bet,cards,dealer,actions,outcome,net";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopCondition {
+    Rounds(u64),
+    // stops once the 95% confidence interval of the edge is within ±half_width, e.g. 0.001
+    // for ±0.1% of the bet, or after max_rounds
+    Confidence { half_width: f64, max_rounds: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationConfig {
+    pub rules: TableRules,
+    pub seats: u8,
+    pub seed: u64,
+    pub stop: StopCondition,
+    // count giving the true count of every hand in the results by true count
+    pub count: TagSystem,
+}
+
+// results of the hands played at a true count, floored to an integer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrueCountStats {
+    pub hands: u64,
+    pub wagered: u64,
+    pub net: i64,
+    pub net_squared: u128,
+}
+
+impl TrueCountStats {
+    fn merge(&mut self, other: &TrueCountStats) {
+        self.hands += other.hands;
+        self.wagered += other.wagered;
+        self.net += other.net;
+        self.net_squared += other.net_squared;
+    }
+
+    pub fn edge(&self) -> f64 {
+        if self.wagered == 0 {
+            return 0.0;
+        }
+        self.net as f64 / self.wagered as f64
+    }
+
+    // half width of the 95% confidence interval of the edge
+    pub fn edge_confidence(&self) -> f64 {
+        if self.hands < 2 {
+            return f64::INFINITY;
+        }
+        let n = self.hands as f64;
+        let mean = self.net as f64 / n;
+        let variance = ((self.net_squared as f64 - n * mean * mean) / (n - 1.0)).max(0.0);
+        Z_95 * (variance / n).sqrt() * n / self.wagered as f64
+    }
+}
+
+// totals kept as integers so that results of separate runs add up exactly
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimulationStats {
+    pub rounds: u64,
+    // one hand per seat and round, the hands of a split count as one
+    pub hands: u64,
+    pub wagered: u64,
+    pub net: i64,
+    pub net_squared: u128,
+    // every played hand, split hands included, in the order of OUTCOMES
+    pub outcomes: [u64; 6],
+    // in the order of ACTIONS
+    pub actions: [u64; 5],
+    // shoes dealt until the reshuffle and their hands, the shoe in play is not counted
+    pub shoes: u64,
+    pub shoe_hands: u64,
+    // hands of every net result, the distribution sessions are drawn from to measure the risk
+    pub nets: BTreeMap<i64, u64>,
+    // by the true count before the bets of the round
+    pub true_counts: BTreeMap<i32, TrueCountStats>,
+}
+
+impl SimulationStats {
+    pub fn merge(&mut self, other: &SimulationStats) {
+        self.rounds += other.rounds;
+        self.hands += other.hands;
+        self.wagered += other.wagered;
+        self.net += other.net;
+        self.net_squared += other.net_squared;
+        for (count, other) in self.outcomes.iter_mut().zip(other.outcomes) {
+            *count += other;
+        }
+        for (count, other) in self.actions.iter_mut().zip(other.actions) {
+            *count += other;
+        }
+        self.shoes += other.shoes;
+        self.shoe_hands += other.shoe_hands;
+        for (net, hands) in &other.nets {
+            *self.nets.entry(*net).or_default() += hands;
+        }
+        for (true_count, stats) in &other.true_counts {
+            self.true_counts
+                .entry(*true_count)
+                .or_default()
+                .merge(stats);
+        }
+    }
+
+    // in chips
+    pub fn ev_per_hand(&self) -> f64 {
+        if self.hands == 0 {
+            return 0.0;
+        }
+        self.net as f64 / self.hands as f64
+    }
+
+    // of the net result of a hand, in squared chips
+    pub fn variance(&self) -> f64 {
+        if self.hands < 2 {
+            return f64::INFINITY;
+        }
+        let n = self.hands as f64;
+        let mean = self.ev_per_hand();
+        ((self.net_squared as f64 - n * mean * mean) / (n - 1.0)).max(0.0)
+    }
+
+    pub fn standard_deviation(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    // of the ev per hand, in chips
+    pub fn standard_error(&self) -> f64 {
+        (self.variance() / self.hands as f64).sqrt()
+    }
+
+    // chance to lose the bankroll before it grows for good, playing on forever at the
+    // measured ev and variance per hand. certain ruin without an edge.
+    pub fn risk_of_ruin(&self, bankroll: i64) -> f64 {
+        RiskModel::from_stats(self).risk_of_ruin(bankroll as f64, Horizon::Lifetime)
+    }
+
+    // net result as a fraction of the initial bets, negative when the house wins
+    pub fn edge(&self) -> f64 {
+        if self.wagered == 0 {
+            return 0.0;
+        }
+        self.net as f64 / self.wagered as f64
+    }
+
+    pub fn edge_standard_error(&self) -> f64 {
+        if self.wagered == 0 {
+            return f64::INFINITY;
+        }
+        self.standard_error() * self.hands as f64 / self.wagered as f64
+    }
+
+    // half width of the 95% confidence interval of the edge
+    pub fn edge_confidence(&self) -> f64 {
+        Z_95 * self.edge_standard_error()
+    }
+
+    pub fn outcome_count(&self, outcome: PlayerOutcome) -> u64 {
+        self.outcomes[OUTCOMES.iter().position(|o| *o == outcome).unwrap()]
+    }
+
+    pub fn action_count(&self, action: PlayerAction) -> u64 {
+        self.actions[ACTIONS.iter().position(|a| *a == action).unwrap()]
+    }
+
+    pub fn hands_per_shoe(&self) -> Option<f64> {
+        (self.shoes > 0).then(|| self.shoe_hands as f64 / self.shoes as f64)
+    }
+
+    fn is_done(&self, stop: StopCondition) -> bool {
+        match stop {
+            StopCondition::Rounds(rounds) => self.rounds >= rounds,
+            StopCondition::Confidence {
+                half_width,
+                max_rounds,
+            } => {
+                self.rounds >= max_rounds
+                    || (self.rounds > 0
+                        && self.rounds.is_multiple_of(CHECK_INTERVAL)
+                        && self.edge_confidence() <= half_width)
+            }
+        }
+    }
+}
+
+impl fmt::Display for SimulationStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Rounds: {}, hands: {}", self.rounds, self.hands)?;
+        writeln!(
+            f,
+            "EV per hand: {:+.4}% of the bet ± {:.4}% (95%), {:+.4} chips, standard error {:.4}",
+            self.edge() * 100.0,
+            self.edge_confidence() * 100.0,
+            self.ev_per_hand(),
+            self.standard_error()
+        )?;
+        writeln!(
+            f,
+            "Standard deviation per hand: {:.4} chips, variance {:.4}",
+            self.standard_deviation(),
+            self.variance()
+        )?;
+        let played: u64 = self.outcomes.iter().sum();
+        writeln!(f, "Outcomes:")?;
+        for (outcome, count) in OUTCOMES.iter().zip(self.outcomes) {
+            writeln!(
+                f,
+                "  {:<10} {:>7.3}%  {count}",
+                outcome.to_string(),
+                percent(count, played)
+            )?;
+        }
+        let decisions: u64 = self.actions.iter().sum();
+        writeln!(f, "Actions:")?;
+        for (action, count) in ACTIONS.iter().zip(self.actions) {
+            writeln!(
+                f,
+                "  {:<10} {:>7.3}%  {count}",
+                action.to_string(),
+                percent(count, decisions)
+            )?;
+        }
+        match self.hands_per_shoe() {
+            Some(hands) => writeln!(f, "Hands per shoe: {hands:.1}"),
+            None => writeln!(f, "Hands per shoe: no shoe finished"),
+        }
+    }
+}
+
+fn percent(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
+
+// plays rounds with every seat driven by the strategy made for it until the stop condition.
+// the strategy is made from the seat and a seed derived from the simulation seed.
+pub fn simulate<F: FnMut(usize, u64) -> Box<dyn PlayerStrategy>>(
+    config: &SimulationConfig,
+    strategy: F,
+) -> SimulationStats {
+    run_rounds(config, config.seed, config.stop, 0, None, strategy)
+}
+
+// same as simulate on all the threads, 0 for one per core. rounds are played in batches of
+// BATCH_ROUNDS seeded from the simulation seed and merged in order, so the results only
+// depend on the seed and never on the number of threads. the confidence interval is
+// checked after every batch.
+pub fn simulate_parallel<F: Fn(usize, u64) -> Box<dyn PlayerStrategy> + Sync>(
+    config: &SimulationConfig,
+    threads: usize,
+    strategy: F,
+) -> SimulationStats {
+    run_parallel(config, threads, strategy, None).unwrap()
+}
+
+// same as simulate_parallel, writing every hand to the log as CSV under HAND_LOG_HEADER in
+// the order of the rounds
+pub fn simulate_parallel_logged<F: Fn(usize, u64) -> Box<dyn PlayerStrategy> + Sync>(
+    config: &SimulationConfig,
+    threads: usize,
+    strategy: F,
+    log: &mut (dyn Write + Send),
+) -> io::Result<SimulationStats> {
+    writeln!(log, "{HAND_LOG_HEADER}")?;
+    let stats = run_parallel(config, threads, strategy, Some(log))?;
+    log.flush()?;
+    Ok(stats)
+}
+
+fn run_parallel<F: Fn(usize, u64) -> Box<dyn PlayerStrategy> + Sync>(
+    config: &SimulationConfig,
+    threads: usize,
+    strategy: F,
+    log: Option<&mut (dyn Write + Send)>,
+) -> io::Result<SimulationStats> {
+    let logged = log.is_some();
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        threads => threads,
+    };
+    let next_batch = AtomicU64::new(0);
+    let stopped = AtomicBool::new(false);
+    let progress = Mutex::new(Progress {
+        log,
+        ..Default::default()
+    });
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                while !stopped.load(Ordering::Relaxed) {
+                    let batch = next_batch.fetch_add(1, Ordering::Relaxed);
+                    let rounds = batch_rounds(config.stop, batch);
+                    if rounds == 0 {
+                        break;
+                    }
+                    let seed = batch_seed(config.seed, batch);
+                    let mut log = logged.then(Vec::new);
+                    let stats = run_rounds(
+                        config,
+                        seed,
+                        StopCondition::Rounds(rounds),
+                        batch * BATCH_ROUNDS,
+                        log.as_mut(),
+                        &strategy,
+                    );
+                    let mut progress = progress.lock().unwrap();
+                    if progress.add(batch, stats, log, config.stop) {
+                        stopped.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+    let progress = progress.into_inner().unwrap();
+    match progress.error {
+        Some(error) => Err(error),
+        None => Ok(progress.total),
+    }
+}
+
+// batches finished out of order wait until every batch before them is merged, their hands
+// are logged as they are merged
+#[derive(Default)]
+struct Progress<'a> {
+    pending: BTreeMap<u64, (SimulationStats, Option<Vec<u8>>)>,
+    merged: u64,
+    done: bool,
+    total: SimulationStats,
+    log: Option<&'a mut (dyn Write + Send)>,
+    // the first write error of the log, which stops the simulation
+    error: Option<io::Error>,
+}
+
+impl Progress<'_> {
+    // true once the merged batches meet the stop condition or the log cannot be written
+    fn add(
+        &mut self,
+        batch: u64,
+        stats: SimulationStats,
+        hands: Option<Vec<u8>>,
+        stop: StopCondition,
+    ) -> bool {
+        self.pending.insert(batch, (stats, hands));
+        while let Some((stats, hands)) = self.pending.remove(&self.merged) {
+            self.merged += 1;
+            if self.done {
+                continue;
+            }
+            self.total.merge(&stats);
+            if let (Some(log), Some(hands)) = (self.log.as_mut(), hands)
+                && let Err(error) = log.write_all(&hands)
+            {
+                self.error = Some(error);
+                self.done = true;
+                continue;
+            }
+            self.done = match stop {
+                StopCondition::Rounds(rounds) => self.total.rounds >= rounds,
+                StopCondition::Confidence {
+                    half_width,
+                    max_rounds,
+                } => self.total.rounds >= max_rounds || self.total.edge_confidence() <= half_width,
+            };
+        }
+        self.done
+    }
+}
+
+// 0 once every round is in a batch
+fn batch_rounds(stop: StopCondition, batch: u64) -> u64 {
+    let rounds = match stop {
+        StopCondition::Rounds(rounds) => rounds,
+        StopCondition::Confidence { max_rounds, .. } => max_rounds,
+    };
+    rounds
+        .saturating_sub(batch.saturating_mul(BATCH_ROUNDS))
+        .min(BATCH_ROUNDS)
+}
+
+// splitmix64 of the batch, so that neighbouring batches get unrelated shoes
+pub fn batch_seed(seed: u64, batch: u64) -> u64 {
+    let mut z = seed.wrapping_add((batch + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// rounds are numbered in the log from first_round + 1
+fn run_rounds<F: FnMut(usize, u64) -> Box<dyn PlayerStrategy>>(
+    config: &SimulationConfig,
+    seed: u64,
+    stop: StopCondition,
+    first_round: u64,
+    mut log: Option<&mut Vec<u8>>,
+    mut strategy: F,
+) -> SimulationStats {
+    let mut game = Game::with_seed(config.seats, config.rules.clone(), seed);
+    for seat in 0..game.nb_seats() {
+        game.set_bankroll(seat, SIMULATION_BANKROLL);
+        game.set_strategy(seat, strategy(seat, seed.wrapping_add(seat as u64)));
+    }
+    let tracker = CountTracker::for_game(Box::new(config.count), &game);
+    let tracker = Rc::new(RefCell::new(tracker));
+    game.subscribe(Box::new(tracker.clone()));
+    let mut stats = SimulationStats::default();
+    let mut hands_in_shoe = 0;
+    while !stats.is_done(stop) {
+        let shuffles = game.get_shuffles();
+        game.new_round();
+        if game.get_shuffles() != shuffles {
+            stats.shoes += 1;
+            stats.shoe_hands += hands_in_shoe;
+            hands_in_shoe = 0;
+        }
+        let true_count = tracker.borrow().get_true_count().floor() as i32;
+        let round = first_round + stats.rounds + 1;
+        let log = log.as_mut().map(|log| (round, &mut **log));
+        play_round(&mut game, &mut stats, true_count, log);
+        hands_in_shoe += game.nb_seats() as u64;
+    }
+    stats
+}
+
+fn play_round(
+    game: &mut Game,
+    stats: &mut SimulationStats,
+    true_count: i32,
+    log: Option<(u64, &mut Vec<u8>)>,
+) {
+    game.place_automated_bets()
+        .expect("simulated seats never run out of chips");
+    // one hand per seat before the deal
+    let bets: Vec<u32> = game.players.iter().map(|player| player.bet).collect();
+    game.deal_initial_cards();
+    game.place_automated_insurance();
+    // actions of every hand, a split hand is inserted after the one it comes from
+    let mut actions = vec![Vec::new(); game.players.len()];
+    while let Some((hand, action)) = game.play_automated_action() {
+        stats.actions[ACTIONS.iter().position(|a| *a == action).unwrap()] += 1;
+        if action == PlayerAction::Split {
+            actions.insert(hand + 1, Vec::new());
+        }
+        actions[hand].push(action);
+    }
+    game.dealer_play();
+    let results = game.settle();
+
+    let mut seat_nets = vec![0; bets.len()];
+    for (hand, net) in results.iter().enumerate() {
+        seat_nets[game.players[hand].seat] += net;
+        let outcome = game.get_outcome(hand);
+        stats.outcomes[OUTCOMES.iter().position(|o| *o == outcome).unwrap()] += 1;
+    }
+    let by_count = stats.true_counts.entry(true_count).or_default();
+    for (bet, net) in bets.into_iter().zip(seat_nets) {
+        let net_squared = (net as i128 * net as i128) as u128;
+        stats.hands += 1;
+        stats.wagered += bet as u64;
+        stats.net += net;
+        stats.net_squared += net_squared;
+        *stats.nets.entry(net).or_default() += 1;
+        by_count.merge(&TrueCountStats {
+            hands: 1,
+            wagered: bet as u64,
+            net,
+            net_squared,
+        });
+    }
+    stats.rounds += 1;
+
+    if let Some((round, log)) = log {
+        let cards = |cards: &[crate::card::Card]| -> String {
+            let cards: Vec<String> = cards.iter().map(|card| card.to_string()).collect();
+            cards.join(" ")
+        };
+        let dealer = cards(&game.dealer.hand);
+        for (hand, (player, net)) in game.players.iter().zip(&results).enumerate() {
+            let played: Vec<String> = actions[hand].iter().map(|a| a.to_string()).collect();
+            // writing to memory never fails
+            let _ = writeln!(
+                log,
+                "{round},{},{},{true_count},{},{},{dealer},{},{},{net}",
+                player.seat + 1,
+                hand + 1,
+                player.bet,
+                cards(&player.hand),
+                played.join(" "),
+                game.get_outcome(hand)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autoplay::{ChartStrategy, MimicDealer, strategy_by_name};
+
+    fn config(stop: StopCondition) -> SimulationConfig {
+        SimulationConfig {
+            rules: TableRules::default(),
+            seats: 2,
+            seed: 11,
+            stop,
+            count: crate::counting::HI_LO,
+        }
+    }
+
+    fn basic(config: &SimulationConfig) -> SimulationStats {
+        simulate(config, |_, _| {
+            Box::new(ChartStrategy::basic(&config.rules, 10))
+        })
+    }
+
+    #[test]
+    fn rounds_are_counted_and_consistent() {
+        let stats = basic(&config(StopCondition::Rounds(2000)));
+        assert_eq!(stats.rounds, 2000);
+        assert_eq!(stats.hands, 4000);
+        assert_eq!(stats.wagered, 40000);
+        assert!(stats.outcomes.iter().sum::<u64>() >= stats.hands);
+        assert!(stats.action_count(PlayerAction::Stand) > 0);
+        assert!(stats.shoes > 0);
+        let hands_per_shoe = stats.hands_per_shoe().unwrap();
+        assert!((40.0..120.0).contains(&hands_per_shoe), "{hands_per_shoe}");
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_results() {
+        let config = config(StopCondition::Rounds(500));
+        assert_eq!(basic(&config), basic(&config));
+    }
+
+    #[test]
+    fn basic_strategy_edge_is_close_to_the_house_edge() {
+        let stats = basic(&config(StopCondition::Rounds(30000)));
+        let edge = stats.edge();
+        assert!((-0.03..0.02).contains(&edge), "{edge}");
+        assert!(stats.edge_confidence() < 0.02);
+
+        let mimic = simulate(&config(StopCondition::Rounds(30000)), |_, _| {
+            Box::new(MimicDealer { bet: 10 })
+        });
+        assert!(mimic.edge() < edge);
+    }
+
+    #[test]
+    fn confidence_target_stops_the_simulation() {
+        let stats = basic(&config(StopCondition::Confidence {
+            half_width: 0.05,
+            max_rounds: 1_000_000,
+        }));
+        assert!(stats.edge_confidence() <= 0.05);
+        assert!(stats.rounds.is_multiple_of(CHECK_INTERVAL));
+        assert!(stats.rounds < 1_000_000);
+
+        let capped = basic(&config(StopCondition::Confidence {
+            half_width: 0.0001,
+            max_rounds: 300,
+        }));
+        assert_eq!(capped.rounds, 300);
+    }
+
+    #[test]
+    fn risk_of_ruin_needs_an_edge() {
+        // +1 chip per hand with a variance of 100
+        let stats = SimulationStats {
+            hands: 100,
+            wagered: 1000,
+            net: 100,
+            net_squared: 10_000,
+            ..Default::default()
+        };
+        assert!((stats.variance() - 100.0).abs() < 1e-9);
+        assert!((stats.risk_of_ruin(100) - (-2.0f64).exp()).abs() < 1e-9);
+        assert_eq!(stats.risk_of_ruin(0), 1.0);
+        let losing = SimulationStats { net: -100, ..stats };
+        assert_eq!(losing.risk_of_ruin(1_000_000), 1.0);
+    }
+
+    #[test]
+    fn merged_stats_add_up() {
+        let config = config(StopCondition::Rounds(300));
+        let first = basic(&config);
+        let second = simulate(&config, |_, seed| {
+            strategy_by_name("random", &config.rules, 10, seed).unwrap()
+        });
+        let mut merged = first.clone();
+        merged.merge(&second);
+        assert_eq!(merged.rounds, 600);
+        assert_eq!(merged.net, first.net + second.net);
+        assert_eq!(merged.nets.values().sum::<u64>(), merged.hands);
+        assert_eq!(
+            merged.outcome_count(PlayerOutcome::Win),
+            first.outcome_count(PlayerOutcome::Win) + second.outcome_count(PlayerOutcome::Win)
+        );
+    }
+
+    #[test]
+    fn report_lists_every_outcome_and_action() {
+        let report = basic(&config(StopCondition::Rounds(100))).to_string();
+        assert!(report.starts_with("Rounds: 100, hands: 200\nEV per hand: "));
+        for name in ["blackjack", "surrender", "double", "split"] {
+            assert!(report.contains(&format!("  {name} ")), "{name}");
+        }
+        assert!(report.contains("Hands per shoe: "));
+    }
+
+    #[test]
+    fn parallel_results_do_not_depend_on_the_threads() {
+        let config = config(StopCondition::Rounds(2 * BATCH_ROUNDS + 500));
+        let random = |_, seed| strategy_by_name("random", &config.rules, 10, seed).unwrap();
+        let one = simulate_parallel(&config, 1, random);
+        assert_eq!(one.rounds, 2 * BATCH_ROUNDS + 500);
+        assert_eq!(simulate_parallel(&config, 3, random), one);
+        assert_eq!(simulate_parallel(&config, 8, random), one);
+    }
+
+    #[test]
+    fn parallel_confidence_target_is_checked_after_every_batch() {
+        let config = config(StopCondition::Confidence {
+            half_width: 0.03,
+            max_rounds: 100 * BATCH_ROUNDS,
+        });
+        let stats = simulate_parallel(&config, 4, |_, _| {
+            Box::new(ChartStrategy::basic(&config.rules, 10))
+        });
+        assert!(stats.edge_confidence() <= 0.03);
+        assert_eq!(stats.rounds, BATCH_ROUNDS);
+    }
+
+    #[test]
+    fn hand_log_does_not_depend_on_the_threads() {
+        let config = config(StopCondition::Rounds(BATCH_ROUNDS + 200));
+        let logged = |threads| {
+            let mut log = Vec::new();
+            let stats = simulate_parallel_logged(
+                &config,
+                threads,
+                |_, _| Box::new(ChartStrategy::basic(&config.rules, 10)),
+                &mut log,
+            )
+            .unwrap();
+            (stats, String::from_utf8(log).unwrap())
+        };
+        let (stats, log) = logged(1);
+        assert_eq!(logged(3), (stats.clone(), log.clone()));
+        let rows: Vec<&str> = log.lines().skip(1).collect();
+        assert_eq!(rows.len() as u64, stats.outcomes.iter().sum::<u64>());
+        assert!(rows.last().unwrap().starts_with("10200,2,"));
+        let net: i64 = rows
+            .iter()
+            .map(|row| row.rsplit(',').next().unwrap().parse::<i64>().unwrap())
+            .sum();
+        assert_eq!(net, stats.net);
+        let hands: u64 = stats.true_counts.values().map(|count| count.hands).sum();
+        assert_eq!(hands, stats.hands);
+    }
+
+    #[test]
+    fn batches_get_distinct_seeds() {
+        assert_ne!(batch_seed(1, 0), batch_seed(1, 1));
+        assert_ne!(batch_seed(1, 0), batch_seed(2, 0));
+        assert_eq!(batch_rounds(StopCondition::Rounds(25_000), 2), 5_000);
+        assert_eq!(batch_rounds(StopCondition::Rounds(25_000), 3), 0);
+    }
+}
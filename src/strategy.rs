@@ -0,0 +1,282 @@
+use std::cell::RefCell;
+
+use crate::{
+    card::Card,
+    chart::{ChartAction, ChartTable, StrategyChart},
+    game::Game,
+    player::{Player, PlayerAction},
+    rules::TableRules,
+};
+
+// basic strategy for the multi deck game, adjusted for one or two decks, H17, DAS and surrender
+pub struct BasicStrategy;
+
+thread_local! {
+    // the chart of the rules last advised on, so advising hand after hand builds it once
+    static LAST_CHART: RefCell<Option<(TableRules, StrategyChart)>> = const { RefCell::new(None) };
+}
+
+fn with_chart<T>(rules: &TableRules, advise: impl FnOnce(&StrategyChart) -> T) -> T {
+    LAST_CHART.with_borrow_mut(|last| {
+        if last.as_ref().is_none_or(|(of, _)| of != rules) {
+            *last = Some((rules.clone(), BasicStrategy::chart(rules)));
+        }
+        advise(&last.as_ref().unwrap().1)
+    })
+}
+
+impl BasicStrategy {
+    // best action for a first decision on the hand, doubling and surrender allowed on two cards
+    pub fn recommend(hand: &[Card], dealer_upcard: &Card, rules: &TableRules) -> PlayerAction {
+        let player = Player {
+            hand: hand.to_vec(),
+            ..Default::default()
+        };
+        let mut legal = vec![PlayerAction::Hit, PlayerAction::Stand];
+        if hand.len() == 2 {
+            legal.push(PlayerAction::Double);
+            if rules.surrender {
+                legal.push(PlayerAction::Surrender);
+            }
+        }
+        if player.is_pair() {
+            legal.push(PlayerAction::Split);
+        }
+        BasicStrategy::recommend_from(hand, dealer_upcard, rules, &legal)
+    }
+
+    // best action among the legal ones, e.g. hit instead of doubling on three cards
+    pub fn recommend_from(
+        hand: &[Card],
+        dealer_upcard: &Card,
+        rules: &TableRules,
+        legal: &[PlayerAction],
+    ) -> PlayerAction {
+        with_chart(rules, |chart| chart.recommend(hand, dealer_upcard, legal))
+    }
+
+    // the whole strategy for the rules as a chart, e.g. to export it and edit it
+    pub fn chart(rules: &TableRules) -> StrategyChart {
+        StrategyChart::from_fn(|table, row, dealer| match table {
+            ChartTable::Hard => hard_action(row.parse().unwrap(), dealer, rules),
+            ChartTable::Soft => soft_action(11 + row[1..].parse::<u8>().unwrap(), dealer, rules),
+            ChartTable::Pairs => {
+                let card_value = match &row[..1] {
+                    "T" => 10,
+                    "A" => 11,
+                    digit => digit.parse().unwrap(),
+                };
+                pair_action(card_value, dealer, rules)
+            }
+        })
+    }
+
+    // advice for a hand of the game, against the dealer face up card
+    pub fn recommend_hand(game: &Game, player_index: usize) -> PlayerAction {
+        with_chart(&game.rules, |chart| {
+            chart.recommend_hand(game, player_index)
+        })
+    }
+}
+
+// dealer is the value of the upcard, 11 for an ace
+pub fn hard_action(total: u8, dealer: u8, rules: &TableRules) -> ChartAction {
+    let few_decks = rules.decks <= 2;
+    let h17 = rules.dealer_hits_soft_17;
+    if rules.surrender {
+        let surrender = match total {
+            15 => (dealer == 10 && rules.decks > 1) || (h17 && dealer == 11),
+            16 => dealer >= 10 || (dealer == 9 && !few_decks),
+            17 => h17 && dealer == 11,
+            _ => false,
+        };
+        if surrender && total == 17 {
+            return ChartAction::SurrenderOrStand;
+        } else if surrender {
+            return ChartAction::SurrenderOrHit;
+        }
+    }
+    match total {
+        8 if rules.decks == 1 && (5..=6).contains(&dealer) => ChartAction::DoubleOrHit,
+        9 if (3..=6).contains(&dealer) || (few_decks && dealer == 2) => ChartAction::DoubleOrHit,
+        10 if dealer <= 9 => ChartAction::DoubleOrHit,
+        11 if dealer <= 10 || h17 || few_decks => ChartAction::DoubleOrHit,
+        12 if (4..=6).contains(&dealer) => ChartAction::Stand,
+        13..=16 if dealer <= 6 => ChartAction::Stand,
+        17.. => ChartAction::Stand,
+        _ => ChartAction::Hit,
+    }
+}
+
+// total counts the ace as 11, e.g. 18 for A7
+pub fn soft_action(total: u8, dealer: u8, rules: &TableRules) -> ChartAction {
+    let few_decks = rules.decks <= 2;
+    let h17 = rules.dealer_hits_soft_17;
+    match total {
+        13 | 14 if (5..=6).contains(&dealer) => ChartAction::DoubleOrHit,
+        15 | 16 if (4..=6).contains(&dealer) => ChartAction::DoubleOrHit,
+        17 if (3..=6).contains(&dealer) || (few_decks && dealer == 2) => ChartAction::DoubleOrHit,
+        18 if (3..=6).contains(&dealer) || (h17 && dealer == 2) => ChartAction::DoubleOrStand,
+        18 if dealer <= 8 || (rules.decks == 1 && !h17 && dealer == 11) => ChartAction::Stand,
+        19 if dealer == 6 && (h17 || rules.decks == 1) => ChartAction::DoubleOrStand,
+        19.. => ChartAction::Stand,
+        _ => ChartAction::Hit,
+    }
+}
+
+// pairs that are not split are played on their total
+pub fn pair_action(card_value: u8, dealer: u8, rules: &TableRules) -> ChartAction {
+    let das = rules.double_after_split;
+    if card_value == 8
+        && dealer == 11
+        && rules.surrender
+        && rules.dealer_hits_soft_17
+        && rules.decks > 2
+    {
+        return ChartAction::SurrenderOrSplit;
+    }
+    let split = match card_value {
+        2 | 3 => (4..=7).contains(&dealer) || (das && dealer <= 3),
+        4 => das && (5..=6).contains(&dealer),
+        6 => (3..=6).contains(&dealer) || (das && dealer == 2),
+        7 => dealer <= 7 || (rules.decks == 1 && das && dealer == 8),
+        8 | 11 => true,
+        9 => dealer <= 9 && dealer != 7,
+        _ => false,
+    };
+    if split {
+        ChartAction::Split
+    } else if card_value == 11 {
+        soft_action(12, dealer, rules)
+    } else {
+        hard_action(card_value * 2, dealer, rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        card::{CardValue, Suit},
+        deck::Deck,
+    };
+
+    fn hand(values: &[u8]) -> Vec<Card> {
+        values.iter().map(|&value| card(value)).collect()
+    }
+
+    fn card(value: u8) -> Card {
+        let value = match value {
+            11 => CardValue::Ace,
+            value => CardValue::Number(value),
+        };
+        Card::new(value, Suit::Hearts)
+    }
+
+    fn recommend(values: &[u8], dealer: u8, rules: &TableRules) -> PlayerAction {
+        BasicStrategy::recommend(&hand(values), &card(dealer), rules)
+    }
+
+    #[test]
+    fn hard_hands() {
+        let rules = TableRules::default();
+        assert_eq!(recommend(&[5, 3], 6, &rules), PlayerAction::Hit);
+        assert_eq!(recommend(&[6, 5], 10, &rules), PlayerAction::Double);
+        assert_eq!(recommend(&[6, 5], 11, &rules), PlayerAction::Hit);
+        assert_eq!(recommend(&[10, 2], 3, &rules), PlayerAction::Hit);
+        assert_eq!(recommend(&[10, 2], 4, &rules), PlayerAction::Stand);
+        assert_eq!(recommend(&[10, 6], 6, &rules), PlayerAction::Stand);
+        assert_eq!(recommend(&[10, 6], 7, &rules), PlayerAction::Hit);
+        assert_eq!(recommend(&[10, 7], 11, &rules), PlayerAction::Stand);
+    }
+
+    #[test]
+    fn soft_hands() {
+        let rules = TableRules::default();
+        assert_eq!(recommend(&[11, 2], 5, &rules), PlayerAction::Double);
+        assert_eq!(recommend(&[11, 2], 4, &rules), PlayerAction::Hit);
+        assert_eq!(recommend(&[11, 7], 2, &rules), PlayerAction::Stand);
+        assert_eq!(recommend(&[11, 7], 4, &rules), PlayerAction::Double);
+        assert_eq!(recommend(&[11, 7], 9, &rules), PlayerAction::Hit);
+        assert_eq!(recommend(&[11, 8], 6, &rules), PlayerAction::Stand);
+        // doubling is only possible on two cards
+        assert_eq!(recommend(&[11, 3, 4], 4, &rules), PlayerAction::Stand);
+        assert_eq!(recommend(&[11, 2, 2], 5, &rules), PlayerAction::Hit);
+    }
+
+    #[test]
+    fn pairs() {
+        let rules = TableRules::default();
+        assert_eq!(recommend(&[11, 11], 11, &rules), PlayerAction::Split);
+        assert_eq!(recommend(&[8, 8], 10, &rules), PlayerAction::Split);
+        assert_eq!(recommend(&[10, 10], 6, &rules), PlayerAction::Stand);
+        assert_eq!(recommend(&[5, 5], 9, &rules), PlayerAction::Double);
+        assert_eq!(recommend(&[9, 9], 7, &rules), PlayerAction::Stand);
+        assert_eq!(recommend(&[2, 2], 2, &rules), PlayerAction::Split);
+        assert_eq!(recommend(&[4, 4], 5, &rules), PlayerAction::Split);
+    }
+
+    #[test]
+    fn rules_change_the_recommendation() {
+        let h17 = TableRules {
+            dealer_hits_soft_17: true,
+            ..Default::default()
+        };
+        assert_eq!(recommend(&[6, 5], 11, &h17), PlayerAction::Double);
+        assert_eq!(recommend(&[11, 7], 2, &h17), PlayerAction::Double);
+        assert_eq!(recommend(&[10, 7], 11, &h17), PlayerAction::Surrender);
+        assert_eq!(recommend(&[8, 8], 11, &h17), PlayerAction::Surrender);
+
+        let no_das = TableRules {
+            double_after_split: false,
+            ..Default::default()
+        };
+        assert_eq!(recommend(&[2, 2], 2, &no_das), PlayerAction::Hit);
+        assert_eq!(recommend(&[4, 4], 5, &no_das), PlayerAction::Hit);
+
+        let single_deck = TableRules::preset("single-deck").unwrap();
+        assert_eq!(recommend(&[5, 3], 6, &single_deck), PlayerAction::Double);
+        assert_eq!(recommend(&[6, 3], 2, &single_deck), PlayerAction::Double);
+    }
+
+    #[test]
+    fn surrender_falls_back_when_not_allowed() {
+        let rules = TableRules::default();
+        assert_eq!(recommend(&[10, 6], 10, &rules), PlayerAction::Surrender);
+        assert_eq!(recommend(&[10, 5], 10, &rules), PlayerAction::Surrender);
+        assert_eq!(recommend(&[10, 3, 3], 10, &rules), PlayerAction::Hit);
+
+        let no_surrender = TableRules {
+            surrender: false,
+            ..Default::default()
+        };
+        assert_eq!(recommend(&[10, 6], 10, &no_surrender), PlayerAction::Hit);
+    }
+
+    #[test]
+    fn pair_is_played_on_its_total_when_it_cannot_be_split() {
+        let legal = [PlayerAction::Hit, PlayerAction::Stand, PlayerAction::Double];
+        let action =
+            BasicStrategy::recommend_from(&hand(&[8, 8]), &card(6), &TableRules::default(), &legal);
+        assert_eq!(action, PlayerAction::Stand);
+    }
+
+    #[test]
+    fn hand_of_the_game_is_advised() {
+        let mut game = Game::with_seed(1, TableRules::default(), 5);
+        game.new_round();
+        game.deck = Deck::from_vec([7, 4, 5, 10, 2].into_iter().rev().map(card).collect());
+        game.place_bet(0, 10);
+        game.deal_initial_cards();
+        let hand = game.current_hand().unwrap();
+        assert_eq!(
+            BasicStrategy::recommend_hand(&game, hand),
+            PlayerAction::Double
+        );
+        game.apply_action(hand, PlayerAction::Hit);
+        assert_eq!(
+            BasicStrategy::recommend_hand(&game, hand),
+            PlayerAction::Stand
+        );
+    }
+}
@@ -0,0 +1,446 @@
+//! Charts drawn as standalone SVG documents: lines, a fan of percentiles and bars.
+//!
+//! Every chart is sized by `WIDTH` and `HEIGHT` and scaled to its data, the axes getting
+//! round ticks. Nothing is fetched from outside, fonts are the generic sans-serif.
+
+use std::fmt::Write;
+
+pub const WIDTH: f64 = 800.0;
+pub const HEIGHT: f64 = 450.0;
+
+// room around the plot for the title, the ticks and the labels
+const LEFT: f64 = 80.0;
+const RIGHT: f64 = 20.0;
+const TOP: f64 = 50.0;
+const BOTTOM: f64 = 60.0;
+
+// colours of the series in turn
+const PALETTE: [&str; 7] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2",
+];
+const GAIN: &str = "#2ca02c";
+const LOSS: &str = "#d62728";
+
+// a named line through its points
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+// a value with the half width of its confidence interval
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bar {
+    pub label: String,
+    pub value: f64,
+    pub error: f64,
+}
+
+pub fn line_chart(title: &str, x_label: &str, y_label: &str, series: &[Series]) -> String {
+    let points = series
+        .iter()
+        .flat_map(|series| series.points.iter().copied());
+    let mut chart = Chart::new(title, x_label, y_label, Bounds::of(points));
+    for (i, series) in series.iter().enumerate() {
+        let colour = PALETTE[i % PALETTE.len()];
+        chart.polyline(&series.points, colour);
+        chart.legend(i, &series.name, colour);
+    }
+    chart.finish()
+}
+
+// bands between the percentiles taken in pairs from the outside, the lowest with the
+// highest, and a line for the middle one when their number is odd. percentiles are given
+// as (percent, value at every x) in increasing order.
+pub fn fan_chart(
+    title: &str,
+    x_label: &str,
+    y_label: &str,
+    xs: &[f64],
+    percentiles: &[(f64, Vec<f64>)],
+) -> String {
+    let points = percentiles
+        .iter()
+        .flat_map(|(_, values)| xs.iter().copied().zip(values.iter().copied()));
+    let mut chart = Chart::new(title, x_label, y_label, Bounds::of(points));
+    let bands = percentiles.len() / 2;
+    for band in 0..bands {
+        let (low, low_values) = &percentiles[band];
+        let (high, high_values) = &percentiles[percentiles.len() - 1 - band];
+        let mut outline: Vec<(f64, f64)> = xs.iter().copied().zip(low_values.clone()).collect();
+        outline.extend(xs.iter().copied().zip(high_values.clone()).rev());
+        let opacity = 0.2 + 0.3 * band as f64 / bands.max(1) as f64;
+        chart.polygon(&outline, PALETTE[0], opacity);
+        chart.legend_area(band, &format!("{low}% to {high}%"), PALETTE[0], opacity);
+    }
+    if percentiles.len() % 2 == 1 {
+        let (middle, values) = &percentiles[bands];
+        let line: Vec<(f64, f64)> = xs.iter().copied().zip(values.clone()).collect();
+        chart.polyline(&line, PALETTE[0]);
+        chart.legend(bands, &format!("{middle}%"), PALETTE[0]);
+    }
+    chart.finish()
+}
+
+// bars from zero, green above and red below, with their confidence interval
+pub fn bar_chart(title: &str, x_label: &str, y_label: &str, bars: &[Bar]) -> String {
+    let values = bars.iter().flat_map(|bar| {
+        let error = if bar.error.is_finite() {
+            bar.error
+        } else {
+            0.0
+        };
+        [
+            (0.0, bar.value - error),
+            (0.0, bar.value + error),
+            (0.0, 0.0),
+        ]
+    });
+    let mut bounds = Bounds::of(values);
+    bounds.x = (0.0, bars.len().max(1) as f64);
+    let mut chart = Chart::new(title, x_label, y_label, bounds);
+    chart.x_ticks = false;
+    let slot = chart.plot_width() / bars.len().max(1) as f64;
+    for (i, bar) in bars.iter().enumerate() {
+        let x = LEFT + slot * i as f64;
+        let (top, bottom) = (chart.y(bar.value.max(0.0)), chart.y(bar.value.min(0.0)));
+        let colour = if bar.value >= 0.0 { GAIN } else { LOSS };
+        let _ = writeln!(
+            chart.body,
+            r#"<rect x="{:.1}" y="{top:.1}" width="{:.1}" height="{:.1}" fill="{colour}"/>"#,
+            x + slot * 0.15,
+            slot * 0.7,
+            (bottom - top).max(0.5)
+        );
+        if bar.error.is_finite() && bar.error > 0.0 {
+            let middle = x + slot / 2.0;
+            let (high, low) = (
+                chart.y(bar.value + bar.error),
+                chart.y(bar.value - bar.error),
+            );
+            let _ = writeln!(
+                chart.body,
+                r#"<path d="M{:.1},{high:.1}H{:.1}M{middle:.1},{high:.1}V{low:.1}M{:.1},{low:.1}H{:.1}" stroke="black" fill="none"/>"#,
+                middle - slot * 0.15,
+                middle + slot * 0.15,
+                middle - slot * 0.15,
+                middle + slot * 0.15
+            );
+        }
+        let _ = writeln!(
+            chart.body,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+            x + slot / 2.0,
+            HEIGHT - BOTTOM + 18.0,
+            escape(&bar.label)
+        );
+    }
+    chart.finish()
+}
+
+// smallest and largest values on both axes
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bounds {
+    x: (f64, f64),
+    y: (f64, f64),
+}
+
+impl Bounds {
+    // one unit around the values when they are all the same, the unit square without any
+    fn of<I: IntoIterator<Item = (f64, f64)>>(points: I) -> Self {
+        let mut bounds = Bounds {
+            x: (f64::INFINITY, f64::NEG_INFINITY),
+            y: (f64::INFINITY, f64::NEG_INFINITY),
+        };
+        for (x, y) in points {
+            if x.is_finite() {
+                bounds.x = (bounds.x.0.min(x), bounds.x.1.max(x));
+            }
+            if y.is_finite() {
+                bounds.y = (bounds.y.0.min(y), bounds.y.1.max(y));
+            }
+        }
+        let widen = |(low, high): (f64, f64)| {
+            if low > high {
+                (0.0, 1.0)
+            } else if low == high {
+                (low - 1.0, high + 1.0)
+            } else {
+                (low, high)
+            }
+        };
+        Bounds {
+            x: widen(bounds.x),
+            y: widen(bounds.y),
+        }
+    }
+}
+
+// round steps of 1, 2 or 5 times a power of ten, four to eight of them over the range
+fn ticks((low, high): (f64, f64)) -> Vec<f64> {
+    let rough = (high - low) / 8.0;
+    let magnitude = 10f64.powf(rough.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= rough)
+        .unwrap();
+    let first = (low / step).ceil() as i64;
+    let last = (high / step).floor() as i64;
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+fn tick_label(value: f64, step: f64) -> String {
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    // no negative zero
+    format!("{:.*}", decimals, value + 0.0)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// an SVG document being drawn over the axes of its bounds
+struct Chart {
+    bounds: Bounds,
+    title: String,
+    x_label: String,
+    y_label: String,
+    x_ticks: bool,
+    body: String,
+    legend: String,
+}
+
+impl Chart {
+    fn new(title: &str, x_label: &str, y_label: &str, bounds: Bounds) -> Self {
+        Chart {
+            bounds,
+            title: title.to_string(),
+            x_label: x_label.to_string(),
+            y_label: y_label.to_string(),
+            x_ticks: true,
+            body: String::new(),
+            legend: String::new(),
+        }
+    }
+
+    fn plot_width(&self) -> f64 {
+        WIDTH - LEFT - RIGHT
+    }
+
+    fn plot_height(&self) -> f64 {
+        HEIGHT - TOP - BOTTOM
+    }
+
+    fn x(&self, x: f64) -> f64 {
+        let (low, high) = self.bounds.x;
+        LEFT + (x - low) / (high - low) * self.plot_width()
+    }
+
+    fn y(&self, y: f64) -> f64 {
+        let (low, high) = self.bounds.y;
+        TOP + (high - y) / (high - low) * self.plot_height()
+    }
+
+    fn path(&self, points: &[(f64, f64)]) -> String {
+        let mut path = String::new();
+        for (i, (x, y)) in points.iter().enumerate() {
+            let command = if i == 0 { 'M' } else { 'L' };
+            let _ = write!(path, "{command}{:.1},{:.1}", self.x(*x), self.y(*y));
+        }
+        path
+    }
+
+    fn polyline(&mut self, points: &[(f64, f64)], colour: &str) {
+        let path = self.path(points);
+        let _ = writeln!(
+            self.body,
+            r#"<path d="{path}" stroke="{colour}" stroke-width="1.5" fill="none"/>"#
+        );
+    }
+
+    fn polygon(&mut self, points: &[(f64, f64)], colour: &str, opacity: f64) {
+        let path = self.path(points);
+        let _ = writeln!(
+            self.body,
+            r#"<path d="{path}Z" fill="{colour}" fill-opacity="{opacity:.2}" stroke="none"/>"#
+        );
+    }
+
+    fn legend(&mut self, row: usize, name: &str, colour: &str) {
+        let (x, y) = self.legend_position(row);
+        let _ = writeln!(
+            self.legend,
+            r#"<path d="M{x:.1},{y:.1}h20" stroke="{colour}" stroke-width="2"/><text x="{:.1}" y="{:.1}">{}</text>"#,
+            x + 25.0,
+            y + 4.0,
+            escape(name)
+        );
+    }
+
+    fn legend_area(&mut self, row: usize, name: &str, colour: &str, opacity: f64) {
+        let (x, y) = self.legend_position(row);
+        let _ = writeln!(
+            self.legend,
+            r#"<rect x="{x:.1}" y="{:.1}" width="20" height="10" fill="{colour}" fill-opacity="{opacity:.2}"/><text x="{:.1}" y="{:.1}">{}</text>"#,
+            y - 5.0,
+            x + 25.0,
+            y + 4.0,
+            escape(name)
+        );
+    }
+
+    fn legend_position(&self, row: usize) -> (f64, f64) {
+        (LEFT + 10.0, TOP + 15.0 + 18.0 * row as f64)
+    }
+
+    fn finish(self) -> String {
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}" font-family="sans-serif" font-size="12">"#
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect width="{WIDTH}" height="{HEIGHT}" fill="white"/>"#
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="28" text-anchor="middle" font-size="16">{}</text>"#,
+            WIDTH / 2.0,
+            escape(&self.title)
+        );
+
+        let y_ticks = ticks(self.bounds.y);
+        let y_step = y_ticks
+            .get(1)
+            .zip(y_ticks.first())
+            .map_or(1.0, |(b, a)| b - a);
+        for tick in &y_ticks {
+            let y = self.y(*tick);
+            let _ = writeln!(
+                svg,
+                r##"<path d="M{LEFT:.1},{y:.1}H{:.1}" stroke="#dddddd"/><text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"##,
+                WIDTH - RIGHT,
+                LEFT - 6.0,
+                y + 4.0,
+                tick_label(*tick, y_step)
+            );
+        }
+        if self.x_ticks {
+            let x_ticks = ticks(self.bounds.x);
+            let x_step = x_ticks
+                .get(1)
+                .zip(x_ticks.first())
+                .map_or(1.0, |(b, a)| b - a);
+            for tick in &x_ticks {
+                let x = self.x(*tick);
+                let _ = writeln!(
+                    svg,
+                    r##"<path d="M{x:.1},{TOP:.1}V{:.1}" stroke="#eeeeee"/><text x="{x:.1}" y="{:.1}" text-anchor="middle">{}</text>"##,
+                    HEIGHT - BOTTOM,
+                    HEIGHT - BOTTOM + 18.0,
+                    tick_label(*tick, x_step)
+                );
+            }
+        }
+        // the zero line when it is in sight
+        if self.bounds.y.0 < 0.0 && self.bounds.y.1 > 0.0 {
+            let _ = writeln!(
+                svg,
+                r##"<path d="M{LEFT:.1},{:.1}H{:.1}" stroke="#888888"/>"##,
+                self.y(0.0),
+                WIDTH - RIGHT
+            );
+        }
+        svg.push_str(&self.body);
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{LEFT}" y="{TOP}" width="{:.1}" height="{:.1}" fill="none" stroke="black"/>"#,
+            self.plot_width(),
+            self.plot_height()
+        );
+        svg.push_str(&self.legend);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+            LEFT + self.plot_width() / 2.0,
+            HEIGHT - 15.0,
+            escape(&self.x_label)
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="20" y="{:.1}" text-anchor="middle" transform="rotate(-90 20 {:.1})">{}</text>"#,
+            TOP + self.plot_height() / 2.0,
+            TOP + self.plot_height() / 2.0,
+            escape(&self.y_label)
+        );
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_are_round() {
+        assert_eq!(
+            ticks((0.0, 1000.0)),
+            vec![0.0, 200.0, 400.0, 600.0, 800.0, 1000.0]
+        );
+        assert_eq!(ticks((-3.0, 7.5)), vec![-2.0, 0.0, 2.0, 4.0, 6.0]);
+        assert_eq!(tick_label(0.5, 0.1), "0.5");
+        assert_eq!(tick_label(-0.0, 1.0), "0");
+        assert_eq!(tick_label(2000.0, 500.0), "2000");
+    }
+
+    #[test]
+    fn charts_are_standalone_svg() {
+        let series = [
+            Series {
+                name: "seat 1".to_string(),
+                points: vec![(0.0, 1000.0), (1.0, 1010.0), (2.0, 990.0)],
+            },
+            Series {
+                name: "seat <2>".to_string(),
+                points: vec![(0.0, 1000.0), (1.0, 985.0), (2.0, 1005.0)],
+            },
+        ];
+        let lines = line_chart("Bankroll", "round", "chips", &series);
+        assert!(lines.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(lines.ends_with("</svg>\n"));
+        assert_eq!(lines.matches("stroke-width=\"1.5\"").count(), 2);
+        assert!(lines.contains("seat &lt;2&gt;"));
+
+        let xs = [0.0, 10.0, 20.0];
+        let percentiles: Vec<(f64, Vec<f64>)> = [5.0, 25.0, 50.0, 75.0, 95.0]
+            .into_iter()
+            .map(|p| (p, xs.iter().map(|x| x * p / 50.0).collect()))
+            .collect();
+        let fan = fan_chart("Sessions", "hand", "chips", &xs, &percentiles);
+        assert_eq!(fan.matches("Z\" fill=").count(), 2);
+        assert!(fan.contains(">5% to 95%<") && fan.contains(">50%<"));
+
+        let bars = [
+            Bar {
+                label: "-1".to_string(),
+                value: -0.01,
+                error: 0.005,
+            },
+            Bar {
+                label: "+3".to_string(),
+                value: 0.02,
+                error: f64::INFINITY,
+            },
+        ];
+        let chart = bar_chart("EV", "true count", "edge", &bars);
+        assert!(chart.contains(&format!("fill=\"{LOSS}\"")));
+        assert!(chart.contains(&format!("fill=\"{GAIN}\"")));
+        assert_eq!(chart.matches("stroke=\"black\" fill=\"none\"").count(), 1);
+    }
+}